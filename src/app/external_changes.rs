@@ -0,0 +1,244 @@
+use std::cell::RefCell;
+use std::path::{Path, PathBuf};
+
+use objc2::msg_send;
+use objc2::runtime::{AnyClass, AnyObject};
+use objc2::DefinedClass;
+use objc2_foundation::{NSPoint, NSRect, NSSize};
+
+use mdit::editor::file_watcher::FileWatcher;
+use mdit::editor::merge::{merge3, MARKER_MINE};
+use mdit::editor::tab_manager::TabManager;
+use mdit::editor::text_view::{replace_and_select, select_and_reveal, text_and_selection};
+use mdit::ui::conflict_banner;
+
+use super::AppDelegate;
+
+/// How often queued file events are handled.
+const POLL_INTERVAL: f64 = 0.5;
+
+/// Watches the files of open documents and remembers which of them changed
+/// on disk while they had unsaved edits.
+#[derive(Default)]
+pub(crate) struct ExternalChangeCoordinator {
+    watcher: RefCell<Option<FileWatcher>>,
+    /// Files whose document shows the conflict banner.
+    conflicts: RefCell<Vec<PathBuf>>,
+}
+
+impl ExternalChangeCoordinator {
+    pub(super) fn start(&self) {
+        match FileWatcher::new() {
+            Ok(watcher) => *self.watcher.borrow_mut() = Some(watcher),
+            Err(e) => eprintln!("mdit: cannot watch files for changes: {}", e),
+        }
+    }
+
+    /// Bring the watch list in line with `tm`'s documents and return the
+    /// files changed since the last call.
+    pub(super) fn changed(&self, tm: &TabManager) -> Vec<PathBuf> {
+        let paths: Vec<PathBuf> = tm.iter().filter_map(|t| t.url.borrow().clone()).collect();
+        self.conflicts.borrow_mut().retain(|p| paths.contains(p));
+        let mut watcher = self.watcher.borrow_mut();
+        let Some(watcher) = watcher.as_mut() else { return Vec::new() };
+        watcher.set_files(paths.iter().map(PathBuf::as_path));
+        watcher.changed()
+    }
+
+    pub(super) fn has_conflict(&self, path: &Path) -> bool {
+        self.conflicts.borrow().iter().any(|p| p == path)
+    }
+
+    pub(super) fn add_conflict(&self, path: &Path) {
+        if !self.has_conflict(path) {
+            self.conflicts.borrow_mut().push(path.to_path_buf());
+        }
+    }
+
+    pub(super) fn resolve(&self, path: &Path) {
+        self.conflicts.borrow_mut().retain(|p| p != path);
+    }
+}
+
+impl AppDelegate {
+    /// Start watching open files; `checkExternalChanges:` handles what arrives.
+    pub(super) fn start_watching_files(&self) {
+        self.ivars().external.start();
+        let target: &AnyObject = unsafe { &*(self as *const AppDelegate as *const AnyObject) };
+        let cls = AnyClass::get(c"NSTimer").expect("NSTimer class not found");
+        let _: *mut AnyObject = unsafe {
+            msg_send![cls,
+                scheduledTimerWithTimeInterval: POLL_INTERVAL,
+                target: target,
+                selector: objc2::sel!(checkExternalChanges:),
+                userInfo: std::ptr::null::<AnyObject>(),
+                repeats: true]
+        };
+    }
+
+    /// Reload documents whose file changed on disk, or flag a conflict
+    /// when they have unsaved edits.
+    pub(super) fn check_external_changes(&self) {
+        let changed = self.ivars().external.changed(&self.ivars().tab_manager.borrow());
+        for path in changed {
+            // Unreadable: deleted, or caught mid-write (another event follows).
+            let Ok(disk) = std::fs::read_to_string(&path) else { continue };
+            let state = {
+                let tm = self.ivars().tab_manager.borrow();
+                tm.find_by_path(&path).and_then(|i| {
+                    let tab = tm.get(i)?;
+                    let buffer = unsafe { tab.text_view.textStorage() }?.string().to_string();
+                    Some((i, tab.is_dirty.get(), *tab.saved_text.borrow() == disk, buffer == disk))
+                })
+            };
+            let Some((index, dirty, same_as_saved, same_as_buffer)) = state else { continue };
+            if same_as_saved {
+                // Our own save, or a touch.
+                continue;
+            }
+            if !dirty || same_as_buffer {
+                self.reload_tab(index, &disk);
+            } else {
+                self.ivars().external.add_conflict(&path);
+                self.update_conflict_banner();
+            }
+        }
+    }
+
+    /// Replace tab `index`'s text with `text` from disk, keeping the caret
+    /// and scroll position as far as the new text allows. The reload is a
+    /// single undoable edit, so Undo brings the previous text back instead
+    /// of replaying edits against text they no longer match.
+    fn reload_tab(&self, index: usize, text: &str) {
+        let views = {
+            let tm = self.ivars().tab_manager.borrow();
+            tm.get(index).map(|tab| (tab.text_view.clone(), tab.scroll_view.clone()))
+        };
+        let Some((tv, scroll_view)) = views else { return };
+        if let Some((old, (caret, _))) = text_and_selection(&tv) {
+            let visible: NSRect = unsafe { msg_send![&*scroll_view, documentVisibleRect] };
+            let mut caret = caret.min(text.len());
+            while !text.is_char_boundary(caret) {
+                caret -= 1;
+            }
+            // Viewer mode is read-only; lift that so the reload is undoable.
+            let editable = tv.isEditable();
+            tv.setEditable(true);
+            replace_and_select(&tv, &old, (0, old.len()), text, (caret, caret));
+            tv.setEditable(editable);
+            let _: () = unsafe { msg_send![&*tv, scrollPoint: visible.origin] };
+        }
+        let path = {
+            let tm = self.ivars().tab_manager.borrow();
+            let Some(tab) = tm.get(index) else { return };
+            tab.is_dirty.set(false);
+            *tab.saved_text.borrow_mut() = text.to_string();
+            self.ivars().autosave.discard(&tab.recovery_id);
+            tab.url.borrow().clone()
+        };
+        if let Some(path) = path {
+            self.ivars().external.resolve(&path);
+        }
+        if index == self.ivars().tab_manager.borrow().active_index() {
+            if let Some(pb) = self.ivars().path_bar.get() {
+                pb.update_wordcount(text);
+            }
+            self.refresh_task_progress();
+        }
+        self.rebuild_tab_bar();
+        self.update_conflict_banner();
+        self.update_welcome_visibility();
+    }
+
+    /// Show the conflict banner over the active document if its file
+    /// changed under unsaved edits; hide it otherwise.
+    pub(super) fn update_conflict_banner(&self) {
+        let Some(banner) = self.ivars().conflict_banner.get() else { return };
+        let path = self.active_path().filter(|p| self.ivars().external.has_conflict(p));
+        let Some(path) = path else {
+            banner.set_visible(false);
+            return;
+        };
+        let name = path
+            .file_name()
+            .map_or_else(|| path.display().to_string(), |n| n.to_string_lossy().into_owned());
+        banner.set_file_name(&name);
+        let frame = self.content_frame();
+        banner.set_frame(NSRect::new(
+            NSPoint::new(frame.origin.x, frame.origin.y + frame.size.height - conflict_banner::HEIGHT),
+            NSSize::new(frame.size.width, conflict_banner::HEIGHT),
+        ));
+        banner.set_visible(true);
+        // Keep the banner above the scroll view, which is re-added on every tab switch.
+        if let Some(win) = self.ivars().window.get() {
+            let content = win.contentView().unwrap();
+            banner.view().removeFromSuperview();
+            content.addSubview(banner.view());
+        }
+    }
+
+    /// Conflict banner ▸ Reload: drop the unsaved edits for the file on disk.
+    pub(super) fn conflict_reload(&self) {
+        let Some(path) = self.active_path() else { return };
+        let Ok(disk) = std::fs::read_to_string(&path) else { return };
+        let index = self.ivars().tab_manager.borrow().active_index();
+        self.reload_tab(index, &disk);
+    }
+
+    /// Conflict banner ▸ Keep Mine: keep the buffer. The disk version
+    /// becomes the base for any later merge, and saving overwrites it.
+    pub(super) fn conflict_keep_mine(&self) {
+        let Some(path) = self.active_path() else { return };
+        if let Ok(disk) = std::fs::read_to_string(&path) {
+            let tm = self.ivars().tab_manager.borrow();
+            if let Some(tab) = tm.active() {
+                *tab.saved_text.borrow_mut() = disk;
+            }
+        }
+        self.ivars().external.resolve(&path);
+        self.update_conflict_banner();
+    }
+
+    /// Conflict banner ▸ Merge: combine the unsaved edits with the changes
+    /// on disk, using the last saved text as the common base. Overlapping
+    /// edits are left between conflict markers, the first one selected.
+    /// The merge is a single undoable edit and the document stays unsaved.
+    pub(super) fn conflict_merge(&self) {
+        let Some(path) = self.active_path() else { return };
+        let Ok(disk) = std::fs::read_to_string(&path) else { return };
+        let (tv, base) = {
+            let tm = self.ivars().tab_manager.borrow();
+            let Some(tab) = tm.active() else { return };
+            (tab.text_view.clone(), tab.saved_text.borrow().clone())
+        };
+        let Some((mine, (caret, _))) = text_and_selection(&tv) else { return };
+        let merged = merge3(&base, &mine, &disk);
+        let mut caret = caret.min(merged.text.len());
+        while !merged.text.is_char_boundary(caret) {
+            caret -= 1;
+        }
+
+        // Viewer mode is read-only; lift that so the merge is undoable.
+        let editable = tv.isEditable();
+        tv.setEditable(true);
+        replace_and_select(&tv, &mine, (0, mine.len()), &merged.text, (caret, caret));
+        tv.setEditable(editable);
+        if let Some(start) = merged.text.find(MARKER_MINE) {
+            select_and_reveal(&tv, &merged.text, (start, start + MARKER_MINE.len()));
+        }
+
+        {
+            let tm = self.ivars().tab_manager.borrow();
+            if let Some(tab) = tm.active() {
+                *tab.saved_text.borrow_mut() = disk;
+            }
+        }
+        self.ivars().external.resolve(&path);
+        self.update_conflict_banner();
+    }
+
+    fn active_path(&self) -> Option<PathBuf> {
+        let tm = self.ivars().tab_manager.borrow();
+        tm.active().and_then(|t| t.url.borrow().clone())
+    }
+}
@@ -3,6 +3,7 @@ use std::path::PathBuf;
 use objc2::{DefinedClass, MainThreadOnly};
 use objc2_foundation::{NSRange, NSString};
 
+use super::helpers::{show_save_conflict_alert, SaveConflictChoice};
 use super::AppDelegate;
 
 impl AppDelegate {
@@ -31,16 +32,7 @@ impl AppDelegate {
         };
 
         // Reuse the active tab if it's a pristine empty tab, otherwise create a new one.
-        let reuse = {
-            let tm = self.ivars().tab_manager.borrow();
-            tm.active().is_some_and(|t| {
-                !t.is_dirty.get()
-                    && t.url.borrow().is_none()
-                    && unsafe { t.text_view.textStorage() }
-                        .is_none_or(|s| s.length() == 0)
-            })
-        };
-        if !reuse {
+        if !self.active_tab_is_pristine() {
             self.add_empty_tab();
         }
         {
@@ -48,6 +40,7 @@ impl AppDelegate {
             if let Some(t) = tm.active() {
                 *t.url.borrow_mut() = Some(path.clone());
                 t.is_dirty.set(false);
+                *t.saved_text.borrow_mut() = content.clone();
                 unsafe {
                     if let Some(storage) = t.text_view.textStorage() {
                         let full = NSRange { location: 0, length: storage.length() };
@@ -69,10 +62,21 @@ impl AppDelegate {
                 }
             }
         }
+        self.refresh_task_progress();
         self.rebuild_tab_bar();
         self.update_welcome_visibility();
     }
 
+    /// True when the active tab is an empty, untitled, unmodified document.
+    pub(super) fn active_tab_is_pristine(&self) -> bool {
+        let tm = self.ivars().tab_manager.borrow();
+        tm.active().is_some_and(|t| {
+            !t.is_dirty.get()
+                && t.url.borrow().is_none()
+                && unsafe { t.text_view.textStorage() }.is_none_or(|s| s.length() == 0)
+        })
+    }
+
     /// Save tab at `index`, or the active tab when `index` is `None`.
     ///
     /// If the tab has no associated path, an `NSSavePanel` is presented first.
@@ -95,6 +99,23 @@ impl AppDelegate {
             },
         };
 
+        // The file changed on disk under unsaved edits: don't silently clobber it.
+        if self.ivars().external.has_conflict(&path) {
+            let name = path
+                .file_name()
+                .map_or_else(|| path.display().to_string(), |n| n.to_string_lossy().into_owned());
+            match show_save_conflict_alert(&name, self.mtm()) {
+                SaveConflictChoice::Overwrite => {}
+                SaveConflictChoice::Merge => {
+                    // Merging leaves the document unsaved, for review.
+                    self.switch_to_tab(idx);
+                    self.conflict_merge();
+                    return;
+                }
+                SaveConflictChoice::Cancel => return,
+            }
+        }
+
         // Read content from TextStorage
         let content = {
             let tm = self.ivars().tab_manager.borrow();
@@ -119,8 +140,13 @@ impl AppDelegate {
             if let Some(t) = tm.get(idx) {
                 *t.url.borrow_mut() = Some(path.clone());
                 t.is_dirty.set(false);
+                *t.saved_text.borrow_mut() = content;
+                self.ivars().autosave.discard(&t.recovery_id);
             }
         }
+        // Saving settles any conflict with a change made on disk: this version wins.
+        self.ivars().external.resolve(&path);
+        self.update_conflict_banner();
         if let Some(pb) = self.ivars().path_bar.get() {
             if idx == self.ivars().tab_manager.borrow().active_index() {
                 pb.update(Some(path.as_path()));
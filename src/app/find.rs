@@ -2,12 +2,17 @@ use std::cell::{Cell, RefCell};
 
 use objc2::msg_send;
 use objc2_app_kit::{NSBackgroundColorAttributeName, NSColor, NSTextView};
-use objc2_foundation::{NSRange, NSString};
-
+use objc2_foundation::NSRange;
+
+use mdit::editor::renderer::compute_attribute_runs;
+use mdit::editor::search::{step_across_tabs, SearchMatch, SearchScope, Searcher, VisibleText};
+use mdit::editor::tab_manager::TabManager;
+use mdit::editor::text_storage::MditEditorDelegate;
+use mdit::editor::text_view::replace_and_select;
+use mdit::markdown::parser::{parse, MarkdownSpan};
 use mdit::editor::view_mode::ViewMode;
 use mdit::ui::find_bar::{FindBar, FIND_H_COMPACT, FIND_H_EXPANDED};
 
-use super::helpers::find_all_ranges;
 use super::PATH_H;
 
 pub(super) enum Direction {
@@ -15,11 +20,16 @@ pub(super) enum Direction {
     Previous,
 }
 
-/// Owns the find/replace state: match list, current index, bar height.
+/// Owns the find/replace state: match list, current index, bar height,
+/// and per-tab match counts when searching all tabs.
 pub(crate) struct FindCoordinator {
     matches: RefCell<Vec<NSRange>>,
     current: Cell<usize>,
     bar_height: Cell<f64>,
+    /// Match count of every tab (empty unless searching all tabs).
+    tab_counts: RefCell<Vec<usize>>,
+    /// Index of the tab `matches` belongs to when `tab_counts` is set.
+    active_tab: Cell<usize>,
 }
 
 impl Default for FindCoordinator {
@@ -28,6 +38,8 @@ impl Default for FindCoordinator {
             matches: RefCell::new(Vec::new()),
             current: Cell::new(0),
             bar_height: Cell::new(0.0),
+            tab_counts: RefCell::new(Vec::new()),
+            active_tab: Cell::new(0),
         }
     }
 }
@@ -52,6 +64,12 @@ impl FindCoordinator {
             Direction::Next => (current + 1) % count,
             Direction::Previous => if current == 0 { count - 1 } else { current - 1 },
         };
+        self.select(idx, fb, tv);
+    }
+
+    /// Make match `idx` of the active tab current: highlight, scroll, count.
+    pub(super) fn select(&self, idx: usize, fb: &FindBar, tv: &NSTextView) {
+        if idx >= self.matches.borrow().len() { return; }
         self.current.set(idx);
 
         // Get storage for highlighting
@@ -60,14 +78,92 @@ impl FindCoordinator {
             self.highlight_current_match(storage);
         }
         self.scroll_to_current_match(tv);
-        fb.update_count(idx + 1, count);
+        self.show_count(fb);
+    }
+
+    /// When searching all tabs, the tab and match index that Find Next /
+    /// Find Previous should go to; may be in another tab.
+    pub(super) fn step_across_tabs(&self, direction: Direction) -> Option<(usize, usize)> {
+        let counts = self.tab_counts.borrow();
+        let from = (self.active_tab.get(), self.current.get());
+        step_across_tabs(&counts, from, matches!(direction, Direction::Next))
+    }
+
+    /// Per-tab match counts for the tab bar (empty unless searching all tabs).
+    pub(super) fn tab_match_counts(&self) -> Vec<usize> {
+        self.tab_counts.borrow().clone()
+    }
+
+    /// Recount matches in every tab when the bar searches all tabs (clear
+    /// the counts otherwise), then refresh the count label and replace row.
+    /// Call after `perform_search` on the active tab. Returns whether the
+    /// counts changed, i.e. the tab bar needs rebuilding.
+    pub(super) fn update_tab_counts(&self, fb: &FindBar, tm: &TabManager) -> bool {
+        let counts = match Searcher::new(&fb.search_text(), fb.search_options()) {
+            Ok(searcher) if fb.is_all_tabs() && !fb.search_text().is_empty() => {
+                let active = tm.active_index();
+                self.active_tab.set(active);
+                tm.iter()
+                    .enumerate()
+                    .map(|(i, tab)| {
+                        if i == active {
+                            return self.matches.borrow().len();
+                        }
+                        let Some(storage) = (unsafe { tab.text_view.textStorage() }) else { return 0 };
+                        let text = storage.string().to_string();
+                        search_document(&searcher, &text, &tab.editor_delegate, tab.mode.get()).len()
+                    })
+                    .collect()
+            }
+            _ => Vec::new(),
+        };
+        let changed = *self.tab_counts.borrow() != counts;
+        *self.tab_counts.borrow_mut() = counts;
+        if !self.tab_counts.borrow().is_empty() {
+            self.show_count(fb);
+            let total: usize = self.tab_counts.borrow().iter().sum();
+            fb.set_no_match(total == 0);
+            let mode = tm.active().map(|t| t.mode.get()).unwrap_or(ViewMode::Viewer);
+            self.update_bar_height(fb, total, mode);
+        }
+        changed
+    }
+
+    /// Replace every match in every tab, one undoable edit per document,
+    /// marking changed tabs dirty. Matches are taken from the Markdown
+    /// source in every tab, whatever its mode. Returns the number of
+    /// documents changed.
+    pub(super) fn replace_all_tabs(&self, fb: &FindBar, tm: &TabManager) -> usize {
+        let query = fb.search_text();
+        if query.is_empty() { return 0; }
+        let Ok(searcher) = Searcher::new(&query, fb.search_options()) else { return 0 };
+        let template = fb.replace_text();
+        let mut changed = 0;
+        for tab in tm.iter() {
+            let Some(storage) = (unsafe { tab.text_view.textStorage() }) else { continue };
+            let text = storage.string().to_string();
+            let Some(edit) = searcher.replace_all_edit(&text, &scope_spans(&searcher, &text), &template) else {
+                continue;
+            };
+            // Viewer-mode tabs are read-only; lift that for this one edit so
+            // it still goes through the normal undo path.
+            let editable = tab.text_view.isEditable();
+            tab.text_view.setEditable(true);
+            replace_and_select(&tab.text_view, &text, edit.range, &edit.replacement, (edit.caret, edit.caret));
+            tab.text_view.setEditable(editable);
+            tab.is_dirty.set(true);
+            changed += 1;
+        }
+        changed
     }
 
     /// Run a search against the given text storage and update highlights + count.
+    /// In Viewer mode only the visible text is searched (see `VisibleText`).
     pub(super) fn perform_search(
         &self,
         fb: &FindBar,
         tv: &NSTextView,
+        editor_delegate: &MditEditorDelegate,
         tab_mode: ViewMode,
     ) {
         let storage = unsafe { tv.textStorage() };
@@ -86,6 +182,7 @@ impl FindCoordinator {
             }
         }
 
+        fb.set_error(None);
         if query.is_empty() {
             *self.matches.borrow_mut() = Vec::new();
             self.current.set(0);
@@ -95,9 +192,20 @@ impl FindCoordinator {
         }
 
         // Find all matches
-        let ns_query = NSString::from_str(&query);
-        let case_sensitive = fb.is_case_sensitive();
-        let matches = find_all_ranges(&storage.string(), &ns_query, !case_sensitive);
+        let found = match Searcher::new(&query, fb.search_options()) {
+            Ok(searcher) => {
+                let text = storage.string().to_string();
+                search_document(&searcher, &text, editor_delegate, tab_mode)
+            }
+            Err(message) => {
+                *self.matches.borrow_mut() = Vec::new();
+                self.current.set(0);
+                fb.set_error(Some(&message));
+                self.update_bar_height(fb, 0, tab_mode);
+                return;
+            }
+        };
+        let matches: Vec<NSRange> = found.iter().map(ns_range).collect();
         let count = matches.len();
 
         // Clamp current index
@@ -133,48 +241,44 @@ impl FindCoordinator {
         self.update_bar_height(fb, count, tab_mode);
     }
 
-    /// Replace the current match with the replacement text.
+    /// Replace the current match with the replacement text. In regex mode
+    /// the replacement may refer to capture groups (`$1`).
     pub(super) fn replace_one(
         &self,
         fb: &FindBar,
         tv: &NSTextView,
+        editor_delegate: &MditEditorDelegate,
         tab_mode: ViewMode,
     ) {
-        let replace_str = fb.replace_text();
-        let range = {
-            let matches = self.matches.borrow();
-            matches.get(self.current.get()).copied()
-        };
-        let Some(range) = range else { return };
-
-        let storage = unsafe { tv.textStorage() };
-        if let Some(storage) = storage {
-            let ns_replace = NSString::from_str(&replace_str);
-            storage.replaceCharactersInRange_withString(range, &ns_replace);
+        if let Some(storage) = unsafe { tv.textStorage() } {
+            let text = storage.string().to_string();
+            if let Some((range, replacement)) = replacements(fb, &text).into_iter().nth(self.current.get()) {
+                let caret = range.0 + replacement.len();
+                replace_and_select(tv, &text, range, &replacement, (caret, caret));
+            }
         }
-        self.perform_search(fb, tv, tab_mode);
+        self.perform_search(fb, tv, editor_delegate, tab_mode);
     }
 
-    /// Replace all matches with the replacement text.
+    /// Replace all matches with the replacement text, as one undoable edit.
     pub(super) fn replace_all(
         &self,
         fb: &FindBar,
         tv: &NSTextView,
+        editor_delegate: &MditEditorDelegate,
         tab_mode: ViewMode,
     ) {
-        let replace_str = fb.replace_text();
-        let ranges: Vec<NSRange> = self.matches.borrow().clone();
-        if ranges.is_empty() { return; }
-
-        let storage = unsafe { tv.textStorage() };
-        if let Some(storage) = storage {
-            let ns_replace = NSString::from_str(&replace_str);
-            // Replace in reverse order to preserve offsets
-            for range in ranges.iter().rev() {
-                storage.replaceCharactersInRange_withString(*range, &ns_replace);
+        if self.matches.borrow().is_empty() { return; }
+
+        let Ok(searcher) = Searcher::new(&fb.search_text(), fb.search_options()) else { return };
+        if let Some(storage) = unsafe { tv.textStorage() } {
+            let text = storage.string().to_string();
+            let spans = scope_spans(&searcher, &text);
+            if let Some(edit) = searcher.replace_all_edit(&text, &spans, &fb.replace_text()) {
+                replace_and_select(tv, &text, edit.range, &edit.replacement, (edit.caret, edit.caret));
             }
         }
-        self.perform_search(fb, tv, tab_mode);
+        self.perform_search(fb, tv, editor_delegate, tab_mode);
     }
 
     /// Open the find bar — set height, show, resize scroll view.
@@ -198,28 +302,12 @@ impl FindCoordinator {
     pub(super) fn close(&self, fb: &FindBar, tv: Option<&NSTextView>, editor_delegate: Option<&mdit::editor::text_storage::MditEditorDelegate>) {
         if !self.is_open() { return; }
 
-        // Remove all highlights
-        let matches: Vec<NSRange> = self.matches.borrow().clone();
-        if !matches.is_empty() {
-            if let Some(tv) = tv {
-                if let Some(storage) = unsafe { tv.textStorage() } {
-                    for &range in &matches {
-                        unsafe {
-                            storage.removeAttribute_range(
-                                NSBackgroundColorAttributeName,
-                                range,
-                            );
-                        }
-                    }
-                    // Re-render to restore legitimate highlight colors
-                    if let Some(ed) = editor_delegate {
-                        ed.reapply(&storage);
-                    }
-                }
-            }
+        if let Some(tv) = tv {
+            self.clear_highlights(tv, editor_delegate);
         }
 
         *self.matches.borrow_mut() = Vec::new();
+        self.tab_counts.borrow_mut().clear();
         self.current.set(0);
         self.bar_height.set(0.0);
         fb.hide();
@@ -228,6 +316,41 @@ impl FindCoordinator {
         fb.set_no_match(false);
     }
 
+    /// Remove match highlights from `tv` (e.g. before leaving its tab) and
+    /// forget its matches; the bar stays open.
+    pub(super) fn clear_highlights(&self, tv: &NSTextView, editor_delegate: Option<&MditEditorDelegate>) {
+        let matches: Vec<NSRange> = std::mem::take(&mut *self.matches.borrow_mut());
+        if matches.is_empty() { return; }
+        if let Some(storage) = unsafe { tv.textStorage() } {
+            for &range in &matches {
+                unsafe {
+                    storage.removeAttribute_range(
+                        NSBackgroundColorAttributeName,
+                        range,
+                    );
+                }
+            }
+            // Re-render to restore legitimate highlight colors
+            if let Some(ed) = editor_delegate {
+                ed.reapply(&storage);
+            }
+        }
+    }
+
+    /// Show the current match number and total — across all tabs when the
+    /// bar searches them.
+    fn show_count(&self, fb: &FindBar) {
+        let count = self.matches.borrow().len();
+        let counts = self.tab_counts.borrow();
+        let (before, total) = if counts.is_empty() {
+            (0, count)
+        } else {
+            (counts.iter().take(self.active_tab.get()).sum(), counts.iter().sum())
+        };
+        let current = if count > 0 { before + self.current.get() + 1 } else { 0 };
+        fb.update_count(current, total);
+    }
+
     /// Highlight the current match (update background colors).
     fn highlight_current_match(&self, storage: &objc2_app_kit::NSTextStorage) {
         let matches = self.matches.borrow().clone();
@@ -282,3 +405,45 @@ impl FindCoordinator {
         self.matches.borrow().len()
     }
 }
+
+/// Convert a match's UTF-16 range for the text view.
+fn ns_range(m: &SearchMatch) -> NSRange {
+    NSRange { location: m.utf16.0, length: m.utf16.1 }
+}
+
+/// The byte range of every match in `text` for the bar's query, paired with
+/// its expanded replacement. Empty when the query is empty or invalid.
+fn replacements(fb: &FindBar, text: &str) -> Vec<((usize, usize), String)> {
+    let query = fb.search_text();
+    if query.is_empty() { return Vec::new(); }
+    let Ok(searcher) = Searcher::new(&query, fb.search_options()) else { return Vec::new() };
+    let template = fb.replace_text();
+    searcher
+        .find_all(text, &scope_spans(&searcher, text))
+        .iter()
+        .map(|m| (m.range, searcher.replacement(text, m, &template)))
+        .collect()
+}
+
+/// Matches in one document: the visible text in Viewer mode, the source otherwise.
+fn search_document(
+    searcher: &Searcher,
+    text: &str,
+    editor_delegate: &MditEditorDelegate,
+    mode: ViewMode,
+) -> Vec<SearchMatch> {
+    if mode == ViewMode::Viewer {
+        let spans = editor_delegate.spans();
+        let runs = compute_attribute_runs(
+            text, &spans, editor_delegate.cursor_pos(), editor_delegate.base_size(),
+        ).runs;
+        searcher.find_visible(text, &spans, &VisibleText::new(text, &runs))
+    } else {
+        searcher.find_all(text, &scope_spans(searcher, text))
+    }
+}
+
+/// Spans for the searcher's scope; parsing is skipped when it searches everything.
+fn scope_spans(searcher: &Searcher, text: &str) -> Vec<MarkdownSpan> {
+    if searcher.options().scope == SearchScope::All { Vec::new() } else { parse(text) }
+}
@@ -0,0 +1,150 @@
+use std::cell::{Cell, RefCell};
+use std::path::{Path, PathBuf};
+
+use objc2::rc::Retained;
+use objc2::runtime::{AnyClass, AnyObject};
+use objc2::{msg_send, DefinedClass};
+
+use mdit::editor::folder_search::{hit_range, FolderSearch, FolderSearchEvent, LineHit};
+use mdit::editor::search::Searcher;
+use mdit::editor::text_view::select_and_reveal;
+use mdit::ui::folder_search_panel::FolderSearchPanel;
+
+use super::AppDelegate;
+
+/// How often the main thread drains results from the background walk.
+const POLL_INTERVAL: f64 = 0.05;
+
+/// Owns the Find in Folder state: the chosen folder, the running search,
+/// every hit shown so far, and the timer that polls for more.
+#[derive(Default)]
+pub(crate) struct FolderSearchCoordinator {
+    root: RefCell<Option<PathBuf>>,
+    search: RefCell<Option<FolderSearch>>,
+    /// Indexed by the number in each hit's `mdit-hit:` link.
+    hits: RefCell<Vec<(PathBuf, LineHit)>>,
+    files_with_hits: Cell<usize>,
+    timer: RefCell<Option<Retained<AnyObject>>>,
+}
+
+impl FolderSearchCoordinator {
+    pub(super) fn root(&self) -> Option<PathBuf> {
+        self.root.borrow().clone()
+    }
+
+    pub(super) fn set_root(&self, root: PathBuf, panel: &FolderSearchPanel) {
+        panel.set_folder(&root);
+        *self.root.borrow_mut() = Some(root);
+    }
+
+    /// Replace any running search with a new one over the chosen folder.
+    /// `target` receives `folderSearchPoll:` until the walk finishes.
+    pub(super) fn start(&self, searcher: Searcher, panel: &FolderSearchPanel, target: &AnyObject) {
+        let Some(root) = self.root() else { return };
+        self.stop_timer();
+        panel.clear_results();
+        self.hits.borrow_mut().clear();
+        self.files_with_hits.set(0);
+        *self.search.borrow_mut() = Some(FolderSearch::start(root, searcher));
+        panel.set_searching(true);
+        panel.set_status("Searching\u{2026}");
+
+        let cls = AnyClass::get(c"NSTimer").expect("NSTimer class not found");
+        let timer: *mut AnyObject = unsafe {
+            msg_send![cls,
+                scheduledTimerWithTimeInterval: POLL_INTERVAL,
+                target: target,
+                selector: objc2::sel!(folderSearchPoll:),
+                userInfo: std::ptr::null::<AnyObject>(),
+                repeats: true]
+        };
+        *self.timer.borrow_mut() = unsafe { Retained::retain(timer) };
+    }
+
+    /// Ask the running search to stop; its final event still arrives.
+    pub(super) fn cancel(&self) {
+        if let Some(search) = self.search.borrow().as_ref() {
+            search.cancel();
+        }
+    }
+
+    /// Show whatever the background walk has found since the last poll.
+    pub(super) fn poll(&self, panel: &FolderSearchPanel) {
+        let events = match self.search.borrow().as_ref() {
+            Some(search) => search.poll(),
+            None => return,
+        };
+        let root = self.root().unwrap_or_default();
+        for event in events {
+            match event {
+                FolderSearchEvent::File(result) => {
+                    let first = self.hits.borrow().len();
+                    panel.append_file(&root, &result, first);
+                    self.files_with_hits.set(self.files_with_hits.get() + 1);
+                    self.hits
+                        .borrow_mut()
+                        .extend(result.hits.into_iter().map(|hit| (result.path.clone(), hit)));
+                    panel.set_status(&format!("Searching\u{2026} {}", self.summary()));
+                }
+                FolderSearchEvent::Finished { files, cancelled } => {
+                    self.stop_timer();
+                    *self.search.borrow_mut() = None;
+                    panel.set_searching(false);
+                    let prefix = if cancelled { "Stopped \u{2014} " } else { "" };
+                    let noun = if files == 1 { "file" } else { "files" };
+                    panel.set_status(&format!("{prefix}{} ({files} {noun} searched)", self.summary()));
+                }
+                FolderSearchEvent::Failed => {
+                    self.stop_timer();
+                    *self.search.borrow_mut() = None;
+                    panel.set_searching(false);
+                    panel.set_status(&format!("Search failed \u{2014} {}", self.summary()));
+                }
+            }
+        }
+    }
+
+    /// The file and line of hit `index`.
+    pub(super) fn hit(&self, index: usize) -> Option<(PathBuf, LineHit)> {
+        self.hits.borrow().get(index).cloned()
+    }
+
+    /// "3 matches in 2 files".
+    fn summary(&self) -> String {
+        let (matches, files) = (self.hits.borrow().len(), self.files_with_hits.get());
+        format!(
+            "{matches} {} in {files} {}",
+            if matches == 1 { "match" } else { "matches" },
+            if files == 1 { "file" } else { "files" },
+        )
+    }
+
+    fn stop_timer(&self) {
+        if let Some(timer) = self.timer.borrow_mut().take() {
+            let _: () = unsafe { msg_send![&*timer, invalidate] };
+        }
+    }
+}
+
+impl AppDelegate {
+    /// Open the file of a Find in Folder hit and select the match.
+    pub(super) fn open_folder_hit(&self, path: &Path, hit: &LineHit) {
+        self.open_file_by_path(path.to_path_buf());
+        let tv = {
+            let tm = self.ivars().tab_manager.borrow();
+            let Some(tab) = tm.active() else { return };
+            if tab.url.borrow().as_deref() != Some(path) {
+                return;
+            }
+            tab.text_view.clone()
+        };
+        if let Some(storage) = unsafe { tv.textStorage() } {
+            let text = storage.string().to_string();
+            select_and_reveal(&tv, &text, hit_range(&text, hit));
+        }
+        if let Some(window) = self.ivars().window.get() {
+            window.makeKeyAndOrderFront(None);
+            let _: () = unsafe { msg_send![&**window, makeFirstResponder: &*tv] };
+        }
+    }
+}
@@ -4,13 +4,17 @@ use objc2::runtime::{AnyClass, AnyObject};
 use objc2::MainThreadOnly;
 use objc2_app_kit::{
     NSAppearanceNameAqua, NSAppearanceNameDarkAqua, NSApplication,
-    NSBackingStoreType, NSBezelStyle, NSButton, NSControl, NSImage,
-    NSTextView, NSView, NSWindow, NSWindowStyleMask,
+    NSBackingStoreType, NSBezelStyle, NSButton, NSControl, NSFont, NSImage, NSPasteboard,
+    NSPasteboardTypeString, NSScrollView, NSTextView, NSView, NSWindow, NSWindowStyleMask,
 };
 use objc2_foundation::{
     ns_string, MainThreadMarker, NSArray, NSPoint, NSRange, NSRect, NSSize, NSString,
 };
 
+use mdit::editor::formatting::TableCommand;
+use mdit::editor::text_view::{apply_text_edit, replace_and_select, text_and_selection};
+use mdit::markdown::parser::MarkdownSpan;
+
 // ---------------------------------------------------------------------------
 // Dirty-check dialog
 // ---------------------------------------------------------------------------
@@ -42,44 +46,190 @@ pub(super) fn show_save_alert(filename: &str, mtm: MainThreadMarker) -> SaveChoi
     }
 }
 
+// ---------------------------------------------------------------------------
+// Save-over-conflict prompt
+// ---------------------------------------------------------------------------
+
+pub(super) enum SaveConflictChoice {
+    Overwrite,
+    Merge,
+    Cancel,
+}
+
+/// Ask before saving over `filename`, which changed on disk while it had
+/// unsaved edits here.
+pub(super) fn show_save_conflict_alert(filename: &str, mtm: MainThreadMarker) -> SaveConflictChoice {
+    use objc2_app_kit::NSAlert;
+    let alert = NSAlert::new(mtm);
+    alert.setMessageText(&NSString::from_str(&format!(
+        "\"{}\" was changed by another application.",
+        filename
+    )));
+    alert.setInformativeText(&NSString::from_str(
+        "Saving will overwrite those changes. Merge combines them with your edits instead.",
+    ));
+    alert.addButtonWithTitle(&NSString::from_str("Overwrite")); // 1000
+    alert.addButtonWithTitle(&NSString::from_str("Merge")); // 1001
+    alert.addButtonWithTitle(&NSString::from_str("Cancel")); // 1002
+    match alert.runModal() {
+        1000 => SaveConflictChoice::Overwrite,
+        1001 => SaveConflictChoice::Merge,
+        _ => SaveConflictChoice::Cancel,
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Crash-recovery prompt
+// ---------------------------------------------------------------------------
+
+pub(super) enum RecoveryChoice {
+    Recover,
+    Discard,
+}
+
+/// Offer to reopen `count` autosaved documents. `details` lists each one
+/// and is shown in a scrolling, monospaced box below the message.
+pub(super) fn show_recovery_alert(count: usize, details: &str, mtm: MainThreadMarker) -> RecoveryChoice {
+    use objc2_app_kit::NSAlert;
+    let alert = NSAlert::new(mtm);
+    let noun = if count == 1 { "document" } else { "documents" };
+    alert.setMessageText(&NSString::from_str(&format!(
+        "mdit quit with {} unsaved {}. Recover {}?",
+        count,
+        noun,
+        if count == 1 { "it" } else { "them" },
+    )));
+    alert.setInformativeText(&NSString::from_str(
+        "Recovered documents reopen with their unsaved changes. Discarded changes can't be restored.",
+    ));
+
+    let frame = NSRect::new(NSPoint::new(0.0, 0.0), NSSize::new(480.0, 220.0));
+    let scroll = NSScrollView::initWithFrame(NSScrollView::alloc(mtm), frame);
+    scroll.setHasVerticalScroller(true);
+    scroll.setAutohidesScrollers(true);
+    let text_view = NSTextView::initWithFrame(
+        NSTextView::alloc(mtm),
+        NSRect::new(NSPoint::new(0.0, 0.0), scroll.contentSize()),
+    );
+    text_view.setEditable(false);
+    text_view.setSelectable(true);
+    text_view.setFont(Some(&NSFont::monospacedSystemFontOfSize_weight(11.0, 0.0)));
+    text_view.setString(&NSString::from_str(details));
+    scroll.setDocumentView(Some(&text_view));
+    alert.setAccessoryView(Some(&scroll));
+
+    alert.addButtonWithTitle(&NSString::from_str("Recover")); // 1000
+    alert.addButtonWithTitle(&NSString::from_str("Discard")); // 1001
+    match alert.runModal() {
+        1000 => RecoveryChoice::Recover,
+        _ => RecoveryChoice::Discard,
+    }
+}
+
 // ---------------------------------------------------------------------------
 // Formatting helpers
 // ---------------------------------------------------------------------------
 
-/// Toggle an inline marker around the current selection.
-pub(super) fn toggle_inline_wrap(tv: &NSTextView, marker: &str) {
-    let range: NSRange = unsafe { msg_send![tv, selectedRange] };
-    let Some(storage) = (unsafe { tv.textStorage() }) else {
+/// Toggle an inline marker around the selection, or the word under the caret.
+///
+/// `spans` is the document's parsed AST; enclosing spans are found
+/// structurally so toggling inside a long bold run splits it instead of
+/// nesting another pair of markers.
+pub(super) fn toggle_inline_wrap(tv: &NSTextView, spans: &[MarkdownSpan], marker: &str) {
+    let Some((text, selection)) = text_and_selection(tv) else {
         return;
     };
-    let full_str = storage.string();
-    let full_len = full_str.length();
-
-    let selected = full_str.substringWithRange(range).to_string();
-
-    // Grab a few characters on each side for marker detection.
-    const MAX_MARKERS: usize = 6;
-    let before_start = range.location.saturating_sub(MAX_MARKERS);
-    let after_end = (range.location + range.length + MAX_MARKERS).min(full_len);
-
-    let before = full_str
-        .substringWithRange(NSRange { location: before_start, length: range.location - before_start })
-        .to_string();
-    let after = full_str
-        .substringWithRange(NSRange {
-            location: range.location + range.length,
-            length: after_end - (range.location + range.length),
-        })
-        .to_string();
-
-    let result = mdit::editor::formatting::compute_inline_toggle(&selected, &before, &after, marker);
-
-    let replace_range = NSRange {
-        location: range.location - result.consumed_before,
-        length: result.consumed_before + range.length + result.consumed_after,
+    let edit = mdit::editor::formatting::inline_toggle_edit(&text, spans, selection, marker);
+    replace_and_select(tv, &text, edit.range, &edit.replacement, edit.selection);
+}
+
+/// Move the line, list item or heading section at the selection up or down.
+pub(super) fn move_block(tv: &NSTextView, spans: &[MarkdownSpan], up: bool) {
+    let Some((text, selection)) = text_and_selection(tv) else {
+        return;
     };
-    let ns = NSString::from_str(&result.replacement);
-    unsafe { msg_send![tv, insertText: &*ns, replacementRange: replace_range] }
+    if let Some(edit) = mdit::editor::block_move::move_block_edit(&text, spans, selection, up) {
+        replace_and_select(tv, &text, edit.range, &edit.replacement, edit.selection);
+    }
+}
+
+/// Promote or demote the heading at the caret together with its subheadings.
+pub(super) fn shift_section(tv: &NSTextView, spans: &[MarkdownSpan], promote: bool) {
+    let Some((text, (caret, _))) = text_and_selection(tv) else {
+        return;
+    };
+    if let Some(edit) = mdit::editor::formatting::shift_section_edit(&text, spans, caret, promote) {
+        apply_text_edit(tv, &text, &edit);
+    }
+}
+
+/// Insert a numbered footnote at the caret and jump to its new definition.
+pub(super) fn insert_footnote(tv: &NSTextView, spans: &[MarkdownSpan]) {
+    let Some((text, selection)) = text_and_selection(tv) else {
+        return;
+    };
+    let edit = mdit::editor::formatting::insert_footnote_edit(&text, spans, selection);
+    // Two small insertions, undone together as one step.
+    let undo: Option<Retained<AnyObject>> = unsafe { msg_send![tv, undoManager] };
+    if let Some(undo) = &undo {
+        let _: () = unsafe { msg_send![undo, beginUndoGrouping] };
+    }
+    let end = text.len();
+    replace_and_select(tv, &text, (end, end), &edit.definition, (end, end));
+    let text = format!("{text}{}", edit.definition);
+    let at = edit.reference_at;
+    replace_and_select(tv, &text, (at, at), &edit.reference, (edit.caret, edit.caret));
+    if let Some(undo) = &undo {
+        let _: () = unsafe { msg_send![undo, endUndoGrouping] };
+    }
+    let caret: NSRange = unsafe { msg_send![tv, selectedRange] };
+    let _: () = unsafe { msg_send![tv, scrollRangeToVisible: caret] };
+}
+
+/// Renumber numeric footnotes in order of first reference.
+pub(super) fn renumber_footnotes(tv: &NSTextView, spans: &[MarkdownSpan]) {
+    let Some((text, (caret, _))) = text_and_selection(tv) else {
+        return;
+    };
+    if let Some(edit) = mdit::editor::formatting::renumber_footnotes_edit(&text, spans, caret) {
+        apply_text_edit(tv, &text, &edit);
+    }
+}
+
+/// Toggle the task checkboxes on the selected lines, in either mode.
+pub(super) fn toggle_tasks(tv: &NSTextView, spans: &[MarkdownSpan]) {
+    let Some((text, selection)) = text_and_selection(tv) else {
+        return;
+    };
+    let Some(edit) = mdit::editor::tasks::toggle_tasks_edit(&text, spans, selection) else {
+        return;
+    };
+    // Viewer mode is read-only; lift that for this one edit so it still goes
+    // through the normal undo and change-notification path.
+    let editable = tv.isEditable();
+    tv.setEditable(true);
+    replace_and_select(tv, &text, edit.range, &edit.replacement, selection);
+    tv.setEditable(editable);
+}
+
+/// Move completed tasks to the bottom of their list.
+pub(super) fn sort_completed_tasks(tv: &NSTextView, spans: &[MarkdownSpan]) {
+    let Some((text, (caret, _))) = text_and_selection(tv) else {
+        return;
+    };
+    if let Some(edit) = mdit::editor::tasks::sort_completed_tasks_edit(&text, spans, caret) {
+        apply_text_edit(tv, &text, &edit);
+    }
+}
+
+/// Move completed tasks under a "Done" heading.
+pub(super) fn archive_completed_tasks(tv: &NSTextView, spans: &[MarkdownSpan]) {
+    let Some((text, (caret, _))) = text_and_selection(tv) else {
+        return;
+    };
+    if let Some(edit) = mdit::editor::tasks::archive_completed_tasks_edit(&text, spans, caret) {
+        apply_text_edit(tv, &text, &edit);
+    }
 }
 
 /// Replace the current NSTextView selection with `prefix + selected + suffix`.
@@ -94,20 +244,27 @@ pub(super) fn insert_link_wrap(tv: &NSTextView, prefix: &str, suffix: &str) {
     unsafe { msg_send![tv, insertText: &*ns, replacementRange: range] }
 }
 
-/// Apply a block-level format to the line containing the caret.
+/// Apply a block-level format to every line touched by the selection.
+///
+/// A multi-line selection stays selected (covering the reformatted lines)
+/// so the user can immediately apply another format.
 pub(super) fn apply_block_format(tv: &NSTextView, desired_prefix: &str) {
-    let caret: NSRange = unsafe { msg_send![tv, selectedRange] };
+    let selection: NSRange = unsafe { msg_send![tv, selectedRange] };
     let Some(storage) = (unsafe { tv.textStorage() }) else {
         return;
     };
     let ns_str = storage.string();
-    let point = NSRange { location: caret.location, length: 0 };
-    let line_range: NSRange = ns_str.lineRangeForRange(point);
-    let line_text = ns_str.substringWithRange(line_range).to_string();
+    let line_range: NSRange = ns_str.lineRangeForRange(selection);
+    let lines_text = ns_str.substringWithRange(line_range).to_string();
+
+    let new_lines = mdit::editor::formatting::set_block_format_lines(&lines_text, desired_prefix);
+    let ns = NSString::from_str(&new_lines);
+    let _: () = unsafe { msg_send![tv, insertText: &*ns, replacementRange: line_range] };
 
-    let new_line = mdit::editor::formatting::set_block_format(&line_text, desired_prefix);
-    let ns = NSString::from_str(&new_line);
-    unsafe { msg_send![tv, insertText: &*ns, replacementRange: line_range] }
+    if selection.length > 0 {
+        let reselect = NSRange { location: line_range.location, length: ns.length() };
+        let _: () = unsafe { msg_send![tv, setSelectedRange: reselect] };
+    }
 }
 
 /// Wrap the current selection in a fenced code block.
@@ -122,29 +279,85 @@ pub(super) fn insert_code_block(tv: &NSTextView) {
     unsafe { msg_send![tv, insertText: &*ns, replacementRange: range] }
 }
 
-// ---------------------------------------------------------------------------
-// Find‐all helper
-// ---------------------------------------------------------------------------
+/// Run a table editing command on the table containing the caret.
+///
+/// Does nothing when the caret is outside a table or the command does not
+/// apply (e.g. deleting the header row).
+pub(super) fn apply_table_command(tv: &NSTextView, command: TableCommand) {
+    let Some((text, (caret, _))) = text_and_selection(tv) else {
+        return;
+    };
+    if let Some(edit) = mdit::editor::formatting::table_command_edit(&text, caret, command) {
+        apply_text_edit(tv, &text, &edit);
+    }
+}
+
+/// Insert an empty table skeleton below the caret's line.
+pub(super) fn insert_table(tv: &NSTextView) {
+    let Some((text, (caret, _))) = text_and_selection(tv) else {
+        return;
+    };
+    let edit = mdit::editor::formatting::insert_table_edit(&text, caret);
+    apply_text_edit(tv, &text, &edit);
+}
+
+/// Copy the table under the selection to the clipboard as CSV or TSV.
+///
+/// Does nothing when the selection does not start inside a table.
+pub(super) fn copy_table_as_delimited(tv: &NSTextView, delimiter: char) {
+    let Some((text, (start, _))) = text_and_selection(tv) else {
+        return;
+    };
+    let Some(data) = mdit::editor::formatting::table_as_delimited(&text, start, delimiter) else {
+        return;
+    };
+    unsafe {
+        let pb = NSPasteboard::generalPasteboard();
+        pb.clearContents();
+        pb.setString_forType(&NSString::from_str(&data), NSPasteboardTypeString);
+    }
+}
 
-/// Find all occurrences of `query` in `text`, returning NSRange for each match.
-/// Uses NSString's rangeOfString:options:range: for proper Unicode + UTF-16 handling.
-pub(super) fn find_all_ranges(text: &NSString, query: &NSString, case_insensitive: bool) -> Vec<NSRange> {
-    let mut ranges = Vec::new();
-    let len = text.length();
-    if len == 0 || query.length() == 0 { return ranges; }
-    let options: usize = if case_insensitive { 1 } else { 0 }; // NSCaseInsensitiveSearch = 1
-    let mut search_from = NSRange { location: 0, length: len };
-    loop {
-        let found: NSRange = unsafe {
-            msg_send![text, rangeOfString: query, options: options, range: search_from]
-        };
-        if found.location >= usize::MAX / 2 { break; } // NSNotFound
-        ranges.push(found);
-        let next_loc = found.location + found.length.max(1);
-        if next_loc >= len { break; }
-        search_from = NSRange { location: next_loc, length: len - next_loc };
+/// Rewrite the whole document in canonical Markdown style.
+///
+/// The caret keeps its byte offset (clamped to the new text) since the
+/// formatter mostly changes whitespace and markers.
+pub(super) fn format_whole_document(tv: &NSTextView) {
+    use mdit::editor::formatting::{format_document, FormatOptions};
+    let Some((text, (caret, _))) = text_and_selection(tv) else {
+        return;
+    };
+    let formatted = format_document(&text, &FormatOptions::default());
+    if formatted == text {
+        return;
+    }
+    let mut caret = caret.min(formatted.len());
+    while !formatted.is_char_boundary(caret) {
+        caret -= 1;
+    }
+    replace_and_select(tv, &text, (0, text.len()), &formatted, (caret, caret));
+}
+
+/// Hard-wrap the paragraphs touched by the selection to `column`.
+pub(super) fn reflow_paragraphs(tv: &NSTextView, column: usize) {
+    use mdit::editor::formatting::reflow_paragraphs_edit;
+    let Some((text, selection)) = text_and_selection(tv) else {
+        return;
+    };
+    if let Some(edit) = reflow_paragraphs_edit(&text, selection, column) {
+        apply_text_edit(tv, &text, &edit);
+    }
+}
+
+/// Join the lines of the paragraphs touched by the selection.
+pub(super) fn unwrap_paragraphs(tv: &NSTextView) {
+    use mdit::editor::formatting::unwrap_paragraphs_edit;
+    let Some((text, selection)) = text_and_selection(tv) else {
+        return;
+    };
+    if let Some(edit) = unwrap_paragraphs_edit(&text, selection) {
+        apply_text_edit(tv, &text, &edit);
     }
-    ranges
 }
 
 // ---------------------------------------------------------------------------
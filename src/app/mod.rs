@@ -1,8 +1,12 @@
 mod find;
+mod folder_search;
 mod preferences;
 mod file_ops;
 mod tabs;
 mod mode;
+mod session;
+mod recovery;
+mod external_changes;
 pub(crate) mod helpers;
 
 use std::cell::{OnceCell, RefCell};
@@ -10,10 +14,10 @@ use std::path::PathBuf;
 
 use objc2::rc::Retained;
 use objc2::runtime::{AnyObject, ProtocolObject};
-use objc2::{define_class, msg_send, DefinedClass, MainThreadOnly};
+use objc2::{define_class, msg_send, sel, DefinedClass, MainThreadOnly};
 use objc2_app_kit::{
     NSApplication, NSApplicationActivationPolicy, NSApplicationDelegate,
-    NSColor, NSTextDelegate, NSTextView, NSTextViewDelegate,
+    NSColor, NSMenu, NSMenuItem, NSTextDelegate, NSTextView, NSTextViewDelegate, NSView,
     NSWindowDelegate,
 };
 use objc2_foundation::{
@@ -21,18 +25,27 @@ use objc2_foundation::{
     NSRange, NSRect, NSSize, NSString,
 };
 
+use mdit::editor::formatting::TableCommand;
+use mdit::editor::search::{SearchScope, Searcher};
 use mdit::editor::tab_manager::TabManager;
+use mdit::editor::tasks::TaskSummary;
 use mdit::editor::view_mode::ViewMode;
-use mdit::menu::build_main_menu;
+use mdit::markdown::parser::MarkdownSpan;
+use mdit::menu::{build_main_menu, find_scope_menu, history_menu, table_menu};
 use mdit::ui::appearance::{ColorScheme, ThemePreference};
+use mdit::ui::conflict_banner::ConflictBanner;
 use mdit::ui::find_bar::FindBar;
+use mdit::ui::folder_search_panel::{hit_index, FolderSearchPanel};
 use mdit::ui::path_bar::PathBar;
 use mdit::ui::sidebar::{FormattingSidebar, SIDEBAR_W};
 use mdit::ui::tab_bar::TabBar;
 use mdit::ui::welcome_overlay::WelcomeOverlay;
 
 use find::{FindCoordinator, Direction};
-use preferences::Preferences;
+use folder_search::FolderSearchCoordinator;
+use recovery::AutosaveCoordinator;
+use external_changes::ExternalChangeCoordinator;
+use preferences::{History, Preferences};
 use helpers::*;
 
 // ---------------------------------------------------------------------------
@@ -87,9 +100,20 @@ pub(super) struct AppDelegateIvars {
     // ── Find bar state ───────────────────────────────────────────────────
     pub(super) find_bar: OnceCell<FindBar>,
     pub(super) find: FindCoordinator,
+    // ── Find in Folder ───────────────────────────────────────────────────
+    pub(super) folder_panel: OnceCell<FolderSearchPanel>,
+    pub(super) folder_search: FolderSearchCoordinator,
+    // ── Crash recovery ───────────────────────────────────────────────────
+    pub(super) autosave: AutosaveCoordinator,
+    // ── External changes ─────────────────────────────────────────────────
+    pub(super) external: ExternalChangeCoordinator,
+    pub(super) conflict_banner: OnceCell<ConflictBanner>,
     // ── Preferences ──────────────────────────────────────────────────────
     pub(super) prefs: Preferences,
     pub(super) welcome_overlay: OnceCell<WelcomeOverlay>,
+    // ── Task progress ────────────────────────────────────────────────────
+    /// The active tab's task counts, with list ranges in UTF-16 offsets.
+    pub(super) task_summary: RefCell<TaskSummary>,
 }
 
 define_class!(
@@ -111,6 +135,10 @@ define_class!(
             let loaded = Preferences::load();
             self.ivars().prefs.set_theme_no_persist(loaded.theme());
             self.ivars().prefs.set_font_size_no_persist(loaded.font_size());
+            self.ivars().prefs.set_wrap_column_no_persist(loaded.wrap_column());
+            for kind in [History::Search, History::Replace] {
+                self.ivars().prefs.set_history_no_persist(kind, loaded.history(kind));
+            }
             let pref = loaded.theme();
             let system_is_dark = detect_is_dark(&app);
             let initial_scheme = pref.resolve(system_is_dark);
@@ -122,14 +150,20 @@ define_class!(
             #[allow(deprecated)]
             app.activateIgnoringOtherApps(true);
 
-            // Open pending file or start with an empty tab.
+            // Reopen the previous session, then any pending file; an empty
+            // tab remains if neither opens anything.
             let pending = self.ivars().pending_open.borrow_mut().take();
             self.add_empty_tab();
             self.apply_scheme(initial_scheme);
+            self.restore_session();
             if let Some(path) = pending {
                 self.open_file_by_path(path);
             }
+            self.offer_recovery();
             self.update_text_container_inset();
+            self.start_session_timer();
+            self.start_autosave_timer();
+            self.start_watching_files();
         }
 
         #[unsafe(method(application:openFile:))]
@@ -144,6 +178,11 @@ define_class!(
             true
         }
 
+        #[unsafe(method(applicationWillTerminate:))]
+        fn will_terminate(&self, _notification: &NSNotification) {
+            self.save_session();
+        }
+
         #[unsafe(method(applicationShouldTerminateAfterLastWindowClosed:))]
         fn should_terminate_after_last_window_closed(&self, _sender: &NSApplication) -> bool {
             true
@@ -201,6 +240,7 @@ define_class!(
                 overlay.set_frame(frame);
             }
             self.update_text_container_inset();
+            self.update_conflict_banner();
         }
     }
 
@@ -386,6 +426,195 @@ define_class!(
             }
         }
 
+        /// Edit > Move Up  (Option+Up is handled by the text view)
+        #[unsafe(method(moveBlockUp:))]
+        fn move_block_up(&self, _sender: &AnyObject) { self.dispatch_move_block(true); }
+
+        /// Edit > Move Down  (Option+Down is handled by the text view)
+        #[unsafe(method(moveBlockDown:))]
+        fn move_block_down(&self, _sender: &AnyObject) { self.dispatch_move_block(false); }
+
+        /// Edit > Promote Section
+        #[unsafe(method(promoteSection:))]
+        fn promote_section(&self, _sender: &AnyObject) { self.dispatch_shift_section(true); }
+
+        /// Edit > Demote Section
+        #[unsafe(method(demoteSection:))]
+        fn demote_section(&self, _sender: &AnyObject) { self.dispatch_shift_section(false); }
+
+        /// Edit > Insert Footnote  (Cmd+Option+F)
+        #[unsafe(method(insertFootnote:))]
+        fn insert_footnote_action(&self, _sender: &AnyObject) {
+            if let Some(tv) = self.editor_text_view() {
+                insert_footnote(&tv, &self.active_spans());
+            }
+        }
+
+        /// Edit > Renumber Footnotes
+        #[unsafe(method(renumberFootnotes:))]
+        fn renumber_footnotes_action(&self, _sender: &AnyObject) {
+            if let Some(tv) = self.editor_text_view() {
+                renumber_footnotes(&tv, &self.active_spans());
+            }
+        }
+
+        /// Edit > Tasks > Toggle Task  (Cmd+Return) — works in both modes.
+        #[unsafe(method(toggleTask:))]
+        fn toggle_task_action(&self, _sender: &AnyObject) {
+            if let Some(tv) = self.active_text_view() {
+                toggle_tasks(&tv, &self.active_spans());
+            }
+        }
+
+        /// Edit > Tasks > Move Completed to Bottom
+        #[unsafe(method(sortCompletedTasks:))]
+        fn sort_completed_tasks_action(&self, _sender: &AnyObject) {
+            if let Some(tv) = self.editor_text_view() {
+                sort_completed_tasks(&tv, &self.active_spans());
+            }
+        }
+
+        /// Edit > Tasks > Archive Completed Tasks
+        #[unsafe(method(archiveCompletedTasks:))]
+        fn archive_completed_tasks_action(&self, _sender: &AnyObject) {
+            if let Some(tv) = self.editor_text_view() {
+                archive_completed_tasks(&tv, &self.active_spans());
+            }
+        }
+
+        /// Edit > Format Document  (Cmd+Shift+Option+F)
+        #[unsafe(method(formatDocument:))]
+        fn format_document_action(&self, _sender: &AnyObject) {
+            if let Some(tv) = self.editor_text_view() {
+                format_whole_document(&tv);
+            }
+        }
+
+        /// Edit > Reflow Paragraph  (Cmd+Option+Q)
+        #[unsafe(method(reflowParagraph:))]
+        fn reflow_paragraph_action(&self, _sender: &AnyObject) {
+            if let Some(tv) = self.editor_text_view() {
+                reflow_paragraphs(&tv, self.ivars().prefs.wrap_column());
+            }
+        }
+
+        /// Edit > Unwrap Paragraph
+        #[unsafe(method(unwrapParagraph:))]
+        fn unwrap_paragraph_action(&self, _sender: &AnyObject) {
+            if let Some(tv) = self.editor_text_view() {
+                unwrap_paragraphs(&tv);
+            }
+        }
+
+        /// Edit > Wrap Column > N — the column is carried in the item's tag.
+        #[unsafe(method(setWrapColumn:))]
+        fn set_wrap_column_action(&self, sender: &AnyObject) {
+            let tag: isize = unsafe { msg_send![sender, tag] };
+            if tag > 0 {
+                self.ivars().prefs.set_wrap_column(tag as usize);
+            }
+        }
+
+        /// Tick the Wrap Column item matching the current preference.
+        #[unsafe(method(validateMenuItem:))]
+        fn validate_menu_item(&self, item: &NSMenuItem) -> bool {
+            if item.action() == Some(sel!(setWrapColumn:)) {
+                let column = self.ivars().prefs.wrap_column();
+                let state: isize = (item.tag() as usize == column).into();
+                let _: () = unsafe { msg_send![item, setState: state] };
+            }
+            if item.action() == Some(sel!(findBarSetScope:)) {
+                let current = self.ivars().find_bar.get().map(|fb| fb.scope());
+                let scope = SearchScope::ALL.get(item.tag() as usize).copied();
+                let state: isize = (scope.is_some() && scope == current).into();
+                let _: () = unsafe { msg_send![item, setState: state] };
+            }
+            if item.action() == Some(sel!(findBarToggleAllTabs:)) {
+                let all_tabs = self.ivars().find_bar.get().is_some_and(|fb| fb.is_all_tabs());
+                let _: () = unsafe { msg_send![item, setState: isize::from(all_tabs)] };
+            }
+            true
+        }
+
+        // ── Table actions ─────────────────────────────────────────────────
+
+        #[unsafe(method(insertTable:))]
+        fn insert_table_action(&self, _sender: &AnyObject) {
+            if let Some(tv) = self.editor_text_view() {
+                insert_table(&tv);
+            }
+        }
+
+        #[unsafe(method(tableInsertRowAbove:))]
+        fn table_insert_row_above(&self, _sender: &AnyObject) {
+            self.dispatch_table_command(TableCommand::InsertRowAbove);
+        }
+
+        #[unsafe(method(tableInsertRowBelow:))]
+        fn table_insert_row_below(&self, _sender: &AnyObject) {
+            self.dispatch_table_command(TableCommand::InsertRowBelow);
+        }
+
+        #[unsafe(method(tableDeleteRow:))]
+        fn table_delete_row(&self, _sender: &AnyObject) {
+            self.dispatch_table_command(TableCommand::DeleteRow);
+        }
+
+        #[unsafe(method(tableInsertColumnLeft:))]
+        fn table_insert_column_left(&self, _sender: &AnyObject) {
+            self.dispatch_table_command(TableCommand::InsertColumnLeft);
+        }
+
+        #[unsafe(method(tableInsertColumnRight:))]
+        fn table_insert_column_right(&self, _sender: &AnyObject) {
+            self.dispatch_table_command(TableCommand::InsertColumnRight);
+        }
+
+        #[unsafe(method(tableDeleteColumn:))]
+        fn table_delete_column(&self, _sender: &AnyObject) {
+            self.dispatch_table_command(TableCommand::DeleteColumn);
+        }
+
+        #[unsafe(method(tableMoveColumnLeft:))]
+        fn table_move_column_left(&self, _sender: &AnyObject) {
+            self.dispatch_table_command(TableCommand::MoveColumnLeft);
+        }
+
+        #[unsafe(method(tableMoveColumnRight:))]
+        fn table_move_column_right(&self, _sender: &AnyObject) {
+            self.dispatch_table_command(TableCommand::MoveColumnRight);
+        }
+
+        #[unsafe(method(formatTable:))]
+        fn format_table(&self, _sender: &AnyObject) {
+            self.dispatch_table_command(TableCommand::Format);
+        }
+
+        #[unsafe(method(copyTableAsCSV:))]
+        fn copy_table_as_csv(&self, _sender: &AnyObject) {
+            if let Some(tv) = self.active_text_view() {
+                copy_table_as_delimited(&tv, ',');
+            }
+        }
+
+        #[unsafe(method(copyTableAsTSV:))]
+        fn copy_table_as_tsv(&self, _sender: &AnyObject) {
+            if let Some(tv) = self.active_text_view() {
+                copy_table_as_delimited(&tv, '\t');
+            }
+        }
+
+        /// Sidebar table button: pop up the Table menu under the mouse.
+        #[unsafe(method(showTableMenu:))]
+        fn show_table_menu(&self, sender: &AnyObject) {
+            let mtm = self.mtm();
+            let Some(event) = NSApplication::sharedApplication(mtm).currentEvent() else {
+                return;
+            };
+            let view = unsafe { &*(sender as *const AnyObject as *const NSView) };
+            NSMenu::popUpContextMenu_withEvent_forView(&table_menu(mtm), &event, view);
+        }
+
         // ── Find bar actions ──────────────────────────────────────────────
 
         #[unsafe(method(openFindBar:))]
@@ -405,18 +634,12 @@ define_class!(
                 self.open_find_bar();
                 return;
             }
-            let Some(fb) = self.ivars().find_bar.get() else { return };
-            let tm = self.ivars().tab_manager.borrow();
-            let Some(tab) = tm.active() else { return };
-            self.ivars().find.navigate(Direction::Next, fb, &tab.text_view);
+            self.find_step(Direction::Next);
         }
 
         #[unsafe(method(findPrevious:))]
         fn find_previous_action(&self, _sender: &AnyObject) {
-            let Some(fb) = self.ivars().find_bar.get() else { return };
-            let tm = self.ivars().tab_manager.borrow();
-            let Some(tab) = tm.active() else { return };
-            self.ivars().find.navigate(Direction::Previous, fb, &tab.text_view);
+            self.find_step(Direction::Previous);
         }
 
         #[unsafe(method(findBarToggleAa:))]
@@ -427,26 +650,215 @@ define_class!(
             self.perform_find_search();
         }
 
+        #[unsafe(method(findBarToggleWholeWord:))]
+        fn find_bar_toggle_whole_word(&self, _sender: &AnyObject) {
+            if let Some(fb) = self.ivars().find_bar.get() {
+                fb.toggle_whole_word();
+            }
+            self.perform_find_search();
+        }
+
+        #[unsafe(method(findBarToggleRegex:))]
+        fn find_bar_toggle_regex(&self, _sender: &AnyObject) {
+            if let Some(fb) = self.ivars().find_bar.get() {
+                fb.toggle_regex();
+            }
+            self.perform_find_search();
+        }
+
+        /// Search In > All Open Tabs
+        #[unsafe(method(findBarToggleAllTabs:))]
+        fn find_bar_toggle_all_tabs(&self, _sender: &AnyObject) {
+            if let Some(fb) = self.ivars().find_bar.get() {
+                fb.toggle_all_tabs();
+            }
+            self.perform_find_search();
+        }
+
+        /// Find bar scope button: pop up the Search In menu under the mouse.
+        #[unsafe(method(findBarShowScopeMenu:))]
+        fn find_bar_show_scope_menu(&self, sender: &AnyObject) {
+            let mtm = self.mtm();
+            let Some(event) = NSApplication::sharedApplication(mtm).currentEvent() else {
+                return;
+            };
+            let view = unsafe { &*(sender as *const AnyObject as *const NSView) };
+            NSMenu::popUpContextMenu_withEvent_forView(&find_scope_menu(mtm), &event, view);
+        }
+
+        /// Search In > scope — the scope's index is carried in the item's tag.
+        #[unsafe(method(findBarSetScope:))]
+        fn find_bar_set_scope(&self, sender: &AnyObject) {
+            let tag: isize = unsafe { msg_send![sender, tag] };
+            let Some(&scope) = SearchScope::ALL.get(tag as usize) else { return };
+            if let Some(fb) = self.ivars().find_bar.get() {
+                fb.set_scope(scope);
+            }
+            self.perform_find_search();
+        }
+
+        #[unsafe(method(findBarTogglePreserveCase:))]
+        fn find_bar_toggle_preserve_case(&self, _sender: &AnyObject) {
+            if let Some(fb) = self.ivars().find_bar.get() {
+                fb.toggle_preserve_case();
+            }
+        }
+
+        /// Find bar ▾ after the search field: pop up recent searches.
+        #[unsafe(method(findBarShowSearchHistory:))]
+        fn find_bar_show_search_history(&self, sender: &AnyObject) {
+            self.show_history_menu(History::Search, sender);
+        }
+
+        /// Find bar ▾ after the replace field: pop up recent replacements.
+        #[unsafe(method(findBarShowReplaceHistory:))]
+        fn find_bar_show_replace_history(&self, sender: &AnyObject) {
+            self.show_history_menu(History::Replace, sender);
+        }
+
+        /// Recent searches > entry — its index is carried in the item's tag.
+        #[unsafe(method(findBarUseSearchHistory:))]
+        fn find_bar_use_search_history(&self, sender: &AnyObject) {
+            let tag: isize = unsafe { msg_send![sender, tag] };
+            let history = self.ivars().prefs.history(History::Search);
+            let (Some(fb), Some(entry)) = (self.ivars().find_bar.get(), history.get(tag as usize)) else {
+                return;
+            };
+            fb.set_search_text(entry);
+            self.perform_find_search();
+        }
+
+        /// Recent replacements > entry — its index is carried in the item's tag.
+        #[unsafe(method(findBarUseReplaceHistory:))]
+        fn find_bar_use_replace_history(&self, sender: &AnyObject) {
+            let tag: isize = unsafe { msg_send![sender, tag] };
+            let history = self.ivars().prefs.history(History::Replace);
+            if let (Some(fb), Some(entry)) = (self.ivars().find_bar.get(), history.get(tag as usize)) {
+                fb.set_replace_text(entry);
+            }
+        }
+
+        #[unsafe(method(findBarClearSearchHistory:))]
+        fn find_bar_clear_search_history(&self, _sender: &AnyObject) {
+            self.ivars().prefs.clear_history(History::Search);
+        }
+
+        #[unsafe(method(findBarClearReplaceHistory:))]
+        fn find_bar_clear_replace_history(&self, _sender: &AnyObject) {
+            self.ivars().prefs.clear_history(History::Replace);
+        }
+
         #[unsafe(method(replaceOne:))]
         fn replace_one_action(&self, _sender: &AnyObject) {
             let Some(fb) = self.ivars().find_bar.get() else { return };
-            let (tv, tab_mode) = {
+            self.remember_find_strings(true);
+            let (tv, ed, tab_mode) = {
                 let tm = self.ivars().tab_manager.borrow();
                 let Some(tab) = tm.active() else { return };
-                (tab.text_view.clone(), tab.mode.get())
+                (tab.text_view.clone(), tab.editor_delegate.clone(), tab.mode.get())
             };
-            self.ivars().find.replace_one(fb, &tv, tab_mode);
+            self.ivars().find.replace_one(fb, &tv, &ed, tab_mode);
+            self.update_tab_match_counts();
         }
 
         #[unsafe(method(replaceAll:))]
         fn replace_all_action(&self, _sender: &AnyObject) {
             let Some(fb) = self.ivars().find_bar.get() else { return };
-            let (tv, tab_mode) = {
+            self.remember_find_strings(true);
+            if fb.is_all_tabs() {
+                let changed = self.ivars().find.replace_all_tabs(fb, &self.ivars().tab_manager.borrow());
+                if changed > 0 {
+                    self.rebuild_tab_bar();
+                }
+                self.perform_find_search();
+                return;
+            }
+            let (tv, ed, tab_mode) = {
                 let tm = self.ivars().tab_manager.borrow();
                 let Some(tab) = tm.active() else { return };
-                (tab.text_view.clone(), tab.mode.get())
+                (tab.text_view.clone(), tab.editor_delegate.clone(), tab.mode.get())
             };
-            self.ivars().find.replace_all(fb, &tv, tab_mode);
+            self.ivars().find.replace_all(fb, &tv, &ed, tab_mode);
+            self.update_tab_match_counts();
+        }
+
+        // ── Find in Folder ────────────────────────────────────────────────
+
+        /// Edit > Find > Find in Folder…  (Cmd+Shift+F)
+        #[unsafe(method(showFindInFolder:))]
+        fn show_find_in_folder(&self, _sender: &AnyObject) {
+            let panel = self.folder_panel();
+            if self.ivars().folder_search.root().is_none() {
+                // Default to the active document's folder.
+                let dir = self.ivars().tab_manager.borrow().active()
+                    .and_then(|t| t.url.borrow().as_ref().and_then(|p| p.parent().map(PathBuf::from)));
+                if let Some(dir) = dir {
+                    self.ivars().folder_search.set_root(dir, panel);
+                }
+            }
+            panel.show();
+        }
+
+        #[unsafe(method(folderSearchChooseFolder:))]
+        fn folder_search_choose_folder(&self, _sender: &AnyObject) {
+            if self.choose_search_folder() && !self.folder_panel().query().is_empty() {
+                self.start_folder_search();
+            }
+        }
+
+        /// Return in the Find in Folder query field.
+        #[unsafe(method(folderSearchStart:))]
+        fn folder_search_start(&self, _sender: &AnyObject) {
+            if self.ivars().folder_search.root().is_none() && !self.choose_search_folder() {
+                return;
+            }
+            self.start_folder_search();
+        }
+
+        #[unsafe(method(folderSearchStop:))]
+        fn folder_search_stop(&self, _sender: &AnyObject) {
+            self.ivars().folder_search.cancel();
+        }
+
+        /// Timer tick while a folder search runs.
+        #[unsafe(method(folderSearchPoll:))]
+        fn folder_search_poll(&self, _timer: &AnyObject) {
+            self.ivars().folder_search.poll(self.folder_panel());
+        }
+
+        /// Periodic session save.
+        #[unsafe(method(saveSession:))]
+        fn save_session_tick(&self, _timer: &AnyObject) {
+            self.save_session();
+        }
+
+        /// Periodic snapshot of unsaved documents.
+        #[unsafe(method(autosave:))]
+        fn autosave_tick(&self, _timer: &AnyObject) {
+            self.autosave();
+        }
+
+        // ── External changes ──────────────────────────────────────────────
+
+        /// Timer tick: handle files changed by other applications.
+        #[unsafe(method(checkExternalChanges:))]
+        fn check_external_changes_tick(&self, _timer: &AnyObject) {
+            self.check_external_changes();
+        }
+
+        #[unsafe(method(conflictReload:))]
+        fn conflict_reload_action(&self, _sender: &AnyObject) {
+            self.conflict_reload();
+        }
+
+        #[unsafe(method(conflictKeepMine:))]
+        fn conflict_keep_mine_action(&self, _sender: &AnyObject) {
+            self.conflict_keep_mine();
+        }
+
+        #[unsafe(method(conflictMerge:))]
+        fn conflict_merge_action(&self, _sender: &AnyObject) {
+            self.conflict_merge();
         }
 
         // ── Live search delegate ──────────────────────────────────────────
@@ -476,7 +888,17 @@ define_class!(
     // ── NSTextViewDelegate: show/hide toolbar on selection ──────────────────
     unsafe impl NSTextDelegate for AppDelegate {
         #[unsafe(method(textDidChange:))]
-        fn text_did_change(&self, _notification: &NSNotification) {
+        fn text_did_change(&self, notification: &NSNotification) {
+            // Replace All across tabs edits background tabs too; it marks
+            // those dirty itself, so only follow the active text view here.
+            let sender: *const AnyObject = unsafe { msg_send![notification, object] };
+            let from_active = {
+                let tm = self.ivars().tab_manager.borrow();
+                tm.active().is_some_and(|t| std::ptr::eq(sender, Retained::as_ptr(&t.text_view).cast()))
+            };
+            if !from_active {
+                return;
+            }
             let already_dirty = {
                 let tm = self.ivars().tab_manager.borrow();
                 tm.active().map(|t| t.is_dirty.get()).unwrap_or(true)
@@ -499,6 +921,7 @@ define_class!(
                     }
                 }
             }
+            self.refresh_task_progress();
             self.update_welcome_visibility();
         }
     }
@@ -506,8 +929,29 @@ define_class!(
     unsafe impl NSTextViewDelegate for AppDelegate {
         #[unsafe(method(textViewDidChangeSelection:))]
         fn text_view_did_change_selection(&self, _notification: &NSNotification) {
-            // Selection changes are handled by the sidebar (visible in Editor mode only).
-            // No floating toolbar to show or hide.
+            // The formatting sidebar needs no selection updates; only the
+            // per-list task progress follows the caret.
+            self.update_task_progress();
+        }
+
+        /// Clicks on Find in Folder results; other links keep the default behavior.
+        #[unsafe(method(textView:clickedOnLink:atIndex:))]
+        fn text_view_clicked_on_link(
+            &self,
+            text_view: &NSTextView,
+            link: &AnyObject,
+            _char_index: usize,
+        ) -> bool {
+            let Some(panel) = self.ivars().folder_panel.get() else { return false };
+            if !panel.is_results_view(text_view) {
+                return false;
+            }
+            let link: Retained<NSString> = unsafe { msg_send![link, description] };
+            let hit = hit_index(&link.to_string()).and_then(|i| self.ivars().folder_search.hit(i));
+            if let Some((path, hit)) = hit {
+                self.open_folder_hit(&path, &hit);
+            }
+            true
         }
     }
 );
@@ -520,17 +964,20 @@ impl AppDelegate {
 
     /// Forward an inline-format toggle to the active editor text view.
     ///
+    /// Passes the active tab's parsed spans (`MditEditorDelegate::spans()`)
+    /// so the toggle can see the real enclosing formatting nodes.
     /// Switches to Editor mode automatically if currently in Viewer mode,
     /// so clicking a sidebar button activates editing.
     fn dispatch_inline_format(&self, marker: &'static str) {
-        if let Some(tv) = self.editor_text_view() {
-            toggle_inline_wrap(&tv, marker);
-        }
+        let Some(tv) = self.editor_text_view() else {
+            return;
+        };
+        toggle_inline_wrap(&tv, &self.active_spans(), marker);
     }
 
-    /// Apply a block-level prefix to the line containing the caret.
+    /// Apply a block-level prefix to every line touched by the selection.
     ///
-    /// Delegates to the pure `set_block_format()` in `editor::formatting`.
+    /// Delegates to the pure `set_block_format_lines()` in `editor::formatting`.
     /// Switches to Editor mode automatically if needed.
     fn dispatch_block_format(&self, prefix: &'static str) {
         if let Some(tv) = self.editor_text_view() {
@@ -538,6 +985,70 @@ impl AppDelegate {
         }
     }
 
+    /// Move the line, list item or section at the caret one step up or down.
+    ///
+    /// Switches to Editor mode automatically if needed.
+    fn dispatch_move_block(&self, up: bool) {
+        if let Some(tv) = self.editor_text_view() {
+            move_block(&tv, &self.active_spans(), up);
+        }
+    }
+
+    /// Promote or demote the heading section at the caret.
+    ///
+    /// Switches to Editor mode automatically if needed.
+    fn dispatch_shift_section(&self, promote: bool) {
+        if let Some(tv) = self.editor_text_view() {
+            shift_section(&tv, &self.active_spans(), promote);
+        }
+    }
+
+    /// The active tab's parsed spans (empty when no tab is open).
+    fn active_spans(&self) -> Vec<MarkdownSpan> {
+        let tm = self.ivars().tab_manager.borrow();
+        tm.active().map(|t| t.editor_delegate.spans()).unwrap_or_default()
+    }
+
+    /// Recount the active tab's tasks after its text changed (or another
+    /// tab became active), then refresh the path bar.
+    fn refresh_task_progress(&self) {
+        let summary = {
+            let tm = self.ivars().tab_manager.borrow();
+            tm.active()
+                .map(|t| {
+                    let text = unsafe { t.text_view.textStorage() }
+                        .map(|storage| storage.string().to_string())
+                        .unwrap_or_default();
+                    TaskSummary::new(&t.editor_delegate.spans())
+                        .map_offsets(|pos| text[..pos.min(text.len())].encode_utf16().count())
+                })
+                .unwrap_or_default()
+        };
+        *self.ivars().task_summary.borrow_mut() = summary;
+        self.update_task_progress();
+    }
+
+    /// Refresh the path bar's task progress for the caret from the counts
+    /// cached by `refresh_task_progress`.
+    fn update_task_progress(&self) {
+        let Some(pb) = self.ivars().path_bar.get() else { return };
+        let caret = self.active_text_view().map_or(0, |tv| {
+            let selection: NSRange = unsafe { msg_send![&*tv, selectedRange] };
+            selection.location
+        });
+        let label = self.ivars().task_summary.borrow().label(caret);
+        pb.update_tasks(label.as_deref());
+    }
+
+    /// Run a table editing command on the table under the caret.
+    ///
+    /// Switches to Editor mode automatically if needed.
+    fn dispatch_table_command(&self, command: TableCommand) {
+        if let Some(tv) = self.editor_text_view() {
+            apply_table_command(&tv, command);
+        }
+    }
+
     /// Create the main window, build the menu, and present it.
     ///
     /// Stores the window in `self.ivars().window`.
@@ -611,11 +1122,54 @@ impl AppDelegate {
         ));
         content.addSubview(welcome_overlay.view());
 
+        // Conflict banner — hidden until an open file changes under unsaved edits.
+        let conflict_banner = ConflictBanner::new(mtm, target);
+        content.addSubview(conflict_banner.view());
+
         let _ = self.ivars().tab_bar.set(tab_bar);
         let _ = self.ivars().path_bar.set(path_bar);
         let _ = self.ivars().sidebar.set(sidebar);
         let _ = self.ivars().find_bar.set(find_bar);
         let _ = self.ivars().welcome_overlay.set(welcome_overlay);
+        let _ = self.ivars().conflict_banner.set(conflict_banner);
+    }
+
+    /// The Find in Folder window, created on first use.
+    fn folder_panel(&self) -> &FolderSearchPanel {
+        self.ivars().folder_panel.get_or_init(|| {
+            let target: &AnyObject = unsafe { &*(self as *const AppDelegate as *const AnyObject) };
+            FolderSearchPanel::new(self.mtm(), target)
+        })
+    }
+
+    /// Ask for the folder to search. Returns false if the user cancelled.
+    fn choose_search_folder(&self) -> bool {
+        use objc2_app_kit::NSOpenPanel;
+        let open = NSOpenPanel::openPanel(self.mtm());
+        open.setCanChooseFiles(false);
+        open.setCanChooseDirectories(true);
+        open.setAllowsMultipleSelection(false);
+        if open.runModal() != 1 { return false; } // NSModalResponseOK = 1
+        let Some(ns_path) = open.URL().and_then(|u| u.path()) else { return false };
+        self.ivars().folder_search.set_root(PathBuf::from(ns_path.to_string()), self.folder_panel());
+        true
+    }
+
+    /// Search the chosen folder for the panel's query, using the find bar's
+    /// match options (case, whole word, regex, scope).
+    fn start_folder_search(&self) {
+        let panel = self.folder_panel();
+        let query = panel.query();
+        if query.is_empty() { return; }
+        self.ivars().prefs.remember(History::Search, &query);
+        let options = self.ivars().find_bar.get().map(|fb| fb.search_options()).unwrap_or_default();
+        match Searcher::new(&query, options) {
+            Ok(searcher) => {
+                let target: &AnyObject = unsafe { &*(self as *const AppDelegate as *const AnyObject) };
+                self.ivars().folder_search.start(searcher, panel, target);
+            }
+            Err(message) => panel.set_status(&format!("Invalid pattern: {message}")),
+        }
     }
 
     /// Frame for the active NSScrollView, positioned between the tab bar and path bar.
@@ -770,16 +1324,49 @@ impl AppDelegate {
         self.perform_find_search();
     }
 
+    /// Recount matches in every tab when searching all tabs and refresh the
+    /// tab bar's match counts if they changed.
+    fn update_tab_match_counts(&self) {
+        let Some(fb) = self.ivars().find_bar.get() else { return };
+        let changed = self.ivars().find.update_tab_counts(fb, &self.ivars().tab_manager.borrow());
+        if changed {
+            self.rebuild_tab_bar();
+        }
+    }
+
+    /// Find Next / Find Previous. When searching all tabs, stepping past the
+    /// last match of this tab switches to the next tab with matches.
+    fn find_step(&self, direction: Direction) {
+        let Some(fb) = self.ivars().find_bar.get() else { return };
+        self.remember_find_strings(false);
+        if !fb.is_all_tabs() {
+            let tm = self.ivars().tab_manager.borrow();
+            let Some(tab) = tm.active() else { return };
+            self.ivars().find.navigate(direction, fb, &tab.text_view);
+            return;
+        }
+        let Some((tab, idx)) = self.ivars().find.step_across_tabs(direction) else { return };
+        if tab != self.ivars().tab_manager.borrow().active_index() {
+            // Keeps the find bar open and searches the new tab.
+            self.switch_to_tab(tab);
+        }
+        let tm = self.ivars().tab_manager.borrow();
+        if let Some(t) = tm.active() {
+            self.ivars().find.select(idx, fb, &t.text_view);
+        }
+    }
+
     /// Run a search against the active tab's text and update highlights + count.
     fn perform_find_search(&self) {
         let Some(fb) = self.ivars().find_bar.get() else { return };
         if !self.ivars().find.is_open() { return; }
-        let (tv, tab_mode) = {
+        let (tv, ed, tab_mode) = {
             let tm = self.ivars().tab_manager.borrow();
             let Some(tab) = tm.active() else { return };
-            (tab.text_view.clone(), tab.mode.get())
+            (tab.text_view.clone(), tab.editor_delegate.clone(), tab.mode.get())
         };
-        self.ivars().find.perform_search(fb, &tv, tab_mode);
+        self.ivars().find.perform_search(fb, &tv, &ed, tab_mode);
+        self.update_tab_match_counts();
         // After search, update bar height frame if needed
         let new_h = self.ivars().find.bar_height();
         if let Some(win) = self.ivars().window.get() {
@@ -796,6 +1383,33 @@ impl AppDelegate {
         }
     }
 
+    /// Add the find bar's query (and, for replace actions, its replacement)
+    /// to the persisted history. Typing only searches live; the query is
+    /// recorded once it is used to step through matches or replace.
+    fn remember_find_strings(&self, replacing: bool) {
+        let Some(fb) = self.ivars().find_bar.get() else { return };
+        let prefs = &self.ivars().prefs;
+        prefs.remember(History::Search, &fb.search_text());
+        if replacing {
+            prefs.remember(History::Replace, &fb.replace_text());
+        }
+    }
+
+    /// Pop up the recent strings for one find bar field under the mouse.
+    fn show_history_menu(&self, kind: History, sender: &AnyObject) {
+        let mtm = self.mtm();
+        let Some(event) = NSApplication::sharedApplication(mtm).currentEvent() else {
+            return;
+        };
+        let (use_action, clear_action) = match kind {
+            History::Search => (sel!(findBarUseSearchHistory:), sel!(findBarClearSearchHistory:)),
+            History::Replace => (sel!(findBarUseReplaceHistory:), sel!(findBarClearReplaceHistory:)),
+        };
+        let menu = history_menu(&self.ivars().prefs.history(kind), use_action, clear_action, mtm);
+        let view = unsafe { &*(sender as *const AnyObject as *const NSView) };
+        NSMenu::popUpContextMenu_withEvent_forView(&menu, &event, view);
+    }
+
     /// Close the find bar, remove highlights, and restore the scroll view.
     fn close_find_bar(&self) {
         let Some(fb) = self.ivars().find_bar.get() else { return };
@@ -804,7 +1418,11 @@ impl AppDelegate {
             .map(|t| (Some(t.text_view.clone()), Some(t.editor_delegate.clone())))
             .unwrap_or((None, None));
         drop(tm);
+        let had_tab_counts = !self.ivars().find.tab_match_counts().is_empty();
         self.ivars().find.close(fb, tv.as_deref(), ed.as_deref());
+        if had_tab_counts {
+            self.rebuild_tab_bar();
+        }
         // Restore scroll view to full height
         let frame = self.content_frame();
         let tm = self.ivars().tab_manager.borrow();
@@ -106,5 +106,6 @@ impl AppDelegate {
             }
         }
         self.update_welcome_visibility();
+        self.update_conflict_banner();
     }
 }
@@ -1,19 +1,44 @@
-use std::cell::Cell;
+use std::cell::{Cell, RefCell};
 
-use objc2_foundation::{NSString, NSUserDefaults};
+use objc2_foundation::{NSArray, NSString, NSUserDefaults};
 
+use mdit::editor::formatting::DEFAULT_WRAP_COLUMN;
+use mdit::editor::search::{remember, HISTORY_LIMIT};
 use mdit::ui::appearance::ThemePreference;
 
 const THEME_PREF_KEY: &str = "mditThemePreference";
 const FONT_SIZE_PREF_KEY: &str = "mditFontSize";
+const WRAP_COLUMN_PREF_KEY: &str = "mditWrapColumn";
+const SEARCH_HISTORY_PREF_KEY: &str = "mditSearchHistory";
+const REPLACE_HISTORY_PREF_KEY: &str = "mditReplaceHistory";
 pub(super) const DEFAULT_FONT_SIZE: f64 = 16.0;
 pub(super) const MIN_FONT_SIZE: f64 = 12.0;
 pub(super) const MAX_FONT_SIZE: f64 = 24.0;
 
-/// Owns the user's persisted theme and font size preferences.
+/// Which find bar field a history list belongs to.
+#[derive(Clone, Copy)]
+pub(super) enum History {
+    Search,
+    Replace,
+}
+
+impl History {
+    fn key(self) -> &'static str {
+        match self {
+            History::Search => SEARCH_HISTORY_PREF_KEY,
+            History::Replace => REPLACE_HISTORY_PREF_KEY,
+        }
+    }
+}
+
+/// Owns the user's persisted theme, font size and wrap column preferences,
+/// plus the find bar's recent search and replace strings.
 pub(crate) struct Preferences {
     theme_pref: Cell<ThemePreference>,
     body_font_size: Cell<f64>,
+    wrap_column: Cell<usize>,
+    search_history: RefCell<Vec<String>>,
+    replace_history: RefCell<Vec<String>>,
 }
 
 impl Default for Preferences {
@@ -21,6 +46,9 @@ impl Default for Preferences {
         Self {
             theme_pref: Cell::new(ThemePreference::default()),
             body_font_size: Cell::new(DEFAULT_FONT_SIZE),
+            wrap_column: Cell::new(DEFAULT_WRAP_COLUMN),
+            search_history: RefCell::new(Vec::new()),
+            replace_history: RefCell::new(Vec::new()),
         }
     }
 }
@@ -31,6 +59,9 @@ impl Preferences {
         Self {
             theme_pref: Cell::new(load_theme_pref()),
             body_font_size: Cell::new(load_font_size_pref()),
+            wrap_column: Cell::new(load_wrap_column_pref()),
+            search_history: RefCell::new(load_history(History::Search)),
+            replace_history: RefCell::new(load_history(History::Replace)),
         }
     }
 
@@ -61,6 +92,53 @@ impl Preferences {
     pub(super) fn set_font_size_no_persist(&self, size: f64) {
         self.body_font_size.set(size);
     }
+
+    /// Column that Reflow Paragraph wraps to.
+    pub(super) fn wrap_column(&self) -> usize {
+        self.wrap_column.get()
+    }
+
+    pub(super) fn set_wrap_column(&self, column: usize) {
+        self.wrap_column.set(column);
+        save_wrap_column_pref(column);
+    }
+
+    /// Set the wrap column without persisting (used during init from loaded values).
+    pub(super) fn set_wrap_column_no_persist(&self, column: usize) {
+        self.wrap_column.set(column);
+    }
+
+    /// Recent strings for one find bar field, most recent first.
+    pub(super) fn history(&self, kind: History) -> Vec<String> {
+        self.history_cell(kind).borrow().clone()
+    }
+
+    /// Record `entry` as the most recent string for `kind`.
+    pub(super) fn remember(&self, kind: History, entry: &str) {
+        let mut history = self.history_cell(kind).borrow_mut();
+        if history.first().map(String::as_str) == Some(entry) {
+            return;
+        }
+        remember(&mut history, entry, HISTORY_LIMIT);
+        save_history(kind, &history);
+    }
+
+    pub(super) fn clear_history(&self, kind: History) {
+        self.history_cell(kind).borrow_mut().clear();
+        save_history(kind, &[]);
+    }
+
+    /// Set a history list without persisting (used during init from loaded values).
+    pub(super) fn set_history_no_persist(&self, kind: History, entries: Vec<String>) {
+        *self.history_cell(kind).borrow_mut() = entries;
+    }
+
+    fn history_cell(&self, kind: History) -> &RefCell<Vec<String>> {
+        match kind {
+            History::Search => &self.search_history,
+            History::Replace => &self.replace_history,
+        }
+    }
 }
 
 /// Persist the user's theme choice to `NSUserDefaults`.
@@ -104,3 +182,46 @@ fn load_font_size_pref() -> f64 {
         .and_then(|s| s.to_string().parse::<f64>().ok())
         .unwrap_or(DEFAULT_FONT_SIZE)
 }
+
+/// Persist the user's wrap column to `NSUserDefaults`.
+fn save_wrap_column_pref(column: usize) {
+    let key = NSString::from_str(WRAP_COLUMN_PREF_KEY);
+    let val = NSString::from_str(&column.to_string());
+    unsafe {
+        let defaults = NSUserDefaults::standardUserDefaults();
+        defaults.setObject_forKey(Some(&*val), &key);
+    }
+}
+
+/// Load the user's wrap column from `NSUserDefaults`.
+/// Falls back to `DEFAULT_WRAP_COLUMN` when no valid value is stored.
+fn load_wrap_column_pref() -> usize {
+    let key = NSString::from_str(WRAP_COLUMN_PREF_KEY);
+    let stored = NSUserDefaults::standardUserDefaults().stringForKey(&key);
+    stored
+        .as_deref()
+        .and_then(|s| s.to_string().parse::<usize>().ok())
+        .filter(|&c| c > 0)
+        .unwrap_or(DEFAULT_WRAP_COLUMN)
+}
+
+/// Persist a find bar history list to `NSUserDefaults`.
+fn save_history(kind: History, entries: &[String]) {
+    let key = NSString::from_str(kind.key());
+    let strings: Vec<_> = entries.iter().map(|e| NSString::from_str(e)).collect();
+    let val = NSArray::from_retained_slice(&strings);
+    unsafe {
+        let defaults = NSUserDefaults::standardUserDefaults();
+        defaults.setObject_forKey(Some(&*val), &key);
+    }
+}
+
+/// Load a find bar history list from `NSUserDefaults`.
+/// Falls back to an empty list when nothing is stored.
+fn load_history(kind: History) -> Vec<String> {
+    let key = NSString::from_str(kind.key());
+    let stored = NSUserDefaults::standardUserDefaults().stringArrayForKey(&key);
+    stored
+        .map(|array| array.iter().map(|s| s.to_string()).take(HISTORY_LIMIT).collect())
+        .unwrap_or_default()
+}
@@ -0,0 +1,209 @@
+use std::cell::{OnceCell, RefCell};
+use std::collections::HashMap;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Sender};
+
+use objc2::msg_send;
+use objc2::rc::Retained;
+use objc2::runtime::{AnyClass, AnyObject};
+use objc2::DefinedClass;
+use objc2_foundation::{NSRange, NSString};
+
+use mdit::editor::recovery::{describe_changes, load_all, now, recovery_dir, snapshot_path, Snapshot};
+use mdit::editor::session::write_atomic;
+use mdit::editor::tab_manager::TabManager;
+
+use super::helpers::{show_recovery_alert, RecoveryChoice};
+use super::AppDelegate;
+
+/// How often dirty documents are snapshotted.
+const AUTOSAVE_INTERVAL: f64 = 15.0;
+
+/// File work for the recovery directory, done off the main thread.
+enum RecoveryJob {
+    Write(PathBuf, Snapshot),
+    Remove(PathBuf),
+}
+
+/// Keeps the recovery directory in step with the open documents: one
+/// snapshot per dirty document, none for clean or closed ones.
+#[derive(Default)]
+pub(crate) struct AutosaveCoordinator {
+    /// Hash of the text last snapshotted, by recovery id.
+    written: RefCell<HashMap<String, u64>>,
+    /// The single worker thread that writes and removes snapshots. One
+    /// thread, fed in order, so a removal is never overtaken by an older
+    /// write of the same file.
+    worker: OnceCell<Sender<RecoveryJob>>,
+}
+
+impl AutosaveCoordinator {
+    /// Snapshot every dirty document whose text changed since the last
+    /// pass, and delete the snapshots of documents that have since been
+    /// saved or closed.
+    pub(super) fn autosave(&self, tm: &TabManager) {
+        let Some(dir) = recovery_dir() else { return };
+        let mut written = self.written.borrow_mut();
+        let mut dirty = Vec::new();
+        for tab in tm.iter().filter(|t| t.is_dirty.get()) {
+            let Some(storage) = (unsafe { tab.text_view.textStorage() }) else { continue };
+            let text = storage.string().to_string();
+            let mut hasher = DefaultHasher::new();
+            text.hash(&mut hasher);
+            let hash = hasher.finish();
+            dirty.push(tab.recovery_id.clone());
+            if written.insert(tab.recovery_id.clone(), hash) == Some(hash) {
+                continue;
+            }
+            let snapshot = Snapshot { original: tab.url.borrow().clone(), saved_at: now(), text };
+            self.send(RecoveryJob::Write(snapshot_path(&dir, &tab.recovery_id), snapshot));
+        }
+        written.retain(|id, _| {
+            let keep = dirty.contains(id);
+            if !keep {
+                self.send(RecoveryJob::Remove(snapshot_path(&dir, id)));
+            }
+            keep
+        });
+    }
+
+    /// Delete the snapshot of document `id` — it was saved or its changes
+    /// were thrown away.
+    pub(super) fn discard(&self, id: &str) {
+        self.written.borrow_mut().remove(id);
+        if let Some(dir) = recovery_dir() {
+            self.send(RecoveryJob::Remove(snapshot_path(&dir, id)));
+        }
+    }
+
+    /// Delete a snapshot file left behind by an earlier run.
+    fn remove(&self, path: PathBuf) {
+        self.send(RecoveryJob::Remove(path));
+    }
+
+    /// Queue `job` on the worker thread, starting it on first use.
+    fn send(&self, job: RecoveryJob) {
+        let worker = self.worker.get_or_init(|| {
+            let (tx, jobs) = mpsc::channel::<RecoveryJob>();
+            std::thread::spawn(move || {
+                for job in jobs {
+                    match job {
+                        RecoveryJob::Write(path, snapshot) => {
+                            if let Err(e) = write_atomic(&path, &snapshot.serialize()) {
+                                eprintln!("mdit: cannot autosave to {:?}: {}", path, e);
+                            }
+                        }
+                        RecoveryJob::Remove(path) => {
+                            let _ = std::fs::remove_file(path);
+                        }
+                    }
+                }
+            });
+            tx
+        });
+        let _ = worker.send(job);
+    }
+}
+
+impl AppDelegate {
+    /// Snapshot dirty documents every `AUTOSAVE_INTERVAL` seconds via `autosave:`.
+    pub(super) fn start_autosave_timer(&self) {
+        let target: &AnyObject = unsafe { &*(self as *const AppDelegate as *const AnyObject) };
+        let cls = AnyClass::get(c"NSTimer").expect("NSTimer class not found");
+        let _: *mut AnyObject = unsafe {
+            msg_send![cls,
+                scheduledTimerWithTimeInterval: AUTOSAVE_INTERVAL,
+                target: target,
+                selector: objc2::sel!(autosave:),
+                userInfo: std::ptr::null::<AnyObject>(),
+                repeats: true]
+        };
+    }
+
+    pub(super) fn autosave(&self) {
+        self.ivars().autosave.autosave(&self.ivars().tab_manager.borrow());
+    }
+
+    /// If the last run left snapshots behind, list them with a diff against
+    /// each file on disk and offer to reopen them as unsaved documents.
+    pub(super) fn offer_recovery(&self) {
+        let Some(dir) = recovery_dir() else { return };
+        let found = load_all(&dir);
+        if found.is_empty() {
+            return;
+        }
+
+        let mut details = String::new();
+        for (_, snapshot) in &found {
+            details.push_str(&format!("{} \u{2014} {}\n", snapshot.title(), format_timestamp(snapshot.saved_at)));
+            if let Some(path) = &snapshot.original {
+                details.push_str(&format!("{}\n", path.display()));
+            }
+            let on_disk = snapshot.original.as_ref().and_then(|p| std::fs::read_to_string(p).ok());
+            details.push_str(&describe_changes(snapshot, on_disk.as_deref()));
+            details.push('\n');
+        }
+
+        match show_recovery_alert(found.len(), &details, self.mtm()) {
+            RecoveryChoice::Recover => {
+                for (_, snapshot) in &found {
+                    self.recover_snapshot(snapshot);
+                }
+                // Snapshot the recovered documents under their new ids
+                // before the old files go.
+                self.autosave();
+            }
+            RecoveryChoice::Discard => {}
+        }
+        for (path, _) in found {
+            self.ivars().autosave.remove(path);
+        }
+    }
+
+    /// Reopen one snapshot as a dirty document: over its file when that
+    /// still exists, otherwise as an untitled tab.
+    fn recover_snapshot(&self, snapshot: &Snapshot) {
+        let original = snapshot.original.as_ref().filter(|p| p.is_file());
+        let index = match original {
+            Some(path) => {
+                self.open_file_by_path(path.clone());
+                self.ivars().tab_manager.borrow().find_by_path(path)
+            }
+            None => {
+                if !self.active_tab_is_pristine() {
+                    self.add_empty_tab();
+                }
+                Some(self.ivars().tab_manager.borrow().active_index())
+            }
+        };
+        let Some(index) = index else { return };
+        {
+            let tm = self.ivars().tab_manager.borrow();
+            let Some(tab) = tm.get(index) else { return };
+            if let Some(storage) = unsafe { tab.text_view.textStorage() } {
+                let full = NSRange { location: 0, length: storage.length() };
+                storage.replaceCharactersInRange_withString(full, &NSString::from_str(&snapshot.text));
+            }
+            tab.is_dirty.set(true);
+        }
+        self.switch_to_tab(index);
+    }
+}
+
+/// A snapshot time in the user's locale, e.g. "Oct 18, 2026 at 2:05 PM".
+fn format_timestamp(secs: u64) -> String {
+    let (Some(formatter_cls), Some(date_cls)) = (AnyClass::get(c"NSDateFormatter"), AnyClass::get(c"NSDate"))
+    else {
+        return secs.to_string();
+    };
+    unsafe {
+        let formatter: Retained<AnyObject> = msg_send![formatter_cls, new];
+        // NSDateFormatterMediumStyle / NSDateFormatterShortStyle
+        let _: () = msg_send![&*formatter, setDateStyle: 2usize];
+        let _: () = msg_send![&*formatter, setTimeStyle: 1usize];
+        let date: Retained<AnyObject> = msg_send![date_cls, dateWithTimeIntervalSince1970: secs as f64];
+        let value: Retained<NSString> = msg_send![&*formatter, stringFromDate: &*date];
+        value.to_string()
+    }
+}
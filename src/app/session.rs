@@ -0,0 +1,122 @@
+use std::path::Path;
+
+use objc2::msg_send;
+use objc2::runtime::{AnyClass, AnyObject};
+use objc2::DefinedClass;
+use objc2_foundation::{NSPoint, NSRange, NSRect};
+
+use mdit::editor::session::{session_path, write_atomic, Session, TabSession};
+use mdit::editor::view_mode::ViewMode;
+
+use super::{preferences, AppDelegate};
+
+/// How often the session is written while the app runs, so a crash loses
+/// at most this much of the working set.
+const SAVE_INTERVAL: f64 = 30.0;
+
+impl AppDelegate {
+    /// The open documents as they stand now. Untitled tabs are left out.
+    fn capture_session(&self) -> Session {
+        let tm = self.ivars().tab_manager.borrow();
+        let mut tabs = Vec::new();
+        let mut active = 0;
+        for (i, tab) in tm.iter().enumerate() {
+            let Some(path) = tab.url.borrow().clone() else { continue };
+            if tm.active_index() == i {
+                active = tabs.len();
+            }
+            let visible: NSRect = unsafe { msg_send![&*tab.scroll_view, documentVisibleRect] };
+            tabs.push(TabSession {
+                path,
+                mode: tab.mode.get(),
+                caret: tab.text_view.selectedRange().location,
+                scroll: visible.origin.y,
+            });
+        }
+        Session { tabs, active, font_size: self.ivars().prefs.font_size() }
+    }
+
+    /// Write the session file. Failures are logged, never shown.
+    pub(super) fn save_session(&self) {
+        let Some(path) = session_path() else { return };
+        if let Err(e) = write_atomic(&path, &self.capture_session().serialize()) {
+            eprintln!("mdit: cannot save session to {:?}: {}", path, e);
+        }
+    }
+
+    /// Save the session every `SAVE_INTERVAL` seconds via `saveSession:`.
+    pub(super) fn start_session_timer(&self) {
+        let target: &AnyObject = unsafe { &*(self as *const AppDelegate as *const AnyObject) };
+        let cls = AnyClass::get(c"NSTimer").expect("NSTimer class not found");
+        let _: *mut AnyObject = unsafe {
+            msg_send![cls,
+                scheduledTimerWithTimeInterval: SAVE_INTERVAL,
+                target: target,
+                selector: objc2::sel!(saveSession:),
+                userInfo: std::ptr::null::<AnyObject>(),
+                repeats: true]
+        };
+    }
+
+    /// Reopen the documents of the previous session, skipping files that
+    /// no longer exist, and restore each one's mode, caret and scroll.
+    pub(super) fn restore_session(&self) {
+        let Some(path) = session_path() else { return };
+        let Ok(src) = std::fs::read_to_string(&path) else { return };
+        let Some(mut session) = Session::parse(&src) else { return };
+        session.retain_existing(Path::is_file);
+        if session.tabs.is_empty() {
+            return;
+        }
+
+        let size = session.font_size;
+        if (preferences::MIN_FONT_SIZE..=preferences::MAX_FONT_SIZE).contains(&size)
+            && size != self.ivars().prefs.font_size()
+        {
+            self.apply_font_size(size);
+        }
+
+        let mut active = None;
+        for (i, tab) in session.tabs.iter().enumerate() {
+            self.open_file_by_path(tab.path.clone());
+            let index = self.ivars().tab_manager.borrow().find_by_path(&tab.path);
+            let Some(index) = index else { continue };
+            self.restore_tab(index, tab);
+            if i == session.active {
+                active = Some(index);
+            }
+        }
+        if let Some(index) = active {
+            self.switch_to_tab(index);
+        }
+    }
+
+    /// Apply a saved mode, caret and scroll offset to tab `index`.
+    fn restore_tab(&self, index: usize, saved: &TabSession) {
+        let tm = self.ivars().tab_manager.borrow();
+        let Some(tab) = tm.get(index) else { return };
+
+        if saved.mode != tab.mode.get() {
+            tab.mode.set(saved.mode);
+            tab.editor_delegate.set_mode(saved.mode);
+            tab.text_view.setEditable(saved.mode == ViewMode::Editor);
+            if let Some(storage) = unsafe { tab.text_view.textStorage() } {
+                tab.editor_delegate.reapply(&storage);
+            }
+        }
+
+        let len = unsafe { tab.text_view.textStorage() }.map_or(0, |s| s.length());
+        tab.text_view.setSelectedRange(NSRange { location: saved.caret.min(len), length: 0 });
+
+        // Lay the whole document out first so the offset isn't clamped to
+        // the part measured so far.
+        unsafe {
+            if let (Some(layout), Some(container)) =
+                (tab.text_view.layoutManager(), tab.text_view.textContainer())
+            {
+                layout.ensureLayoutForTextContainer(&container);
+            }
+            let _: () = msg_send![&*tab.text_view, scrollPoint: NSPoint::new(0.0, saved.scroll)];
+        }
+    }
+}
@@ -7,6 +7,7 @@ use mdit::editor::tab_manager::TabCloseResult;
 use mdit::editor::view_mode::ViewMode;
 use mdit::ui::appearance::ColorScheme;
 use mdit::ui::sidebar::SIDEBAR_W;
+use mdit::ui::tab_bar::tab_label_with_matches;
 
 use super::helpers::{SaveChoice, show_save_alert};
 use super::{AppDelegate, TAB_H, PATH_H};
@@ -22,14 +23,29 @@ impl AppDelegate {
         };
         let mtm = self.mtm();
         let target: &AnyObject = unsafe { &*(self as *const AppDelegate as *const AnyObject) };
-        let labels = self.ivars().tab_manager.borrow().tab_labels();
+        let mut labels = self.ivars().tab_manager.borrow().tab_labels();
+        // While searching all tabs, each tab shows its match count.
+        let counts = self.ivars().find.tab_match_counts();
+        for ((label, _), &count) in labels.iter_mut().zip(&counts) {
+            *label = tab_label_with_matches(label, count);
+        }
         tab_bar.rebuild(mtm, &labels, target);
     }
 
     /// Switch to tab `index`.
     pub(super) fn switch_to_tab(&self, index: usize) {
-        // Reset find bar state on tab switch
-        self.close_find_bar();
+        // Reset find bar state on tab switch — unless it searches all tabs,
+        // in which case it stays open and moves its highlights along.
+        let keep_find = self.ivars().find.is_open()
+            && self.ivars().find_bar.get().is_some_and(|fb| fb.is_all_tabs());
+        if keep_find {
+            let tm = self.ivars().tab_manager.borrow();
+            if let Some(t) = tm.active() {
+                self.ivars().find.clear_highlights(&t.text_view, Some(&t.editor_delegate));
+            }
+        } else {
+            self.close_find_bar();
+        }
 
         let Some(win) = self.ivars().window.get() else {
             return;
@@ -90,10 +106,15 @@ impl AppDelegate {
             let win_w = win.contentView().unwrap().bounds().size.width;
             pb.set_wordcount_visible(is_editor, win_w);
         }
+        self.refresh_task_progress();
 
         self.rebuild_tab_bar();
         self.update_text_container_inset();
         self.update_welcome_visibility();
+        self.update_conflict_banner();
+        if keep_find {
+            self.perform_find_search();
+        }
     }
 
     /// Create a new empty tab and activate it.
@@ -125,7 +146,7 @@ impl AppDelegate {
         // Close find bar before closing tab
         self.close_find_bar();
 
-        let (is_dirty, filename) = {
+        let (is_dirty, filename, recovery_id) = {
             let tm = self.ivars().tab_manager.borrow();
             let tab = match tm.get(index) {
                 Some(t) => t,
@@ -138,13 +159,19 @@ impl AppDelegate {
                 .and_then(|p| p.file_name())
                 .map(|n| n.to_string_lossy().into_owned())
                 .unwrap_or_else(|| "Untitled".to_string());
-            (dirty, name)
+            (dirty, name, tab.recovery_id.clone())
         };
 
         if is_dirty {
             match show_save_alert(&filename, self.mtm()) {
-                SaveChoice::Save => self.perform_save(Some(index)),
-                SaveChoice::DontSave => {}
+                SaveChoice::Save => {
+                    self.perform_save(Some(index));
+                    // The save was cancelled or left for a merge: keep the tab.
+                    if self.ivars().tab_manager.borrow().get(index).is_some_and(|t| t.is_dirty.get()) {
+                        return;
+                    }
+                }
+                SaveChoice::DontSave => self.ivars().autosave.discard(&recovery_id),
                 SaveChoice::Cancel => return,
             }
         }
@@ -173,6 +200,7 @@ impl AppDelegate {
                     }
                     *t.url.borrow_mut() = None;
                     t.is_dirty.set(false);
+                    t.saved_text.borrow_mut().clear();
                     // Re-add the scroll view (we removed it above).
                     let content = self.ivars().window.get().unwrap().contentView().unwrap();
                     t.scroll_view.setFrame(self.content_frame());
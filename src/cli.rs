@@ -0,0 +1,65 @@
+//! Command-line entry points that run without starting the GUI.
+//!
+//! `mdit --format [--check] [FILE...]` rewrites Markdown files in canonical
+//! style, or formats stdin to stdout when no files are given. With
+//! `--check`, nothing is written; the changed files are listed and the exit
+//! status is 1 if any would change.
+
+use std::io::Read;
+use std::path::Path;
+
+use mdit::editor::formatting::{format_document, FormatOptions};
+
+/// Run a CLI command if `args` (without the program name) requests one.
+///
+/// Returns the process exit code, or `None` when the GUI should start.
+pub fn run(args: &[String]) -> Option<i32> {
+    match args.first().map(String::as_str) {
+        Some("--format") => Some(format_command(&args[1..])),
+        _ => None,
+    }
+}
+
+fn format_command(args: &[String]) -> i32 {
+    let check = args.iter().any(|a| a == "--check");
+    let files: Vec<&Path> = args.iter().filter(|a| *a != "--check").map(Path::new).collect();
+    let options = FormatOptions::default();
+
+    if files.is_empty() {
+        let mut input = String::new();
+        if let Err(e) = std::io::stdin().read_to_string(&mut input) {
+            eprintln!("mdit: cannot read stdin: {}", e);
+            return 2;
+        }
+        let output = format_document(&input, &options);
+        if check {
+            return i32::from(output != input);
+        }
+        print!("{}", output);
+        return 0;
+    }
+
+    let mut status = 0;
+    for path in files {
+        let input = match std::fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(e) => {
+                eprintln!("mdit: cannot read {:?}: {}", path, e);
+                status = 2;
+                continue;
+            }
+        };
+        let output = format_document(&input, &options);
+        if output == input {
+            continue;
+        }
+        if check {
+            println!("{}", path.display());
+            status = status.max(1);
+        } else if let Err(e) = std::fs::write(path, output) {
+            eprintln!("mdit: cannot save {:?}: {}", path, e);
+            status = 2;
+        }
+    }
+    status
+}
@@ -0,0 +1,209 @@
+//! Moving lines, list items and heading sections up or down.
+//!
+//! The range computations walk the span tree so a list item travels with
+//! its nested children and a heading with everything below it, the way the
+//! reader sees the document. Offsets are UTF-8 bytes; every range covers
+//! whole lines and excludes the trailing newline.
+
+use crate::editor::formatting::line_bounds;
+use crate::markdown::parser::{MarkdownSpan, NodeKind};
+
+/// What a move acts on, chosen from the caret position.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MoveUnit {
+    /// The selected lines (or the caret line).
+    Lines,
+    /// A list item including its nested lists and continuation lines.
+    ListItem,
+    /// A heading plus everything up to the next heading of equal or higher level.
+    Section,
+}
+
+/// The result of a move: replace `range` with `replacement`, then select
+/// `selection` (byte offsets into the edited text).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlockMoveEdit {
+    pub range: (usize, usize),
+    pub replacement: String,
+    pub selection: (usize, usize),
+}
+
+/// Top-level headings as `(line start, level)`, in document order.
+fn heading_lines(text: &str, spans: &[MarkdownSpan]) -> Vec<(usize, u8)> {
+    spans
+        .iter()
+        .filter_map(|span| match span.kind {
+            NodeKind::Heading { level } => Some((line_bounds(text, span.source_range.0).0, level)),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Shrink `[start, end)` so it ends at the last non-blank line.
+fn trim_trailing_blank_lines(text: &str, start: usize, end: usize) -> usize {
+    start + text[start..end].trim_end().len()
+}
+
+/// Whole-line range of a span, without the trailing newline comrak
+/// sometimes includes.
+pub(crate) fn span_lines(text: &str, span: &MarkdownSpan) -> (usize, usize) {
+    let (start, end) = span.source_range;
+    let end = end.min(text.len());
+    let last = start + text[start..end].trim_end_matches('\n').len();
+    (line_bounds(text, start).0, line_bounds(text, last).1)
+}
+
+/// The range of the heading section starting on the line of `pos`:
+/// the heading and everything up to the next heading of equal or higher
+/// level, minus trailing blank lines. Returns `(range, level)`.
+pub fn section_range(text: &str, spans: &[MarkdownSpan], pos: usize) -> Option<((usize, usize), u8)> {
+    let headings = heading_lines(text, spans);
+    let heading = spans.iter().find(|span| {
+        matches!(span.kind, NodeKind::Heading { .. })
+            && pos >= line_bounds(text, span.source_range.0).0
+            && pos <= span.source_range.1
+    })?;
+    let start = line_bounds(text, heading.source_range.0).0;
+    let index = headings.iter().position(|&(line, _)| line == start)?;
+    let level = headings[index].1;
+    let end = headings[index + 1..]
+        .iter()
+        .find(|&&(_, l)| l <= level)
+        .map_or(text.len(), |&(line, _)| line);
+    Some(((start, trim_trailing_blank_lines(text, start, end)), level))
+}
+
+/// The innermost list item containing `pos`, with its parent list's items.
+fn find_item(spans: &[MarkdownSpan], pos: usize) -> Option<(&[MarkdownSpan], usize)> {
+    for span in spans {
+        let (start, end) = span.source_range;
+        if pos < start || pos > end {
+            continue;
+        }
+        if span.kind == NodeKind::List {
+            for (i, item) in span.children.iter().enumerate() {
+                let (s, e) = item.source_range;
+                if pos >= s && pos <= e {
+                    return find_item(&item.children, pos).or(Some((&span.children, i)));
+                }
+            }
+        }
+        if let Some(found) = find_item(&span.children, pos) {
+            return Some(found);
+        }
+    }
+    None
+}
+
+/// The range of the innermost list item at `pos`, including nested items.
+pub fn list_item_range(text: &str, spans: &[MarkdownSpan], pos: usize) -> Option<(usize, usize)> {
+    let (items, i) = find_item(spans, pos)?;
+    Some(span_lines(text, &items[i]))
+}
+
+/// The whole lines touched by `selection`. A selection ending at the very
+/// start of a line does not include that line.
+pub fn selected_lines(text: &str, selection: (usize, usize)) -> (usize, usize) {
+    let (start, mut end) = selection;
+    if end > start && text[..end].ends_with('\n') {
+        end -= 1;
+    }
+    (line_bounds(text, start).0, line_bounds(text, end).1)
+}
+
+/// Decide what Move Up / Move Down acts on and return its range.
+///
+/// A multi-line selection always moves lines. Otherwise the caret's heading
+/// section or list item is moved as a unit, falling back to the caret line.
+pub fn move_unit_range(
+    text: &str,
+    spans: &[MarkdownSpan],
+    selection: (usize, usize),
+) -> (MoveUnit, (usize, usize)) {
+    let lines = selected_lines(text, selection);
+    if text[lines.0..lines.1].contains('\n') {
+        return (MoveUnit::Lines, lines);
+    }
+    if let Some((range, _)) = section_range(text, spans, selection.0) {
+        return (MoveUnit::Section, range);
+    }
+    if let Some(range) = list_item_range(text, spans, item_pos(text, selection.0)) {
+        return (MoveUnit::ListItem, range);
+    }
+    (MoveUnit::Lines, lines)
+}
+
+/// The block the unit at `range` swaps with when moving up or down.
+fn neighbour(
+    text: &str,
+    spans: &[MarkdownSpan],
+    unit: MoveUnit,
+    range: (usize, usize),
+    pos: usize,
+    up: bool,
+) -> Option<(usize, usize)> {
+    match unit {
+        MoveUnit::Lines if up => (range.0 > 0).then(|| line_bounds(text, range.0 - 1)),
+        MoveUnit::Lines => (range.1 < text.len()).then(|| line_bounds(text, range.1 + 1)),
+        MoveUnit::ListItem => {
+            let (items, i) = find_item(spans, pos)?;
+            let j = if up { i.checked_sub(1)? } else { i + 1 };
+            items.get(j).map(|item| span_lines(text, item))
+        }
+        MoveUnit::Section => {
+            let (_, level) = section_range(text, spans, range.0)?;
+            let headings = heading_lines(text, spans);
+            let index = headings.iter().position(|&(line, _)| line == range.0)?;
+            let sibling = if up {
+                headings[..index].iter().rev().find(|&&(_, l)| l <= level)?
+            } else {
+                headings[index + 1..].iter().find(|&&(_, l)| l <= level)?
+            };
+            if sibling.1 != level {
+                return None;
+            }
+            section_range(text, spans, sibling.0).map(|(r, _)| r)
+        }
+    }
+}
+
+/// The caret position used to look up its list item: a caret in the
+/// indentation of a nested item counts as being on that item.
+fn item_pos(text: &str, pos: usize) -> usize {
+    let (start, end) = line_bounds(text, pos);
+    let line = &text[start..end];
+    pos.max(start + (line.len() - line.trim_start().len()))
+}
+
+/// Move the unit at `selection` one step up or down, swapping it with the
+/// neighbouring line, sibling list item or sibling section. Blank lines
+/// between the two stay where they are. The selection travels with the
+/// moved text. Returns `None` at the edge of the document or list, or when
+/// a section has no sibling at its level.
+pub fn move_block_edit(
+    text: &str,
+    spans: &[MarkdownSpan],
+    selection: (usize, usize),
+    up: bool,
+) -> Option<BlockMoveEdit> {
+    let (unit, moving) = move_unit_range(text, spans, selection);
+    let other = neighbour(text, spans, unit, moving, item_pos(text, selection.0), up)?;
+    let (first, second) = if up { (other, moving) } else { (moving, other) };
+    if first.1 > second.0 {
+        return None;
+    }
+
+    let between = &text[first.1..second.0];
+    let replacement = format!("{}{between}{}", &text[second.0..second.1], &text[first.0..first.1]);
+    let new_start = if up {
+        first.0
+    } else {
+        first.0 + (second.1 - second.0) + between.len()
+    };
+    let shift = |pos: usize| new_start + (pos - moving.0);
+    Some(BlockMoveEdit {
+        range: (first.0, second.1),
+        replacement,
+        selection: (shift(selection.0), shift(selection.1)),
+    })
+}
@@ -6,13 +6,24 @@ use crate::markdown::parser::{MarkdownSpan, NodeKind};
 /// skipped — we only return formatting spans like `Strong`, `Emph`,
 /// `Code`, `Heading`, etc.
 pub fn find_containing_span(spans: &[MarkdownSpan], pos: usize) -> Option<&MarkdownSpan> {
+    find_containing_span_where(spans, pos, &is_interesting)
+}
+
+/// Return the innermost span that contains `pos` and whose kind passes
+/// `accept`. Spans that fail it don't hide their ancestors: asking for
+/// `Strong` with the caret in `**a _b_**` finds the `Strong`.
+pub fn find_containing_span_where<'a>(
+    spans: &'a [MarkdownSpan],
+    pos: usize,
+    accept: &impl Fn(&NodeKind) -> bool,
+) -> Option<&'a MarkdownSpan> {
     for span in spans {
         if pos >= span.source_range.0 && pos <= span.source_range.1 {
             // Check children first (prefer the innermost match)
-            if let Some(inner) = find_containing_span(&span.children, pos) {
+            if let Some(inner) = find_containing_span_where(&span.children, pos, accept) {
                 return Some(inner);
             }
-            if is_interesting(&span.kind) {
+            if accept(&span.kind) {
                 return Some(span);
             }
         }
@@ -26,6 +37,7 @@ fn is_interesting(kind: &NodeKind) -> bool {
         kind,
         NodeKind::Text
             | NodeKind::Other
+            | NodeKind::LineBreak
             | NodeKind::Paragraph
             | NodeKind::List
             | NodeKind::Item
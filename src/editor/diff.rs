@@ -0,0 +1,125 @@
+//! Line-based diffs between two versions of a document.
+//!
+//! Lines keep their terminators, so concatenating the `Same` and `Added`
+//! lines of a diff rebuilds the new text exactly, and `Same` plus `Removed`
+//! rebuilds the old one.
+
+/// Above this many lines × lines the middle of a diff is not aligned; it
+/// is reported as removed and re-added instead.
+const MAX_CELLS: usize = 4_000_000;
+
+/// One line of a diff.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiffLine<'a> {
+    Same(&'a str),
+    Removed(&'a str),
+    Added(&'a str),
+}
+
+/// Lines of `text`, each with its `\n` (the last may lack one).
+pub fn lines(text: &str) -> Vec<&str> {
+    text.split_inclusive('\n').collect()
+}
+
+/// The shortest edit turning `old` into `new`, as a longest common
+/// subsequence of lines. Removed lines come before added ones within each
+/// changed run.
+pub fn line_diff<'a>(old: &'a str, new: &'a str) -> Vec<DiffLine<'a>> {
+    let (a, b) = (lines(old), lines(new));
+    let prefix = a.iter().zip(&b).take_while(|(x, y)| x == y).count();
+    let suffix = a[prefix..]
+        .iter()
+        .rev()
+        .zip(b[prefix..].iter().rev())
+        .take_while(|(x, y)| x == y)
+        .count();
+    let (mid_a, mid_b) = (&a[prefix..a.len() - suffix], &b[prefix..b.len() - suffix]);
+
+    let mut out: Vec<DiffLine> = a[..prefix].iter().map(|l| DiffLine::Same(l)).collect();
+    if mid_a.len().saturating_mul(mid_b.len()) > MAX_CELLS {
+        out.extend(mid_a.iter().map(|l| DiffLine::Removed(l)));
+        out.extend(mid_b.iter().map(|l| DiffLine::Added(l)));
+    } else {
+        out.extend(lcs_diff(mid_a, mid_b));
+    }
+    out.extend(a[a.len() - suffix..].iter().map(|l| DiffLine::Same(l)));
+    out
+}
+
+/// Render a diff for display: `- ` and `+ ` prefix changed lines, up to
+/// `context` unchanged lines surround each change, and `…` marks skipped
+/// stretches. Empty when nothing changed.
+pub fn render_diff(diff: &[DiffLine], context: usize) -> String {
+    let changed: Vec<usize> = diff
+        .iter()
+        .enumerate()
+        .filter(|(_, l)| !matches!(l, DiffLine::Same(_)))
+        .map(|(i, _)| i)
+        .collect();
+    if changed.is_empty() {
+        return String::new();
+    }
+    let near_change = |i: usize| {
+        let at = changed.partition_point(|&c| c < i.saturating_sub(context));
+        changed.get(at).is_some_and(|&c| c <= i + context)
+    };
+
+    let mut out = String::new();
+    let mut skipped = false;
+    for (i, line) in diff.iter().enumerate() {
+        if !near_change(i) {
+            skipped = true;
+            continue;
+        }
+        if skipped {
+            out.push_str("\u{2026}\n");
+            skipped = false;
+        }
+        let (prefix, text) = match line {
+            DiffLine::Same(t) => ("  ", t),
+            DiffLine::Removed(t) => ("- ", t),
+            DiffLine::Added(t) => ("+ ", t),
+        };
+        out.push_str(prefix);
+        out.push_str(text.trim_end_matches('\n'));
+        out.push('\n');
+    }
+    if skipped {
+        out.push_str("\u{2026}\n");
+    }
+    out
+}
+
+fn lcs_diff<'a>(a: &[&'a str], b: &[&'a str]) -> Vec<DiffLine<'a>> {
+    // table[i][j]: length of the LCS of a[i..] and b[j..].
+    let width = b.len() + 1;
+    let mut table = vec![0u32; (a.len() + 1) * width];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            table[i * width + j] = if a[i] == b[j] {
+                table[(i + 1) * width + j + 1] + 1
+            } else {
+                table[(i + 1) * width + j].max(table[i * width + j + 1])
+            };
+        }
+    }
+
+    let mut out = Vec::with_capacity(a.len() + b.len());
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() && j < b.len() {
+        if a[i] == b[j] {
+            out.push(DiffLine::Same(a[i]));
+            i += 1;
+            j += 1;
+        } else if table[(i + 1) * width + j] >= table[i * width + j + 1] {
+            out.push(DiffLine::Removed(a[i]));
+            i += 1;
+        } else {
+            out.push(DiffLine::Added(b[j]));
+            j += 1;
+        }
+    }
+    out.extend(a[i..].iter().map(|l| DiffLine::Removed(l)));
+    out.extend(b[j..].iter().map(|l| DiffLine::Added(l)));
+    out
+}
@@ -7,6 +7,7 @@ use objc2::rc::Retained;
 use objc2_app_kit::{NSScrollView, NSTextView};
 use objc2_foundation::{MainThreadMarker, NSRect};
 
+use crate::editor::recovery;
 use crate::editor::text_storage::MditEditorDelegate;
 use crate::editor::text_view::create_editor_view;
 use crate::editor::view_mode::ViewMode;
@@ -21,8 +22,13 @@ pub struct DocumentState {
     pub url: RefCell<Option<PathBuf>>,
     /// True when content differs from the last saved version.
     pub is_dirty: Cell<bool>,
+    /// Text as last loaded from or saved to disk — the base when merging
+    /// changes made to the file by another program.
+    pub saved_text: RefCell<String>,
     /// Current view mode: Viewer (rendered, read-only) or Editor (raw, editable).
     pub mode: Cell<ViewMode>,
+    /// Names this document's autosave snapshot in the recovery directory.
+    pub recovery_id: String,
 }
 
 impl DocumentState {
@@ -37,7 +43,9 @@ impl DocumentState {
             editor_delegate,
             url: RefCell::new(None),
             is_dirty: Cell::new(false),
+            saved_text: RefCell::new(String::new()),
             mode: Cell::new(ViewMode::Viewer),
+            recovery_id: recovery::new_id(),
         }
     }
 }
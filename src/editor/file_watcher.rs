@@ -0,0 +1,79 @@
+//! Watches the files of open documents for changes made by other programs.
+//!
+//! Each file's parent directory is watched rather than the file itself, so
+//! a save that replaces the file (write to a temporary, then rename — what
+//! most editors and `git` do) is still seen. Events arrive on the watcher's
+//! own thread and queue up until [`FileWatcher::changed`] drains them.
+
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{channel, Receiver};
+
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+
+pub struct FileWatcher {
+    watcher: RecommendedWatcher,
+    events: Receiver<notify::Result<Event>>,
+    /// Watched file, by the canonical form event paths arrive in.
+    files: HashMap<PathBuf, PathBuf>,
+    /// Watched directories.
+    dirs: HashSet<PathBuf>,
+}
+
+impl FileWatcher {
+    pub fn new() -> notify::Result<Self> {
+        let (tx, events) = channel();
+        let watcher = notify::recommended_watcher(move |event| {
+            let _ = tx.send(event);
+        })?;
+        Ok(Self { watcher, events, files: HashMap::new(), dirs: HashSet::new() })
+    }
+
+    /// Watch exactly `paths`: start on new ones, stop on ones no longer
+    /// listed. Cheap when nothing changed, so it can run on every poll.
+    pub fn set_files<'a>(&mut self, paths: impl IntoIterator<Item = &'a Path>) {
+        let files: HashMap<PathBuf, PathBuf> =
+            paths.into_iter().map(|p| (canonical(p), p.to_path_buf())).collect();
+        let dirs: HashSet<PathBuf> =
+            files.keys().filter_map(|p| p.parent().map(Path::to_path_buf)).collect();
+        for dir in self.dirs.difference(&dirs) {
+            let _ = self.watcher.unwatch(dir);
+        }
+        for dir in dirs.difference(&self.dirs) {
+            if let Err(e) = self.watcher.watch(dir, RecursiveMode::NonRecursive) {
+                eprintln!("mdit: cannot watch {:?}: {}", dir, e);
+            }
+        }
+        self.files = files;
+        self.dirs = dirs;
+    }
+
+    /// The watched files changed, created or replaced since the last call,
+    /// each once, as they were given to [`set_files`](Self::set_files).
+    pub fn changed(&self) -> Vec<PathBuf> {
+        let mut out: Vec<PathBuf> = Vec::new();
+        for event in self.events.try_iter().flatten() {
+            if matches!(event.kind, EventKind::Access(_)) {
+                continue;
+            }
+            for path in &event.paths {
+                if let Some(file) = self.files.get(&canonical(path)) {
+                    if !out.contains(file) {
+                        out.push(file.clone());
+                    }
+                }
+            }
+        }
+        out
+    }
+}
+
+/// `path` with its directory resolved (`/var` → `/private/var` on macOS),
+/// matching the form the platform reports events in. The file itself may
+/// be missing mid-replace, so only the parent is canonicalized.
+fn canonical(path: &Path) -> PathBuf {
+    match (path.parent().and_then(|d| d.canonicalize().ok()), path.file_name()) {
+        (Some(dir), Some(name)) => dir.join(name),
+        _ => path.to_path_buf(),
+    }
+}
@@ -0,0 +1,228 @@
+//! Find in Folder: run a [`Searcher`] over every Markdown file under a
+//! directory.
+//!
+//! The walk honours `.gitignore`, `.ignore` and hidden-file rules (via the
+//! `ignore` crate) whether or not the folder is a git checkout, and visits
+//! files in path order so results are stable. [`FolderSearch`] runs the walk
+//! on a background thread and streams one [`FileResult`] per matching file;
+//! the UI drains them with [`FolderSearch::poll`]. Offsets are UTF-8 bytes.
+
+use std::cell::Cell;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::sync::Arc;
+
+use crate::editor::search::{SearchScope, Searcher};
+use crate::markdown::parser::parse;
+
+/// Preview text kept before a match on long lines, in characters.
+const PREVIEW_LEAD: usize = 40;
+/// Maximum preview length, in characters.
+const PREVIEW_MAX: usize = 120;
+
+/// One match in a file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LineHit {
+    /// 0-based line number.
+    pub line: usize,
+    /// Byte range of the match within its line.
+    pub columns: (usize, usize),
+    /// The line, trimmed and shortened around the match.
+    pub preview: String,
+    /// Byte range of the match within `preview`.
+    pub preview_range: (usize, usize),
+}
+
+/// Every match in one file, in document order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileResult {
+    pub path: PathBuf,
+    pub hits: Vec<LineHit>,
+}
+
+/// Messages streamed from a running [`FolderSearch`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FolderSearchEvent {
+    File(FileResult),
+    /// The walk ended; `files` Markdown files were searched.
+    Finished { files: usize, cancelled: bool },
+    /// The search thread went away without finishing (it panicked).
+    Failed,
+}
+
+/// Whether `path` has a `.md` or `.markdown` extension (any case).
+pub fn is_markdown(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| e.eq_ignore_ascii_case("md") || e.eq_ignore_ascii_case("markdown"))
+}
+
+/// Markdown files under `root`, in path order, skipping ignored and hidden
+/// entries.
+pub fn markdown_files(root: &Path) -> impl Iterator<Item = PathBuf> {
+    ignore::WalkBuilder::new(root)
+        .require_git(false)
+        .sort_by_file_path(|a, b| a.cmp(b))
+        .build()
+        .filter_map(Result::ok)
+        .filter(|entry| entry.file_type().is_some_and(|t| t.is_file()))
+        .map(ignore::DirEntry::into_path)
+        .filter(|path| is_markdown(path))
+}
+
+/// Every match of `searcher` in `text` as a line hit.
+pub fn search_text(searcher: &Searcher, text: &str) -> Vec<LineHit> {
+    let spans = if searcher.options().scope == SearchScope::All { Vec::new() } else { parse(text) };
+    let line_starts: Vec<usize> = std::iter::once(0)
+        .chain(text.match_indices('\n').map(|(i, _)| i + 1))
+        .collect();
+
+    searcher
+        .find_all(text, &spans)
+        .into_iter()
+        .map(|m| {
+            let line = line_starts.partition_point(|&s| s <= m.range.0) - 1;
+            let start = line_starts[line];
+            let end = text[start..].find('\n').map_or(text.len(), |i| start + i);
+            let line_text = text[start..end].trim_end_matches('\r');
+            // A match may run past the end of its line (or start on the
+            // `\r\n` that ends it); the hit stops there.
+            let column = |pos: usize| (pos - start).min(line_text.len());
+            let columns = (column(m.range.0), column(m.range.1));
+            let (preview, preview_range) = preview(line_text, columns);
+            LineHit { line, columns, preview, preview_range }
+        })
+        .collect()
+}
+
+/// Search the file at `path`. `None` if it has no matches or can't be read.
+pub fn search_file(searcher: &Searcher, path: &Path) -> Option<FileResult> {
+    let text = std::fs::read_to_string(path).ok()?;
+    let hits = search_text(searcher, &text);
+    (!hits.is_empty()).then(|| FileResult { path: path.to_path_buf(), hits })
+}
+
+/// Search every Markdown file under `root`, calling `on_result` for each
+/// file with matches. Checks `cancel` before each file. Returns the number
+/// of files searched.
+pub fn search_folder(
+    root: &Path,
+    searcher: &Searcher,
+    cancel: &AtomicBool,
+    mut on_result: impl FnMut(FileResult),
+) -> usize {
+    let mut files = 0;
+    for path in markdown_files(root) {
+        if cancel.load(Ordering::Relaxed) {
+            break;
+        }
+        files += 1;
+        if let Some(result) = search_file(searcher, &path) {
+            on_result(result);
+        }
+    }
+    files
+}
+
+/// Byte range in `text` for `hit`, clamped to what is there now — the
+/// document may have changed since the file was searched.
+pub fn hit_range(text: &str, hit: &LineHit) -> (usize, usize) {
+    let start: usize = text.split_inclusive('\n').take(hit.line).map(str::len).sum();
+    let line = text[start..].split('\n').next().unwrap_or("");
+    let clamp = |col: usize| {
+        let mut col = col.min(line.len());
+        while !line.is_char_boundary(col) {
+            col -= 1;
+        }
+        start + col
+    };
+    (clamp(hit.columns.0), clamp(hit.columns.1))
+}
+
+/// A running background search. Dropping it cancels the walk.
+pub struct FolderSearch {
+    cancel: Arc<AtomicBool>,
+    events: Receiver<FolderSearchEvent>,
+    /// Whether the final event (finished or failed) has been polled.
+    done: Cell<bool>,
+}
+
+impl FolderSearch {
+    /// Start searching `root` on a new thread.
+    pub fn start(root: PathBuf, searcher: Searcher) -> Self {
+        let cancel = Arc::new(AtomicBool::new(false));
+        let (tx, events) = mpsc::channel();
+        let flag = Arc::clone(&cancel);
+        std::thread::spawn(move || {
+            let files = search_folder(&root, &searcher, &flag, |result| {
+                // The receiver is gone: nobody wants the rest.
+                if tx.send(FolderSearchEvent::File(result)).is_err() {
+                    flag.store(true, Ordering::Relaxed);
+                }
+            });
+            let cancelled = flag.load(Ordering::Relaxed);
+            let _ = tx.send(FolderSearchEvent::Finished { files, cancelled });
+        });
+        Self { cancel, events, done: Cell::new(false) }
+    }
+
+    /// Ask the walk to stop before the next file.
+    pub fn cancel(&self) {
+        self.cancel.store(true, Ordering::Relaxed);
+    }
+
+    /// Events that have arrived since the last poll, without blocking. If
+    /// the search thread dies before sending `Finished`, the last poll ends
+    /// with `Failed` instead, so callers always see one final event.
+    pub fn poll(&self) -> Vec<FolderSearchEvent> {
+        let mut out = Vec::new();
+        loop {
+            match self.events.try_recv() {
+                Ok(event) => {
+                    if matches!(event, FolderSearchEvent::Finished { .. }) {
+                        self.done.set(true);
+                    }
+                    out.push(event);
+                }
+                Err(TryRecvError::Empty) => return out,
+                Err(TryRecvError::Disconnected) => {
+                    if !self.done.replace(true) {
+                        out.push(FolderSearchEvent::Failed);
+                    }
+                    return out;
+                }
+            }
+        }
+    }
+
+    /// Block until the next event; `None` once the search has finished and
+    /// every event has been taken.
+    pub fn recv(&self) -> Option<FolderSearchEvent> {
+        self.events.recv().ok()
+    }
+}
+
+impl Drop for FolderSearch {
+    fn drop(&mut self) {
+        self.cancel();
+    }
+}
+
+/// Trim leading whitespace and cut long lines to a window around the match,
+/// marking cuts with "…". Returns the preview and the match range within it.
+fn preview(line: &str, (start, end): (usize, usize)) -> (String, (usize, usize)) {
+    let indent = line.len() - line.trim_start().len();
+    let mut from = indent.min(start);
+    if line[from..start].chars().count() > PREVIEW_LEAD {
+        from = line[..start].char_indices().rev().nth(PREVIEW_LEAD - 1).map_or(from, |(i, _)| i);
+    }
+    let mut to = line[from..].char_indices().nth(PREVIEW_MAX).map_or(line.len(), |(i, _)| from + i);
+    to = to.max(end);
+
+    let prefix = if from > indent { "\u{2026}" } else { "" };
+    let suffix = if to < line.len() { "\u{2026}" } else { "" };
+    let preview = format!("{prefix}{}{suffix}", &line[from..to]);
+    let shift = prefix.len() + start - from;
+    (preview, (shift, shift + end - start))
+}
@@ -3,16 +3,39 @@
 //! All functions are free of AppKit dependencies and operate on plain `&str`,
 //! making them easy to unit-test.
 
+use crate::editor::cursor_tracker::find_containing_span_where;
+use crate::editor::html_to_markdown::decode_entities;
+use crate::editor::renderer::TableInfo;
+use crate::markdown::parser::{make_options, parse, MarkdownSpan, NodeKind};
+
 // ---------------------------------------------------------------------------
 // Block-format helpers
 // ---------------------------------------------------------------------------
 
 /// Known block-level prefixes, longest first so `### ` is matched before `# `.
-const BLOCK_PREFIXES: &[&str] = &["- [ ] ", "- [x] ", "### ", "## ", "# ", "1. ", "> ", "- "];
+const BLOCK_PREFIXES: &[&str] = &[
+    "- [ ] ", "- [x] ", "###### ", "##### ", "#### ", "### ", "## ", "# ", "1. ", "> ", "- ",
+];
 
 /// Detect which block-level prefix (if any) a line starts with.
+///
+/// Any ordered-list number (`2. `, `10. `) is reported as `"1. "`.
 pub fn detect_block_prefix(line: &str) -> Option<&'static str> {
-    BLOCK_PREFIXES.iter().copied().find(|p| line.starts_with(p))
+    split_block_prefix(line).0
+}
+
+/// Split a line into its block-level prefix (if any) and the remaining content.
+fn split_block_prefix(line: &str) -> (Option<&'static str>, &str) {
+    if let Some(p) = BLOCK_PREFIXES.iter().copied().find(|p| line.starts_with(p)) {
+        return (Some(p), &line[p.len()..]);
+    }
+    let digits = line.bytes().take_while(u8::is_ascii_digit).count();
+    if (1..=9).contains(&digits) {
+        if let Some(content) = line[digits..].strip_prefix(". ") {
+            return (Some("1. "), content);
+        }
+    }
+    (None, line)
 }
 
 /// Set the block format of a line.
@@ -22,11 +45,7 @@ pub fn detect_block_prefix(line: &str) -> Option<&'static str> {
 /// * No prefix and `desired` is non-empty → **apply**.
 /// * `desired` is `""` → strip any prefix (Normal button).
 pub fn set_block_format(line: &str, desired: &str) -> String {
-    let current = detect_block_prefix(line);
-    let content = match current {
-        Some(p) => &line[p.len()..],
-        None => line,
-    };
+    let (current, content) = split_block_prefix(line);
 
     // Toggle off: line already has the desired prefix.
     if let Some(cur) = current {
@@ -43,6 +62,44 @@ pub fn set_block_format(line: &str, desired: &str) -> String {
     }
 }
 
+/// Set the block format of every line in `text` (a multi-line selection).
+///
+/// Toggles consistently across the selection: if every non-blank line already
+/// has `desired`, all are switched off; otherwise all are switched to
+/// `desired`.  Leading indentation is kept so nested items stay nested, blank
+/// lines are left alone, and ordered lists are numbered sequentially per
+/// nesting level.
+pub fn set_block_format_lines(text: &str, desired: &str) -> String {
+    let lines: Vec<&str> = text.split('\n').collect();
+    let all_on = !desired.is_empty()
+        && lines
+            .iter()
+            .filter(|l| !l.trim().is_empty())
+            .all(|l| split_block_prefix(&l[leading_ws(l)..]).0 == Some(desired));
+
+    let formatted: Vec<String> = lines
+        .iter()
+        .map(|line| {
+            if line.trim().is_empty() {
+                return line.to_string();
+            }
+            let indent = leading_ws(line);
+            let (_, content) = split_block_prefix(&line[indent..]);
+            if all_on || desired.is_empty() {
+                format!("{}{}", &line[..indent], content)
+            } else {
+                format!("{}{}{}", &line[..indent], desired, content)
+            }
+        })
+        .collect();
+
+    if desired == "1. " && !all_on {
+        renumber_list_lines(&formatted).join("\n")
+    } else {
+        formatted.join("\n")
+    }
+}
+
 // ---------------------------------------------------------------------------
 // Inline-format helpers
 // ---------------------------------------------------------------------------
@@ -201,11 +258,340 @@ pub fn compute_inline_toggle(
     }
 }
 
+// ---------------------------------------------------------------------------
+// AST-aware inline toggle
+// ---------------------------------------------------------------------------
+
+/// Result of [`inline_toggle_edit`]: one replacement plus the selection to
+/// restore afterwards.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InlineToggleEdit {
+    /// Byte range `[start, end)` of the original text to replace.
+    pub range: (usize, usize),
+    /// The text that replaces `range`.
+    pub replacement: String,
+    /// Selection `[start, end)` in the post-edit document (equal for a caret).
+    pub selection: (usize, usize),
+}
+
+/// The AST node kind produced by an inline marker.
+fn inline_marker_kind(marker: &str) -> Option<NodeKind> {
+    match marker {
+        "**" => Some(NodeKind::Strong),
+        "_" | "*" => Some(NodeKind::Emph),
+        "`" => Some(NodeKind::Code),
+        "~~" => Some(NodeKind::Strikethrough),
+        "==" => Some(NodeKind::Highlight),
+        "__" => Some(NodeKind::Underline),
+        "~" => Some(NodeKind::Subscript),
+        "^" => Some(NodeKind::Superscript),
+        _ => None,
+    }
+}
+
+/// Length in bytes of the opening (and closing) delimiter of an inline span.
+fn delimiter_len(text: &str, span: &MarkdownSpan) -> usize {
+    match span.kind {
+        NodeKind::Code => text[span.source_range.0..].bytes().take_while(|&b| b == b'`').count(),
+        NodeKind::Emph | NodeKind::Subscript | NodeKind::Superscript => 1,
+        _ => 2,
+    }
+}
+
+/// Collect the outermost spans of `kind` that overlap `[start, end)`.
+fn overlapping_spans<'a>(
+    spans: &'a [MarkdownSpan],
+    range: (usize, usize),
+    kind: &NodeKind,
+    out: &mut Vec<&'a MarkdownSpan>,
+) {
+    for span in spans {
+        if span.source_range.0 >= range.1 || span.source_range.1 <= range.0 {
+            continue;
+        }
+        if span.kind == *kind {
+            out.push(span);
+        } else {
+            overlapping_spans(&span.children, range, kind, out);
+        }
+    }
+}
+
+/// Byte range of the word touching `pos`, or `None` if there is none.
+fn word_at(text: &str, pos: usize) -> Option<(usize, usize)> {
+    let is_word = |c: char| c.is_alphanumeric() || c == '\'';
+    let start = text[..pos]
+        .char_indices()
+        .rev()
+        .take_while(|&(_, c)| is_word(c))
+        .last()
+        .map_or(pos, |(i, _)| i);
+    let end = pos + text[pos..].chars().take_while(|&c| is_word(c)).map(char::len_utf8).sum::<usize>();
+    (start < end).then_some((start, end))
+}
+
+/// Map `pos` through a sorted list of disjoint `(start, end, insert)` edits.
+///
+/// Text inserted exactly at `pos` lands before it when `after_inserts` is set
+/// (so a selection start skips an opening marker) and after it otherwise.
+fn map_through(ops: &[(usize, usize, String)], pos: usize, after_inserts: bool) -> usize {
+    let mut shift = 0isize;
+    for (start, end, insert) in ops {
+        if *end < pos || (*end == pos && (start < end || after_inserts)) {
+            shift += insert.len() as isize - (end - start) as isize;
+        } else if *start < pos {
+            // `pos` sits inside deleted text: snap to where the deletion was.
+            return (*start as isize + shift) as usize;
+        }
+    }
+    (pos as isize + shift) as usize
+}
+
+/// Fold sorted, disjoint edits into a single [`InlineToggleEdit`]. No edits
+/// at all give an empty edit that keeps the selection.
+fn splice_inline_ops(
+    text: &str,
+    mut ops: Vec<(usize, usize, String)>,
+    selection: (usize, usize),
+) -> InlineToggleEdit {
+    ops.sort_by_key(|op| (op.0, op.1));
+    let Some(first) = ops.first() else {
+        return InlineToggleEdit {
+            range: (selection.0, selection.0),
+            replacement: String::new(),
+            selection,
+        };
+    };
+    let range = (first.0, ops.iter().map(|op| op.1).max().unwrap_or(first.1));
+    let mut replacement = String::new();
+    let mut cursor = range.0;
+    for (start, end, insert) in &ops {
+        replacement.push_str(&text[cursor..*start]);
+        replacement.push_str(insert);
+        cursor = *end;
+    }
+    replacement.push_str(&text[cursor..range.1]);
+
+    let start = map_through(&ops, selection.0, true);
+    let end = if selection.0 == selection.1 { start } else { map_through(&ops, selection.1, false) };
+    InlineToggleEdit { range, replacement, selection: (start, end) }
+}
+
+/// Toggle an inline format using the parsed AST instead of nearby characters.
+///
+/// `spans` must be the parse of `text`; `selection` is a byte range.
+///
+/// * Selection inside a span of the marker's kind → the selected part is
+///   removed from the span, splitting it if needed; selecting the whole
+///   span unwraps it completely.
+/// * Selection overlapping existing spans of that kind → one span covering
+///   the union is produced (the inner markers are dropped).
+/// * Otherwise the selection is wrapped.
+///
+/// A caret acts on the word under it as if that word were selected. With
+/// no word there, a caret inside a span unwraps the span and anywhere else
+/// inserts an empty marker pair.
+///
+/// Markers are kept outside surrounding whitespace so the result still
+/// parses as the intended span.
+pub fn inline_toggle_edit(
+    text: &str,
+    spans: &[MarkdownSpan],
+    selection: (usize, usize),
+    marker: &str,
+) -> InlineToggleEdit {
+    let (sel_start, sel_end) = selection;
+    let Some(kind) = inline_marker_kind(marker) else {
+        let ops = vec![(sel_start, sel_start, marker.to_string()), (sel_end, sel_end, marker.to_string())];
+        return splice_inline_ops(text, ops, selection);
+    };
+    if sel_start != sel_end {
+        return splice_inline_ops(text, inline_toggle_ops(text, spans, selection, &kind, marker), selection);
+    }
+
+    // ── Caret only: toggle the word under the caret ─────────────────────────
+    let caret = sel_start;
+    let range = match word_at(text, caret) {
+        Some(word) => word,
+        None if containing_span(spans, selection, &kind).is_some() => selection,
+        None => {
+            let ops = vec![(caret, caret, format!("{}{}", marker, marker))];
+            let inside = caret + marker.len();
+            return InlineToggleEdit { selection: (inside, inside), ..splice_inline_ops(text, ops, selection) };
+        }
+    };
+    let ops = inline_toggle_ops(text, spans, range, &kind, marker);
+    // A caret at the end of the word stays before a closing marker.
+    let at = map_through(&ops, caret, caret < range.1);
+    InlineToggleEdit { selection: (at, at), ..splice_inline_ops(text, ops, selection) }
+}
+
+/// The innermost span of `kind` containing all of `range`.
+fn containing_span<'a>(spans: &'a [MarkdownSpan], range: (usize, usize), kind: &NodeKind) -> Option<&'a MarkdownSpan> {
+    find_containing_span_where(spans, range.0, &|k| k == kind).filter(|span| range.1 <= span.source_range.1)
+}
+
+/// The edits of [`inline_toggle_edit`] for `range`, sorted. An empty range
+/// only occurs inside a span of `kind`, and unwraps it.
+fn inline_toggle_ops(
+    text: &str,
+    spans: &[MarkdownSpan],
+    range: (usize, usize),
+    kind: &NodeKind,
+    marker: &str,
+) -> Vec<(usize, usize, String)> {
+    let (start, end) = range;
+    let mut ops = Vec::new();
+
+    // ── Inside an existing span: unwrap all or part of it ──────────────────
+    if let Some(span) = containing_span(spans, range, kind) {
+        let (open, close) = span.source_range;
+        let len = delimiter_len(text, span);
+        let (content_start, content_end) = (open + len, close - len);
+        let (mut a, mut b) = if start == end {
+            (content_start, content_end)
+        } else {
+            (start.clamp(content_start, content_end), end.clamp(content_start, content_end))
+        };
+        // Never split inside a nested node: move the split points to its edges.
+        for child in span.children.iter().filter(|c| c.kind != NodeKind::Text) {
+            let (child_start, child_end) = child.source_range;
+            if child_start < a && a < child_end {
+                a = child_start;
+            }
+            if child_start < b && b < child_end {
+                b = child_end;
+            }
+        }
+        let before = &text[content_start..a];
+        let after = &text[b..content_end];
+        let open_marker = text[open..content_start].to_string();
+
+        if before.trim().is_empty() {
+            ops.push((open, content_start, String::new()));
+        } else {
+            let at = content_start + before.trim_end().len();
+            ops.push((at, at, open_marker.clone()));
+        }
+        if after.trim().is_empty() {
+            ops.push((content_end, close, String::new()));
+        } else {
+            let at = content_end - after.trim_start().len();
+            ops.push((at, at, open_marker));
+        }
+        return ops;
+    }
+
+    // ── Wrap, absorbing any overlapping spans of the same kind ──────────────
+    let mut existing = Vec::new();
+    overlapping_spans(spans, range, kind, &mut existing);
+    let union_start = existing.iter().map(|s| s.source_range.0).fold(start, usize::min);
+    let union_end = existing.iter().map(|s| s.source_range.1).fold(end, usize::max);
+    let inner = &text[union_start..union_end];
+    let wrap_start = union_start + (inner.len() - inner.trim_start().len());
+    let wrap_end = union_start + inner.trim_end().len();
+    if wrap_start >= wrap_end {
+        return vec![(start, start, marker.to_string()), (end, end, marker.to_string())];
+    }
+
+    // The selection is one existing span plus surrounding whitespace: unwrap it.
+    if let [span] = existing.as_slice() {
+        let (open, close) = span.source_range;
+        if (open, close) == (wrap_start, wrap_end) {
+            let len = delimiter_len(text, span);
+            return vec![(open, open + len, String::new()), (close - len, close, String::new())];
+        }
+    }
+
+    let mut keeps_open = false;
+    let mut keeps_close = false;
+    for span in &existing {
+        let len = delimiter_len(text, span);
+        let (open, close) = span.source_range;
+        if open == wrap_start {
+            keeps_open = true;
+        } else {
+            ops.push((open, open + len, String::new()));
+        }
+        if close == wrap_end {
+            keeps_close = true;
+        } else {
+            ops.push((close - len, close, String::new()));
+        }
+    }
+    if !keeps_open {
+        ops.push((wrap_start, wrap_start, marker.to_string()));
+    }
+    if !keeps_close {
+        ops.push((wrap_end, wrap_end, marker.to_string()));
+    }
+    ops.sort_by_key(|op| (op.0, op.1));
+    ops
+}
+
 /// Compute the text for a link wrap: `prefix + selected + suffix`.
 pub fn compute_link_wrap(selected: &str, prefix: &str, suffix: &str) -> String {
     format!("{}{}{}", prefix, selected, suffix)
 }
 
+/// The trimmed clipboard text if it is a single absolute URL (`scheme://…`
+/// or `mailto:…`).
+pub fn clipboard_url(clip: &str) -> Option<&str> {
+    let url = clip.trim();
+    if url.is_empty() || url.contains(char::is_whitespace) {
+        return None;
+    }
+    let has_scheme = url.split_once("://").is_some_and(|(scheme, rest)| {
+        !rest.is_empty()
+            && scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+            && scheme.chars().all(|c| c.is_ascii_alphanumeric() || "+-.".contains(c))
+    });
+    let is_mailto = url.strip_prefix("mailto:").is_some_and(|addr| addr.contains('@'));
+    (has_scheme || is_mailto).then_some(url)
+}
+
+/// A link destination, wrapped in `<…>` when it contains whitespace, angle
+/// brackets or unbalanced parentheses.
+pub fn link_destination(url: &str) -> String {
+    let mut depth: i32 = 0;
+    let balanced = url.chars().all(|c| {
+        depth += match c {
+            '(' => 1,
+            ')' => -1,
+            _ => 0,
+        };
+        depth >= 0
+    }) && depth == 0;
+    if balanced && !url.contains(char::is_whitespace) && !url.contains(['<', '>']) {
+        url.to_string()
+    } else {
+        format!("<{}>", url.replace('<', "%3C").replace('>', "%3E"))
+    }
+}
+
+/// Pasting a URL onto a selection: turn the selection into `[selection](url)`.
+///
+/// Returns `None` (paste normally) unless the selection is non-empty, on a
+/// single line and not itself a URL, and `clip` is a URL. Whitespace at the
+/// edges of the selection stays outside the brackets.
+pub fn link_paste_edit(text: &str, selection: (usize, usize), clip: &str) -> Option<TextEdit> {
+    let url = clipboard_url(clip)?;
+    let (start, end) = selection;
+    let selected = text.get(start..end)?;
+    let label = selected.trim();
+    if label.is_empty() || selected.contains('\n') || clipboard_url(label).is_some() {
+        return None;
+    }
+    let lead = selected.len() - selected.trim_start().len();
+    let link = format!("[{label}]({})", link_destination(url));
+    let replacement = format!("{}{link}{}", &selected[..lead], &selected[lead + label.len()..]);
+    Some(TextEdit {
+        range: selection,
+        caret: start + lead + link.len(),
+        replacement,
+    })
+}
+
 /// Compute the text for a fenced code block wrap.
 ///
 /// If `selected` is empty, produces an empty fence with a blank line.
@@ -218,3 +604,1912 @@ pub fn compute_code_block_wrap(selected: &str) -> String {
         format!("{}\n{}\n{}", fence, selected, fence)
     }
 }
+
+// ---------------------------------------------------------------------------
+// Document edits
+// ---------------------------------------------------------------------------
+
+/// A single replacement in the document, computed by the multi-line helpers
+/// below.  All offsets are UTF-8 byte offsets; the caller converts them to
+/// UTF-16 when applying the edit to the text view.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextEdit {
+    /// Byte range `[start, end)` of the original text to replace.
+    pub range: (usize, usize),
+    /// The text that replaces `range`.
+    pub replacement: String,
+    /// Byte offset of the caret in the document *after* the edit.
+    pub caret: usize,
+}
+
+/// Byte range `[start, end)` of the line containing `pos`, excluding the
+/// trailing newline. A `pos` inside a multi-byte character counts as the
+/// start of that character.
+pub fn line_bounds(text: &str, pos: usize) -> (usize, usize) {
+    let mut pos = pos.min(text.len());
+    while !text.is_char_boundary(pos) {
+        pos -= 1;
+    }
+    let start = text[..pos].rfind('\n').map_or(0, |i| i + 1);
+    let end = text[pos..].find('\n').map_or(text.len(), |i| pos + i);
+    (start, end)
+}
+
+// ---------------------------------------------------------------------------
+// List helpers
+// ---------------------------------------------------------------------------
+
+/// The kind of marker that opens a list item or blockquote line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ListKind {
+    /// `- `, `* ` or `+ `.
+    Bullet(char),
+    /// `1. ` or `1) `.
+    Ordered { number: u32, delimiter: char },
+    /// `- [ ] ` / `- [x] `.
+    Task { bullet: char, checked: bool },
+    /// `> `.
+    Quote,
+}
+
+/// A list-item (or blockquote) marker found at the start of a line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ListMarker {
+    /// Byte width of the leading whitespace.
+    pub indent: usize,
+    pub kind: ListKind,
+    /// Byte offset where the item's text begins (after marker and task box).
+    pub content_start: usize,
+}
+
+impl ListMarker {
+    /// The marker to start the next sibling item with, including indentation.
+    ///
+    /// Ordered numbers are incremented and task items restart unchecked.
+    pub fn continuation(&self) -> String {
+        let indent = " ".repeat(self.indent);
+        match self.kind {
+            ListKind::Bullet(b) => format!("{}{} ", indent, b),
+            ListKind::Ordered { number, delimiter } => {
+                format!("{}{}{} ", indent, number + 1, delimiter)
+            }
+            ListKind::Task { bullet, .. } => format!("{}{} [ ] ", indent, bullet),
+            ListKind::Quote => format!("{}> ", indent),
+        }
+    }
+
+    /// Indentation a child item needs to nest under this one.
+    fn child_indent(&self) -> usize {
+        match self.kind {
+            // The task box is item content; children align with the bullet text.
+            ListKind::Task { .. } => self.indent + 2,
+            _ => self.content_start,
+        }
+    }
+}
+
+/// Parse the list or blockquote marker at the start of `line`, if any.
+pub fn parse_list_marker(line: &str) -> Option<ListMarker> {
+    let body = line.trim_start_matches([' ', '\t']);
+    let indent = line.len() - body.len();
+
+    // Markers must be followed by a space or end the line.
+    let after = |rest: &str| rest.is_empty() || rest.starts_with(' ');
+    let skip_space = |rest: &str| usize::from(rest.starts_with(' '));
+
+    let mut chars = body.chars();
+    let (kind, marker_len) = match chars.next()? {
+        '>' if after(&body[1..]) => (ListKind::Quote, 1 + skip_space(&body[1..])),
+        b @ ('-' | '*' | '+') if after(&body[1..]) && !is_thematic_break(body) => {
+            let rest = &body[1..];
+            let box_text = rest.strip_prefix(' ').unwrap_or(rest);
+            let checked = match box_text.get(..3) {
+                Some("[ ]") => Some(false),
+                Some("[x]") | Some("[X]") => Some(true),
+                _ => None,
+            };
+            match checked {
+                Some(checked) if after(&box_text[3..]) => {
+                    let len = 2 + 3 + skip_space(&box_text[3..]);
+                    (ListKind::Task { bullet: b, checked }, len)
+                }
+                _ => (ListKind::Bullet(b), 1 + skip_space(rest)),
+            }
+        }
+        '0'..='9' => {
+            let digits = body.bytes().take_while(u8::is_ascii_digit).count();
+            if digits > 9 {
+                return None;
+            }
+            let delimiter = body[digits..].chars().next()?;
+            if !matches!(delimiter, '.' | ')') || !after(&body[digits + 1..]) {
+                return None;
+            }
+            let number = body[..digits].parse().ok()?;
+            let len = digits + 1 + skip_space(&body[digits + 1..]);
+            (ListKind::Ordered { number, delimiter }, len)
+        }
+        _ => return None,
+    };
+
+    Some(ListMarker { indent, kind, content_start: indent + marker_len })
+}
+
+/// `* * *`, `- - -` and the like: three or more of one of `-`, `*` or `_`,
+/// optionally separated by spaces or tabs.
+fn is_thematic_break(body: &str) -> bool {
+    let mut marks = body.chars().filter(|c| !matches!(c, ' ' | '\t'));
+    let Some(first) = marks.next() else {
+        return false;
+    };
+    matches!(first, '-' | '*' | '_') && marks.clone().all(|c| c == first) && marks.count() >= 2
+}
+
+/// What pressing Return inside a list or blockquote line should do.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ListContinuation {
+    /// Insert this text at the caret: a newline followed by the next marker.
+    Continue(String),
+    /// The item is empty — remove its marker to end the list.
+    End,
+}
+
+/// Compute the Return behaviour for `line` with the caret at byte `caret`.
+///
+/// Returns `None` when the line is not a list item or the caret sits inside
+/// the marker, in which case a plain newline should be inserted.
+pub fn compute_list_continuation(line: &str, caret: usize) -> Option<ListContinuation> {
+    let marker = parse_list_marker(line)?;
+    if caret < marker.content_start {
+        return None;
+    }
+    if line[marker.content_start..].trim().is_empty() {
+        return Some(ListContinuation::End);
+    }
+    Some(ListContinuation::Continue(format!("\n{}", marker.continuation())))
+}
+
+/// Byte range of the contiguous list block around the line starting at
+/// `line_start`: list items plus their indented continuation lines, bounded
+/// by blank lines or unindented paragraphs.
+fn list_block_bounds(text: &str, line_start: usize) -> (usize, usize) {
+    let belongs = |line: &str| {
+        parse_list_marker(line).is_some()
+            || (line.starts_with([' ', '\t']) && !line.trim().is_empty())
+    };
+
+    let mut start = line_start;
+    while start > 0 {
+        let (prev_start, prev_end) = line_bounds(text, start - 1);
+        if !belongs(&text[prev_start..prev_end]) {
+            break;
+        }
+        start = prev_start;
+    }
+
+    let (_, mut end) = line_bounds(text, line_start);
+    while end < text.len() {
+        let (next_start, next_end) = line_bounds(text, end + 1);
+        if !belongs(&text[next_start..next_end]) {
+            break;
+        }
+        end = next_end;
+    }
+    (start, end)
+}
+
+/// Renumber ordered items so each nesting level counts up without gaps.
+///
+/// The first top-level item keeps its number (lists may start at any value);
+/// nested levels always restart at 1.  Non-list lines are left untouched.
+pub fn renumber_list_lines(lines: &[String]) -> Vec<String> {
+    // Stack of (indent, next number) — one entry per open nesting level.
+    let mut levels: Vec<(usize, Option<u32>)> = Vec::new();
+    lines
+        .iter()
+        .map(|line| {
+            let Some(marker) = parse_list_marker(line) else {
+                return line.clone();
+            };
+            if marker.kind == ListKind::Quote {
+                return line.clone();
+            }
+            while levels.last().is_some_and(|&(indent, _)| indent > marker.indent) {
+                levels.pop();
+            }
+            if levels.last().map(|&(indent, _)| indent) != Some(marker.indent) {
+                levels.push((marker.indent, None));
+            }
+            let is_top = levels.len() == 1;
+            let level = levels.last_mut().expect("level pushed above");
+
+            let ListKind::Ordered { number, delimiter } = marker.kind else {
+                level.1 = None;
+                return line.clone();
+            };
+            let n = level.1.unwrap_or(if is_top { number } else { 1 });
+            level.1 = Some(n + 1);
+            if n == number {
+                return line.clone();
+            }
+            let digits_end = line[marker.indent..]
+                .find(delimiter)
+                .map_or(marker.indent, |i| marker.indent + i);
+            format!("{}{}{}", &line[..marker.indent], n, &line[digits_end..])
+        })
+        .collect()
+}
+
+/// Indent (or outdent) the list item at `index`, together with its nested
+/// children, then renumber the block.
+///
+/// Indenting nests the item under its previous sibling; outdenting moves it
+/// to its parent's level.  Returns `false` (leaving `lines` untouched) when
+/// the line is not a list item, has no previous sibling to nest under, or is
+/// already at the top level.
+pub fn shift_list_item(lines: &mut Vec<String>, index: usize, outdent: bool) -> bool {
+    let Some(marker) = lines.get(index).and_then(|l| parse_list_marker(l)) else {
+        return false;
+    };
+    if marker.kind == ListKind::Quote {
+        return false;
+    }
+    let current = marker.indent;
+    let preceding = || {
+        lines[..index]
+            .iter()
+            .rev()
+            .filter_map(|l| parse_list_marker(l))
+            .filter(|m| m.kind != ListKind::Quote)
+    };
+
+    let target = if outdent {
+        if current == 0 {
+            return false;
+        }
+        preceding().find(|m| m.indent < current).map_or(0, |m| m.indent)
+    } else {
+        match preceding().find(|m| m.indent <= current) {
+            Some(sibling) if sibling.indent == current => sibling.child_indent(),
+            _ => return false,
+        }
+    };
+    let delta = target as isize - current as isize;
+
+    // The item and every following line indented deeper than it move together.
+    let mut end = index + 1;
+    while end < lines.len() && leading_ws(&lines[end]) > current {
+        end += 1;
+    }
+    for line in &mut lines[index..end] {
+        *line = shift_line(line, delta);
+    }
+
+    *lines = renumber_list_lines(lines);
+    true
+}
+
+/// Byte width of a line's leading whitespace.
+fn leading_ws(line: &str) -> usize {
+    line.len() - line.trim_start_matches([' ', '\t']).len()
+}
+
+/// Add (`delta > 0`) or remove (`delta < 0`) leading spaces.
+fn shift_line(line: &str, delta: isize) -> String {
+    if delta >= 0 {
+        format!("{}{}", " ".repeat(delta as usize), line)
+    } else {
+        let remove = delta.unsigned_abs().min(leading_ws(line));
+        line[remove..].to_string()
+    }
+}
+
+/// Compute the edit for Return at byte `caret` inside a list item.
+///
+/// Continues the list with the next marker and renumbers following ordered
+/// items; on an empty item, removes the marker instead.  Returns `None` when
+/// a plain newline should be inserted.
+pub fn list_newline_edit(text: &str, caret: usize) -> Option<TextEdit> {
+    let (line_start, line_end) = line_bounds(text, caret);
+    let line = &text[line_start..line_end];
+    match compute_list_continuation(line, caret - line_start)? {
+        ListContinuation::End => Some(TextEdit {
+            range: (line_start, line_end),
+            replacement: String::new(),
+            caret: line_start,
+        }),
+        ListContinuation::Continue(insert) => {
+            let (block_start, block_end) = list_block_bounds(text, line_start);
+            let block = format!(
+                "{}{}{}",
+                &text[block_start..caret],
+                insert,
+                &text[caret..block_end]
+            );
+            let lines: Vec<String> = block.split('\n').map(str::to_string).collect();
+            let renumbered = renumber_list_lines(&lines);
+
+            // The caret lands after the marker of the freshly inserted line.
+            let new_line = text[block_start..caret].matches('\n').count() + 1;
+            let line_offset: usize = renumbered[..new_line].iter().map(|l| l.len() + 1).sum();
+            let content = parse_list_marker(&renumbered[new_line]).map_or(0, |m| m.content_start);
+
+            Some(TextEdit {
+                range: (block_start, block_end),
+                replacement: renumbered.join("\n"),
+                caret: block_start + line_offset + content,
+            })
+        }
+    }
+}
+
+/// Compute the edit for Tab (`outdent == false`) or Shift-Tab on the list
+/// item containing byte `caret`.  Returns `None` when the item cannot move.
+pub fn shift_list_item_edit(text: &str, caret: usize, outdent: bool) -> Option<TextEdit> {
+    let (line_start, line_end) = line_bounds(text, caret);
+    let (block_start, block_end) = list_block_bounds(text, line_start);
+    let mut lines: Vec<String> = text[block_start..block_end]
+        .split('\n')
+        .map(str::to_string)
+        .collect();
+    let index = text[block_start..line_start].matches('\n').count();
+    let old_len = line_end - line_start;
+    if !shift_list_item(&mut lines, index, outdent) {
+        return None;
+    }
+
+    let line_offset: usize = lines[..index].iter().map(|l| l.len() + 1).sum();
+    let column = (caret - line_start) as isize + lines[index].len() as isize - old_len as isize;
+    let column = (column.max(0) as usize).min(lines[index].len());
+    Some(TextEdit {
+        range: (block_start, block_end),
+        replacement: lines.join("\n"),
+        caret: block_start + line_offset + column,
+    })
+}
+
+// ---------------------------------------------------------------------------
+// Heading sections
+// ---------------------------------------------------------------------------
+
+/// Promote (`##` → `#`) or demote (`##` → `###`) the heading at `pos` and
+/// every heading nested in its section, clamped to levels 1–6.
+///
+/// Setext headings are rewritten in ATX style. Returns `None` when the caret
+/// is not on a heading or the section's own heading is already at the limit.
+pub fn shift_section_edit(
+    text: &str,
+    spans: &[MarkdownSpan],
+    pos: usize,
+    promote: bool,
+) -> Option<TextEdit> {
+    let ((start, end), level) = crate::editor::block_move::section_range(text, spans, pos)?;
+    if (promote && level == 1) || (!promote && level == 6) {
+        return None;
+    }
+
+    let mut replacement = String::new();
+    let mut caret = None;
+    let mut last = start;
+    for span in spans {
+        let NodeKind::Heading { level } = span.kind else {
+            continue;
+        };
+        let line_start = line_bounds(text, span.source_range.0).0;
+        if line_start < start || line_start >= end {
+            continue;
+        }
+        let span_end = span.source_range.1.min(end);
+        // The section ends before trailing whitespace, which an empty
+        // heading (`# `) may consist of.
+        let line_end = line_bounds(text, span_end.saturating_sub(1).max(line_start)).1.min(end);
+        let new_level = if promote { level.saturating_sub(1).max(1) } else { (level + 1).min(6) };
+        if new_level == level {
+            continue;
+        }
+        let prefix = format!("{} ", "#".repeat(new_level as usize));
+
+        let old = &text[line_start..line_end];
+        let (first, rest) = old.split_once('\n').unwrap_or((old, ""));
+        let new = if rest.is_empty() {
+            let indent = leading_ws(first);
+            let heading = &first[indent..];
+            if heading.trim_end().bytes().all(|b| b == b'#') {
+                // Empty heading: just the hashes, no prefix to split.
+                format!("{}{}", &first[..indent], prefix.trim_end())
+            } else {
+                format!("{}{}", &first[..indent], set_block_format(heading, &prefix))
+            }
+        } else {
+            // Setext: content lines above an `===` / `---` underline.
+            let content: Vec<&str> = old.lines().map(str::trim).collect();
+            format!("{prefix}{}", content[..content.len() - 1].join(" "))
+        };
+
+        replacement.push_str(&text[last..line_start]);
+        if (line_start..=line_end).contains(&pos) {
+            let delta = new.len() as isize - old.len() as isize;
+            let in_line = (pos - line_start) as isize + if rest.is_empty() { delta } else { 0 };
+            let in_line = in_line.clamp(0, new.len() as isize) as usize;
+            caret = Some(start + replacement.len() + in_line);
+        }
+        replacement.push_str(&new);
+        last = line_end;
+    }
+    replacement.push_str(&text[last..end]);
+
+    (replacement != text[start..end]).then(|| TextEdit {
+        range: (start, end),
+        caret: caret.unwrap_or(start),
+        replacement,
+    })
+}
+
+// ---------------------------------------------------------------------------
+// Footnotes
+// ---------------------------------------------------------------------------
+
+/// Source ranges of code spans, code blocks and math, where Markdown syntax
+/// is literal.
+fn code_ranges(spans: &[MarkdownSpan], out: &mut Vec<(usize, usize)>) {
+    for span in spans {
+        match span.kind {
+            NodeKind::Code | NodeKind::CodeBlock { .. } | NodeKind::Math => out.push(span.source_range),
+            _ => code_ranges(&span.children, out),
+        }
+    }
+}
+
+/// A `[^label]` occurrence in the source.
+struct FootnoteLabel<'a> {
+    /// Byte range of the whole `[^label]`.
+    range: (usize, usize),
+    label: &'a str,
+    /// `true` for a definition (`[^label]:` at the start of a line).
+    definition: bool,
+}
+
+/// Every footnote reference and definition outside code, in source order.
+///
+/// Scans the text rather than the span tree because comrak drops
+/// definitions that nothing references.
+fn footnote_labels<'a>(text: &'a str, spans: &[MarkdownSpan]) -> Vec<FootnoteLabel<'a>> {
+    let mut code = Vec::new();
+    code_ranges(spans, &mut code);
+    let mut labels = Vec::new();
+    for (start, _) in text.match_indices("[^") {
+        if code.iter().any(|&(s, e)| start >= s && start < e) {
+            continue;
+        }
+        let rest = &text[start + 2..];
+        let Some(len) = rest.find(|c: char| c == ']' || c.is_whitespace() || c == '[') else {
+            continue;
+        };
+        if len == 0 || !rest[len..].starts_with(']') {
+            continue;
+        }
+        let end = start + 2 + len + 1;
+        let line_start = line_bounds(text, start).0;
+        let definition = text[end..].starts_with(':')
+            && text[line_start..start].len() <= 3
+            && text[line_start..start].bytes().all(|b| b == b' ');
+        labels.push(FootnoteLabel { range: (start, end), label: &rest[..len], definition });
+    }
+    labels
+}
+
+/// The two insertions that add a footnote, both positioned in the original
+/// text. Apply `definition` (at the end) before `reference` so the earlier
+/// offset stays valid.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FootnoteEdit {
+    /// Byte offset where the `[^n]` reference is inserted.
+    pub reference_at: usize,
+    pub reference: String,
+    /// The `[^n]: ` definition, appended at the end of the document.
+    pub definition: String,
+    /// Byte offset of the caret in the document after both insertions.
+    pub caret: usize,
+}
+
+/// Insert a footnote reference `[^n]` at the caret (the end of the
+/// selection), using one more than the highest numeric label in use, and
+/// append its `[^n]: ` definition to the footnote section at the end of the
+/// document. Existing trailing whitespace is kept; only the newlines still
+/// missing before the definition are added. The caret lands on the new
+/// definition, ready for typing.
+pub fn insert_footnote_edit(text: &str, spans: &[MarkdownSpan], selection: (usize, usize)) -> FootnoteEdit {
+    let labels = footnote_labels(text, spans);
+    let number = labels
+        .iter()
+        .filter_map(|f| f.label.parse::<usize>().ok())
+        .max()
+        .unwrap_or(0)
+        + 1;
+    let at = selection.1.min(text.len());
+    let body = text.trim_end();
+
+    // The document already ends in definitions when everything after the
+    // last one is blank or an indented continuation line.
+    let in_footnotes = labels.iter().rev().find(|f| f.definition).is_some_and(|f| {
+        body[f.range.0.min(body.len())..]
+            .lines()
+            .skip(1)
+            .all(|l| l.trim().is_empty() || l.starts_with([' ', '\t']))
+    });
+    let wanted: usize = match (body.is_empty(), in_footnotes) {
+        (true, _) => 0,
+        (false, true) => 1,
+        (false, false) => 2,
+    };
+    let have = text[body.len()..].matches('\n').count();
+    let mut definition = "\n".repeat(wanted.saturating_sub(have));
+    if definition.is_empty() && !text.is_empty() && !text.ends_with('\n') {
+        definition.push('\n');
+    }
+    definition.push_str(&format!("[^{number}]: "));
+
+    let reference = format!("[^{number}]");
+    let caret = text.len() + reference.len() + definition.len();
+    definition.push('\n');
+    FootnoteEdit { reference_at: at, reference, definition, caret }
+}
+
+/// Renumber numeric footnote labels `1, 2, 3…` in order of first reference.
+/// Definitions nobody references keep their relative order after those.
+/// Named labels (`[^note]`) are left alone. Returns `None` when the numbers
+/// are already in order.
+pub fn renumber_footnotes_edit(text: &str, spans: &[MarkdownSpan], caret: usize) -> Option<TextEdit> {
+    let labels = footnote_labels(text, spans);
+    let numeric = |f: &&FootnoteLabel| f.label.bytes().all(|b| b.is_ascii_digit());
+    let mut order: Vec<&str> = Vec::new();
+    for f in labels.iter().filter(numeric).filter(|f| !f.definition) {
+        if !order.contains(&f.label) {
+            order.push(f.label);
+        }
+    }
+    for f in labels.iter().filter(numeric).filter(|f| f.definition) {
+        if !order.contains(&f.label) {
+            order.push(f.label);
+        }
+    }
+
+    let changes: Vec<((usize, usize), String)> = labels
+        .iter()
+        .filter(numeric)
+        .filter_map(|f| {
+            let n = order.iter().position(|&l| l == f.label)? + 1;
+            let new = format!("[^{n}]");
+            (new != text[f.range.0..f.range.1]).then_some((f.range, new))
+        })
+        .collect();
+    let (first, last) = (changes.first()?.0 .0, changes.last()?.0 .1);
+
+    let mut replacement = String::new();
+    let mut pos = first;
+    let mut new_caret = caret;
+    for ((start, end), new) in &changes {
+        replacement.push_str(&text[pos..*start]);
+        replacement.push_str(new);
+        if caret >= *end {
+            new_caret = new_caret + new.len() - (end - start);
+        }
+        pos = *end;
+    }
+    replacement.push_str(&text[pos..last]);
+    Some(TextEdit { range: (first, last), replacement, caret: new_caret })
+}
+
+// ---------------------------------------------------------------------------
+// Auto-pairing
+// ---------------------------------------------------------------------------
+
+/// Characters that auto-insert a closer, with that closer.
+const AUTO_PAIRS: [(char, char); 6] = [('(', ')'), ('[', ']'), ('"', '"'), ('`', '`'), ('$', '$'), ('*', '*')];
+
+/// Markers that only wrap a selection; typing them never inserts a pair.
+const WRAP_ONLY: [char; 3] = ['_', '~', '='];
+
+/// An edit that also sets a (possibly non-empty) selection afterwards.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PairEdit {
+    /// Byte range `[start, end)` of the original text to replace.
+    pub range: (usize, usize),
+    pub replacement: String,
+    /// Byte range to select in the edited text.
+    pub selection: (usize, usize),
+}
+
+/// Whether typing `c` can trigger [`auto_pair_edit`] at all, so callers
+/// can skip the work for ordinary characters.
+pub fn is_auto_pair_char(c: char) -> bool {
+    AUTO_PAIRS.iter().any(|&(open, close)| c == open || c == close) || WRAP_ONLY.contains(&c)
+}
+
+fn closer_for(c: char) -> Option<char> {
+    AUTO_PAIRS.iter().find(|&&(open, _)| open == c).map(|&(_, close)| close)
+}
+
+/// The innermost code span, code block or math span strictly containing `pos`.
+fn code_span_at(spans: &[MarkdownSpan], pos: usize) -> Option<&MarkdownSpan> {
+    spans.iter().find_map(|span| {
+        let (start, end) = span.source_range;
+        if pos <= start || pos >= end {
+            return None;
+        }
+        match span.kind {
+            NodeKind::Code | NodeKind::CodeBlock { .. } | NodeKind::Math => Some(span),
+            _ => code_span_at(&span.children, pos),
+        }
+    })
+}
+
+/// Whether a symmetric marker at `pos` would open rather than close: it
+/// must not directly follow a letter or digit.
+fn opens_at(text: &str, pos: usize) -> bool {
+    !text[..pos].chars().next_back().is_some_and(char::is_alphanumeric)
+}
+
+/// Length in bytes of the run of `c` ending at `pos` and starting at `pos`.
+fn runs_around(text: &str, pos: usize, c: char) -> (usize, usize) {
+    let left = text[..pos].len() - text[..pos].trim_end_matches(c).len();
+    let right = text[pos..].len() - text[pos..].trim_start_matches(c).len();
+    (left, right)
+}
+
+/// What typing `typed` over `selection` should do instead of inserting it
+/// plainly, or `None` for a plain insert.
+///
+/// * With a single-line selection, brackets, quotes and inline markers
+///   wrap it and keep it selected, so typing `*` twice makes it bold.
+/// * Typing a closer in front of the same character steps over it.
+/// * Typing an opener before whitespace, punctuation or the end of the line
+///   inserts its closer too. Quote-like markers only pair where they can
+///   open (not after a letter or digit), `*` not at the start of a line
+///   where it is a bullet, and a backtick not after another backtick so
+///   code fences can be typed.
+/// * Between an empty `*…*` or `$…$` pair, typing the marker again grows
+///   both sides (`**|**`, `$$|$$`).
+///
+/// Inside code spans, code blocks and math nothing pairs; only the span's
+/// own closing delimiter is stepped over.
+pub fn auto_pair_edit(text: &str, spans: &[MarkdownSpan], selection: (usize, usize), typed: char) -> Option<PairEdit> {
+    let (start, end) = selection;
+    let closer = closer_for(typed);
+    let in_code = code_span_at(spans, start);
+
+    if start != end {
+        let selected = &text[start..end];
+        if in_code.is_some() || selected.contains('\n') {
+            return None;
+        }
+        let close = closer.or_else(|| WRAP_ONLY.contains(&typed).then_some(typed))?;
+        let open_len = typed.len_utf8();
+        return Some(PairEdit {
+            range: selection,
+            replacement: format!("{typed}{selected}{close}"),
+            selection: (start + open_len, end + open_len),
+        });
+    }
+
+    let pos = start;
+    let next = text[pos..].chars().next();
+    let step_over = PairEdit {
+        range: (pos, pos + typed.len_utf8()),
+        replacement: typed.to_string(),
+        selection: (pos + 1, pos + 1),
+    };
+
+    if let Some(code) = in_code {
+        let delimiter = match code.kind {
+            NodeKind::Code => '`',
+            NodeKind::Math => '$',
+            _ => return None,
+        };
+        let closing = text[pos..code.source_range.1].chars().all(|c| c == delimiter);
+        return (typed == delimiter && next == Some(delimiter) && closing).then_some(step_over);
+    }
+
+    let symmetric = closer == Some(typed);
+    if next == Some(typed) && matches!(typed, '*' | '$') {
+        let (left, right) = runs_around(text, pos, typed);
+        let max = if typed == '*' { 3 } else { 2 };
+        if left > 0 && left == right && left < max && opens_at(text, pos - left) {
+            return Some(PairEdit {
+                range: (pos, pos),
+                replacement: format!("{typed}{typed}"),
+                selection: (pos + 1, pos + 1),
+            });
+        }
+    }
+    let is_closer = AUTO_PAIRS.iter().any(|&(_, close)| close == typed);
+    if is_closer && next == Some(typed) {
+        return Some(step_over);
+    }
+
+    let closer = closer?;
+    let before_closing = next.is_none_or(|c| c.is_whitespace() || ")]}>.,;:!?".contains(c) || c == closer);
+    if !before_closing {
+        return None;
+    }
+    if symmetric && !opens_at(text, pos) {
+        return None;
+    }
+    let line_start = line_bounds(text, pos).0;
+    if typed == '*' && text[line_start..pos].trim().is_empty() {
+        return None;
+    }
+    if typed == '`' && text[..pos].ends_with('`') {
+        return None;
+    }
+    Some(PairEdit {
+        range: (pos, pos),
+        replacement: format!("{typed}{closer}"),
+        selection: (pos + 1, pos + 1),
+    })
+}
+
+/// Backspace between an empty auto-paired opener and closer (`(|)`,
+/// `"|"`, `**|**`) deletes both characters. Never applies inside code.
+pub fn pair_backspace_edit(text: &str, spans: &[MarkdownSpan], caret: usize) -> Option<TextEdit> {
+    let open = text[..caret].chars().next_back()?;
+    let closer = closer_for(open)?;
+    if !text[caret..].starts_with(closer) {
+        return None;
+    }
+    let open_at = caret - open.len_utf8();
+    if closer == open && !opens_at(text, open_at) {
+        return None;
+    }
+    if code_span_at(spans, caret).is_some() {
+        return None;
+    }
+    Some(TextEdit { range: (open_at, caret + closer.len_utf8()), replacement: String::new(), caret: open_at })
+}
+
+// ---------------------------------------------------------------------------
+// Table helpers
+// ---------------------------------------------------------------------------
+
+/// Column alignment, as declared by a GFM delimiter row.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ColumnAlign {
+    /// `---`
+    #[default]
+    None,
+    /// `:--`
+    Left,
+    /// `:-:`
+    Center,
+    /// `--:`
+    Right,
+}
+
+/// Table editing commands offered by the sidebar and the Edit ▸ Table menu.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TableCommand {
+    InsertRowAbove,
+    InsertRowBelow,
+    DeleteRow,
+    InsertColumnLeft,
+    InsertColumnRight,
+    DeleteColumn,
+    MoveColumnLeft,
+    MoveColumnRight,
+    /// Re-pad all cells so the pipes line up.
+    Format,
+}
+
+/// An editable grid model of a GFM table.
+///
+/// Row indices used by the editing methods count the header as row 0 and
+/// body rows from 1; the delimiter row is not addressable.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MarkdownTable {
+    pub header: Vec<String>,
+    pub alignments: Vec<ColumnAlign>,
+    pub rows: Vec<Vec<String>>,
+}
+
+impl MarkdownTable {
+    /// An empty table with `columns` columns and `body_rows` body rows.
+    pub fn new(columns: usize, body_rows: usize) -> Self {
+        Self {
+            header: vec![String::new(); columns],
+            alignments: vec![ColumnAlign::None; columns],
+            rows: vec![vec![String::new(); columns]; body_rows],
+        }
+    }
+
+    /// Build the grid from a `NodeKind::Table` span.
+    ///
+    /// Cell text comes from the `TableCell` spans (escaped pipes are kept
+    /// verbatim); alignments are read from the delimiter line below the header.
+    /// Short rows are padded with empty cells and long rows are truncated.
+    pub fn from_span(text: &str, table: &MarkdownSpan) -> Option<Self> {
+        let rows: Vec<Vec<String>> = table
+            .children
+            .iter()
+            .filter(|r| matches!(r.kind, NodeKind::TableRow { .. }))
+            .map(|row| {
+                row.children
+                    .iter()
+                    .filter(|c| c.kind == NodeKind::TableCell)
+                    // Cells comrak synthesises for short rows carry no valid range.
+                    .map(|c| {
+                        text.get(c.source_range.0..c.source_range.1)
+                            .unwrap_or("")
+                            .trim()
+                            .to_string()
+                    })
+                    .collect()
+            })
+            .collect();
+        let (header, body) = rows.split_first()?;
+
+        let header_end = table.children.first()?.source_range.1;
+        let (delim_start, delim_end) = line_bounds(text, (header_end + 1).min(text.len()));
+        let mut alignments: Vec<ColumnAlign> = split_table_line(&text[delim_start..delim_end])
+            .iter()
+            .map(|cell| parse_alignment(cell))
+            .collect();
+        alignments.resize(header.len(), ColumnAlign::None);
+
+        let columns = header.len();
+        let body = body
+            .iter()
+            .map(|row| {
+                let mut row = row.clone();
+                row.resize(columns, String::new());
+                row
+            })
+            .collect();
+        Some(Self { header: header.clone(), alignments, rows: body })
+    }
+
+    /// Build the grid from the renderer's [`TableInfo`] for a table.
+    ///
+    /// Cells are the text between the structural pipes of each row, so
+    /// escaped pipes inside cells are preserved.
+    pub fn from_table_info(text: &str, info: &TableInfo) -> Option<Self> {
+        let mut rows = info.row_ranges.iter().zip(&info.row_pipes).map(|(&range, pipes)| {
+            row_cell_ranges(text, range, pipes)
+                .into_iter()
+                .map(|(s, e)| text[s..e].trim().to_string())
+                .collect::<Vec<_>>()
+        });
+        let header = rows.next()?;
+        let columns = header.len();
+
+        let (delim_start, delim_end) = line_bounds(text, (info.row_ranges[0].1 + 1).min(text.len()));
+        let mut alignments: Vec<ColumnAlign> = split_table_line(&text[delim_start..delim_end])
+            .iter()
+            .map(|cell| parse_alignment(cell))
+            .collect();
+        alignments.resize(columns, ColumnAlign::None);
+
+        let body = rows
+            .map(|mut row| {
+                row.resize(columns, String::new());
+                row
+            })
+            .collect();
+        Some(Self { header, alignments, rows: body })
+    }
+
+    /// Build a grid from delimited records, treating the first as the header.
+    ///
+    /// Pipes are escaped and embedded newlines become `<br>` so every record
+    /// stays on one table line. Short records are padded to the widest one.
+    pub fn from_records(records: &[Vec<String>]) -> Option<Self> {
+        let columns = records.iter().map(Vec::len).max().filter(|&n| n > 0)?;
+        let mut rows = records.iter().map(|record| {
+            let mut row: Vec<String> = record
+                .iter()
+                .map(|field| {
+                    field
+                        .trim()
+                        .replace('|', "\\|")
+                        .replace("\r\n", "<br>")
+                        .replace('\n', "<br>")
+                })
+                .collect();
+            row.resize(columns, String::new());
+            row
+        });
+        let header = rows.next()?;
+        Some(Self {
+            header,
+            alignments: vec![ColumnAlign::None; columns],
+            rows: rows.collect(),
+        })
+    }
+
+    /// Serialize as CSV (`,`) or TSV (`\t`), header first, one record per line.
+    ///
+    /// Reverses the escaping done by [`Self::from_records`] and quotes fields
+    /// that contain the delimiter, a quote or a newline.
+    pub fn to_delimited(&self, delimiter: char) -> String {
+        let mut out = String::new();
+        for row in std::iter::once(&self.header).chain(&self.rows) {
+            let fields: Vec<String> = row
+                .iter()
+                .map(|cell| {
+                    let field = cell.replace("\\|", "|").replace("<br>", "\n");
+                    if field.contains(delimiter) || field.contains('"') || field.contains('\n') {
+                        format!("\"{}\"", field.replace('"', "\"\""))
+                    } else {
+                        field
+                    }
+                })
+                .collect();
+            out.push_str(&fields.join(&delimiter.to_string()));
+            out.push('\n');
+        }
+        out
+    }
+
+    /// Number of columns.
+    pub fn column_count(&self) -> usize {
+        self.header.len()
+    }
+
+    /// Apply `command` with the caret in cell (`row`, `col`).
+    ///
+    /// Returns the cell the caret should move to, or `None` when the command
+    /// does not apply (e.g. deleting the header row or the only column).
+    pub fn apply(&mut self, command: TableCommand, row: usize, col: usize) -> Option<(usize, usize)> {
+        let columns = self.column_count();
+        match command {
+            TableCommand::InsertRowAbove => {
+                if row == 0 {
+                    return None;
+                }
+                self.rows.insert(row - 1, vec![String::new(); columns]);
+                Some((row, col))
+            }
+            TableCommand::InsertRowBelow => {
+                self.rows.insert(row, vec![String::new(); columns]);
+                Some((row + 1, col))
+            }
+            TableCommand::DeleteRow => {
+                if row == 0 || row > self.rows.len() {
+                    return None;
+                }
+                self.rows.remove(row - 1);
+                Some((row.min(self.rows.len()), col))
+            }
+            TableCommand::InsertColumnLeft | TableCommand::InsertColumnRight => {
+                let at = if command == TableCommand::InsertColumnLeft { col } else { col + 1 };
+                let at = at.min(columns);
+                self.header.insert(at, String::new());
+                self.alignments.insert(at, ColumnAlign::None);
+                for r in &mut self.rows {
+                    r.insert(at, String::new());
+                }
+                Some((row, at))
+            }
+            TableCommand::DeleteColumn => {
+                if columns <= 1 || col >= columns {
+                    return None;
+                }
+                self.header.remove(col);
+                self.alignments.remove(col);
+                for r in &mut self.rows {
+                    r.remove(col);
+                }
+                Some((row, col.min(columns - 2)))
+            }
+            TableCommand::MoveColumnLeft | TableCommand::MoveColumnRight => {
+                let target = if command == TableCommand::MoveColumnLeft {
+                    col.checked_sub(1)?
+                } else {
+                    col + 1
+                };
+                if target >= columns || col >= columns {
+                    return None;
+                }
+                self.header.swap(col, target);
+                self.alignments.swap(col, target);
+                for r in &mut self.rows {
+                    r.swap(col, target);
+                }
+                Some((row, target))
+            }
+            TableCommand::Format => Some((row, col)),
+        }
+    }
+
+    /// Serialize with padded cells so every pipe lines up.
+    pub fn to_markdown(&self) -> String {
+        self.render().0.join("\n")
+    }
+
+    /// Byte offset of cell (`row`, `col`)'s text within [`Self::to_markdown`].
+    pub fn cell_offset(&self, row: usize, col: usize) -> usize {
+        let (lines, cell_starts) = self.render();
+        let line = if row == 0 { 0 } else { (row + 1).min(lines.len() - 1) };
+        let line_start: usize = lines[..line].iter().map(|l| l.len() + 1).sum();
+        let starts = &cell_starts[line];
+        line_start + starts.get(col).or(starts.last()).copied().unwrap_or(0)
+    }
+
+    /// Render every line, returning the lines and, per line, the byte offset
+    /// where each cell's text starts.
+    fn render(&self) -> (Vec<String>, Vec<Vec<usize>>) {
+        let columns = self.column_count();
+        let mut widths = vec![3usize; columns];
+        for row in std::iter::once(&self.header).chain(&self.rows) {
+            for (w, cell) in widths.iter_mut().zip(row) {
+                *w = (*w).max(cell.chars().count());
+            }
+        }
+
+        let mut lines = Vec::with_capacity(self.rows.len() + 2);
+        let mut cell_starts = Vec::with_capacity(self.rows.len() + 2);
+        let mut push_row = |cells: Vec<String>, aligns: &[ColumnAlign]| {
+            let mut line = String::from("|");
+            let mut starts = Vec::with_capacity(columns);
+            for ((cell, &width), &align) in cells.iter().zip(&widths).zip(aligns) {
+                let pad = width.saturating_sub(cell.chars().count());
+                let (left, right) = match align {
+                    ColumnAlign::Right => (pad, 0),
+                    ColumnAlign::Center => (pad / 2, pad - pad / 2),
+                    _ => (0, pad),
+                };
+                line.push(' ');
+                line.push_str(&" ".repeat(left));
+                starts.push(line.len());
+                line.push_str(cell);
+                line.push_str(&" ".repeat(right));
+                line.push_str(" |");
+            }
+            lines.push(line);
+            cell_starts.push(starts);
+        };
+
+        push_row(self.header.clone(), &self.alignments);
+        let delimiters = widths
+            .iter()
+            .zip(&self.alignments)
+            .map(|(&w, &align)| match align {
+                ColumnAlign::None => "-".repeat(w),
+                ColumnAlign::Left => format!(":{}", "-".repeat(w - 1)),
+                ColumnAlign::Right => format!("{}:", "-".repeat(w - 1)),
+                ColumnAlign::Center => format!(":{}:", "-".repeat(w - 2)),
+            })
+            .collect();
+        push_row(delimiters, &vec![ColumnAlign::None; columns]);
+        for row in &self.rows {
+            push_row(row.clone(), &self.alignments);
+        }
+        (lines, cell_starts)
+    }
+}
+
+/// Split a table line into its trimmed cells, honouring `\|` escapes.
+fn split_table_line(line: &str) -> Vec<String> {
+    let trimmed = line.trim();
+    let inner = trimmed.strip_prefix('|').unwrap_or(trimmed);
+    let inner = match inner.strip_suffix('|') {
+        Some(rest) if !rest.ends_with('\\') => rest,
+        _ => inner,
+    };
+    let mut cells = Vec::new();
+    let mut current = String::new();
+    let mut escaped = false;
+    for ch in inner.chars() {
+        if ch == '|' && !escaped {
+            cells.push(current.trim().to_string());
+            current.clear();
+        } else {
+            current.push(ch);
+        }
+        escaped = ch == '\\' && !escaped;
+    }
+    cells.push(current.trim().to_string());
+    cells
+}
+
+/// Read the alignment from one delimiter-row cell (`:--`, `:-:`, `--:`).
+fn parse_alignment(cell: &str) -> ColumnAlign {
+    match (cell.starts_with(':'), cell.ends_with(':') && cell.len() > 1) {
+        (true, true) => ColumnAlign::Center,
+        (true, false) => ColumnAlign::Left,
+        (false, true) => ColumnAlign::Right,
+        (false, false) => ColumnAlign::None,
+    }
+}
+
+/// Find the innermost `NodeKind::Table` span containing byte `pos`.
+pub fn find_table_span(spans: &[MarkdownSpan], pos: usize) -> Option<&MarkdownSpan> {
+    spans.iter().find_map(|span| {
+        if pos < span.source_range.0 || pos > span.source_range.1 {
+            return None;
+        }
+        if span.kind == NodeKind::Table {
+            Some(span)
+        } else {
+            find_table_span(&span.children, pos)
+        }
+    })
+}
+
+/// Map a caret inside `table` to its (row, column) cell.
+///
+/// The header is row 0 and the delimiter line counts as part of it.
+fn locate_table_cell(text: &str, table: &MarkdownSpan, caret: usize) -> (usize, usize) {
+    let (line_start, line_end) = line_bounds(text, caret);
+    let rows: Vec<&MarkdownSpan> = table
+        .children
+        .iter()
+        .filter(|r| matches!(r.kind, NodeKind::TableRow { .. }))
+        .collect();
+    let row = rows
+        .iter()
+        .position(|r| r.source_range.0 <= line_end && r.source_range.1 >= line_start)
+        .unwrap_or(0);
+    let col = rows.get(row).map_or(0, |r| {
+        r.children
+            .iter()
+            .filter(|c| c.kind == NodeKind::TableCell)
+            .take_while(|c| c.source_range.0 <= caret)
+            .count()
+            .saturating_sub(1)
+    });
+    (row, col)
+}
+
+/// Byte ranges of the cells in one table row, given its structural pipes.
+///
+/// Leading and trailing pipes are optional in GFM, so the first and last
+/// cells may be bounded by the row edges instead.
+fn row_cell_ranges(text: &str, row: (usize, usize), pipes: &[usize]) -> Vec<(usize, usize)> {
+    let (row_start, row_end) = row;
+    let leading = pipes.first().is_some_and(|&p| text[row_start..p].trim().is_empty());
+    let trailing = pipes.last().is_some_and(|&p| text[p + 1..row_end].trim().is_empty());
+
+    let mut cells = Vec::new();
+    let mut start = if leading { pipes[0] + 1 } else { row_start };
+    for &pipe in pipes.iter().skip(usize::from(leading)) {
+        cells.push((start, pipe));
+        start = pipe + 1;
+    }
+    if !trailing || cells.is_empty() {
+        cells.push((start, row_end));
+    }
+    cells
+}
+
+/// Map `caret` to its (row, column) cell using [`TableInfo::row_pipes`].
+///
+/// Row 0 is the header; a caret on the delimiter line counts as the header.
+/// A caret just before a pipe belongs to the cell on its left. Returns `None`
+/// when the caret lies outside the table.
+pub fn table_cell_at(text: &str, info: &TableInfo, caret: usize) -> Option<(usize, usize)> {
+    if caret < info.source_range.0 || caret > info.source_range.1 {
+        return None;
+    }
+    let row = info.row_ranges.iter().rposition(|&(start, _)| start <= caret).unwrap_or(0);
+    let cells = row_cell_ranges(text, info.row_ranges[row], &info.row_pipes[row]);
+    let col = cells
+        .iter()
+        .take_while(|&&(start, _)| start <= caret)
+        .count()
+        .saturating_sub(1);
+    Some((row, col))
+}
+
+/// Compute the edit for Tab / Shift-Tab inside a table.
+///
+/// Moves the caret to the next (or previous) cell, wrapping across rows, and
+/// re-pads the whole table. Tab in the last cell appends an empty row;
+/// Shift-Tab in the first cell stays put. Returns `None` when the caret is
+/// outside the table or the table sits inside a blockquote.
+pub fn table_tab_edit(text: &str, info: &TableInfo, caret: usize, backwards: bool) -> Option<TextEdit> {
+    let (start, _) = line_bounds(text, info.source_range.0);
+    if start != info.source_range.0 {
+        return None;
+    }
+    let (_, end) = line_bounds(text, info.source_range.1.saturating_sub(1));
+
+    let (row, col) = table_cell_at(text, info, caret)?;
+    let mut grid = MarkdownTable::from_table_info(text, info)?;
+    let columns = grid.column_count();
+    let last_row = grid.rows.len();
+    let col = col.min(columns - 1);
+
+    let target = if backwards {
+        match (row, col) {
+            (0, 0) => (0, 0),
+            (r, 0) => (r - 1, columns - 1),
+            (r, c) => (r, c - 1),
+        }
+    } else if col + 1 < columns {
+        (row, col + 1)
+    } else {
+        if row == last_row {
+            grid.apply(TableCommand::InsertRowBelow, row, col)?;
+        }
+        (row + 1, 0)
+    };
+
+    Some(TextEdit {
+        range: (start, end),
+        replacement: grid.to_markdown(),
+        caret: start + grid.cell_offset(target.0, target.1),
+    })
+}
+
+/// Compute the edit for running `command` on the table containing `caret`.
+///
+/// Returns `None` when the caret is not inside a table, the command does not
+/// apply, or the table is nested inside a blockquote (its lines carry a
+/// prefix the serializer would drop).
+pub fn table_command_edit(text: &str, caret: usize, command: TableCommand) -> Option<TextEdit> {
+    let spans = parse(text);
+    let table = find_table_span(&spans, caret)?;
+    let (start, _) = line_bounds(text, table.source_range.0);
+    if start != table.source_range.0 {
+        return None;
+    }
+    let (_, end) = line_bounds(text, table.source_range.1.saturating_sub(1));
+
+    let mut grid = MarkdownTable::from_span(text, table)?;
+    let (row, col) = locate_table_cell(text, table, caret);
+    let (new_row, new_col) = grid.apply(command, row, col)?;
+    Some(TextEdit {
+        range: (start, end),
+        replacement: grid.to_markdown(),
+        caret: start + grid.cell_offset(new_row, new_col),
+    })
+}
+
+/// Compute the edit that inserts an empty 2-column table below the caret's
+/// line, leaving the caret in the first header cell.
+pub fn insert_table_edit(text: &str, caret: usize) -> TextEdit {
+    let grid = MarkdownTable::new(2, 1);
+    let (line_start, line_end) = line_bounds(text, caret);
+    let (at, prefix) = if text[line_start..line_end].trim().is_empty() {
+        (line_start, "")
+    } else {
+        (line_end, "\n\n")
+    };
+    let suffix = if text[line_end..].starts_with("\n\n") || line_end == text.len() {
+        ""
+    } else {
+        "\n"
+    };
+    let replacement = format!("{}{}{}", prefix, grid.to_markdown(), suffix);
+    let caret = at + prefix.len() + grid.cell_offset(0, 0);
+    let end = if at == line_start { line_end } else { at };
+    TextEdit { range: (at, end), replacement, caret }
+}
+
+/// Compute the edit that replaces the selection with a standalone block,
+/// adding blank lines around it as needed. The caret lands after the block.
+pub fn insert_block_edit(text: &str, selection: (usize, usize), block: &str) -> TextEdit {
+    let (start, end) = selection;
+    let before = &text[..start];
+    let after = &text[end..];
+    let prefix = if before.is_empty() || before.ends_with("\n\n") {
+        ""
+    } else if before.ends_with('\n') {
+        "\n"
+    } else {
+        "\n\n"
+    };
+    let suffix = if after.is_empty() || after.starts_with("\n\n") {
+        ""
+    } else if after.starts_with('\n') {
+        "\n"
+    } else {
+        "\n\n"
+    };
+    TextEdit {
+        range: (start, end),
+        replacement: format!("{}{}{}", prefix, block, suffix),
+        caret: start + prefix.len() + block.len(),
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Delimited text (CSV / TSV)
+// ---------------------------------------------------------------------------
+
+/// Split CSV/TSV text into records of fields.
+///
+/// Fields may be wrapped in double quotes, in which case they can contain the
+/// delimiter, newlines and `""` (an escaped quote). `\r\n` line endings are
+/// accepted and a trailing newline does not produce an empty record.
+pub fn parse_delimited(text: &str, delimiter: char) -> Vec<Vec<String>> {
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut at_field_start = true;
+    let mut chars = text.chars().peekable();
+
+    while let Some(ch) = chars.next() {
+        if in_quotes {
+            if ch != '"' {
+                field.push(ch);
+            } else if chars.peek() == Some(&'"') {
+                chars.next();
+                field.push('"');
+            } else {
+                in_quotes = false;
+            }
+            continue;
+        }
+        match ch {
+            '"' if at_field_start => in_quotes = true,
+            c if c == delimiter => {
+                record.push(std::mem::take(&mut field));
+                at_field_start = true;
+                continue;
+            }
+            '\r' if chars.peek() == Some(&'\n') => continue,
+            '\n' => {
+                record.push(std::mem::take(&mut field));
+                records.push(std::mem::take(&mut record));
+                at_field_start = true;
+                continue;
+            }
+            _ => field.push(ch),
+        }
+        at_field_start = false;
+    }
+    if !field.is_empty() || !record.is_empty() {
+        record.push(field);
+        records.push(record);
+    }
+    records
+}
+
+/// Detect whether `text` is tab- or comma-separated data.
+///
+/// Requires at least two records of at least two fields each, all with the
+/// same field count. Comma-separated input is rejected when a field starts
+/// with a space, since that is far more likely to be prose than a CSV export,
+/// and any input whose first field is empty on every line is rejected too —
+/// that is tab-indented code, not a table.
+/// Returns the delimiter and the parsed records.
+pub fn detect_delimited(text: &str) -> Option<(char, Vec<Vec<String>>)> {
+    ['\t', ','].into_iter().find_map(|delimiter| {
+        if !text.contains(delimiter) {
+            return None;
+        }
+        let records = parse_delimited(text, delimiter);
+        let columns = records.first()?.len();
+        let uniform = records.len() >= 2 && columns >= 2 && records.iter().all(|r| r.len() == columns);
+        let prose = delimiter == ',' && records.iter().flatten().any(|f| f.starts_with(' '));
+        let indented = records.iter().all(|r| r.first().is_some_and(String::is_empty));
+        (uniform && !prose && !indented).then_some((delimiter, records))
+    })
+}
+
+/// Convert pasted CSV/TSV text into an aligned GFM table.
+///
+/// Returns `None` when the text does not look like delimited data.
+pub fn delimited_to_table(text: &str) -> Option<String> {
+    let (_, records) = detect_delimited(text)?;
+    Some(MarkdownTable::from_records(&records)?.to_markdown())
+}
+
+/// Serialize the table containing `pos` as CSV (`,`) or TSV (`\t`).
+pub fn table_as_delimited(text: &str, pos: usize, delimiter: char) -> Option<String> {
+    let spans = parse(text);
+    let table = find_table_span(&spans, pos)?;
+    Some(MarkdownTable::from_span(text, table)?.to_delimited(delimiter))
+}
+
+// ---------------------------------------------------------------------------
+// Document formatter
+// ---------------------------------------------------------------------------
+
+/// Style choices for [`format_document`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FormatOptions {
+    /// Emphasis delimiter: `'_'` or `'*'`.
+    pub emphasis: char,
+    /// Bullet list marker: `'-'`, `'*'` or `'+'`.
+    pub bullet: char,
+}
+
+impl Default for FormatOptions {
+    fn default() -> Self {
+        Self { emphasis: '_', bullet: '-' }
+    }
+}
+
+/// Rewrite a whole document in canonical style.
+///
+/// Runs comrak's CommonMark formatter (ATX headings, fenced code, sequential
+/// ordered lists, one blank line between blocks), then applies mdit's own
+/// choices: no trailing whitespace, the configured emphasis and bullet
+/// markers, padded tables, and one-line footnote definitions. A leading
+/// `---` front-matter block is kept as written.
+///
+/// The output is idempotent and always parses to a span tree equivalent to
+/// the input's (see [`spans_equivalent`]); if a step would break that, the
+/// step is skipped, and if comrak itself would, `text` is returned as is.
+pub fn format_document(text: &str, options: &FormatOptions) -> String {
+    let original = parse(text);
+
+    let mut comrak_opts = make_options();
+    // Keep bare URLs bare: comrak would otherwise print them as `<url>`.
+    comrak_opts.extension.autolink = false;
+    comrak_opts.extension.front_matter_delimiter = Some("---".into());
+    comrak_opts.render.prefer_fenced = true;
+    comrak_opts.render.list_style = match options.bullet {
+        '*' => comrak::options::ListStyleType::Star,
+        '+' => comrak::options::ListStyleType::Plus,
+        _ => comrak::options::ListStyleType::Dash,
+    };
+    let mut out = comrak::markdown_to_commonmark(text, &comrak_opts);
+    if !spans_equivalent(text, &original, &out, &parse(&out)) {
+        return text.to_string();
+    }
+
+    let passes: [fn(&str, &FormatOptions) -> String; 6] = [
+        |t, _| strip_trailing_whitespace(t),
+        |t, _| unescape_intraword_underscores(t),
+        |t, _| collapse_footnote_definitions(t),
+        |t, _| drop_list_separators(t),
+        |t, _| pad_tables(t),
+        |t, o| set_emphasis_marker(t, o.emphasis),
+    ];
+    for pass in passes {
+        let candidate = pass(&out, options);
+        if spans_equivalent(text, &original, &candidate, &parse(&candidate)) {
+            out = candidate;
+        }
+    }
+    out
+}
+
+/// Compare the span trees of two documents, ignoring source positions.
+///
+/// `a` must be the parse of `a_src` and `b` that of `b_src`. `Other` nodes
+/// (soft breaks, HTML blocks, ...) are replaced by their children, so
+/// comrak's `<!-- end list -->` separators do not count as differences.
+/// Runs of text are compared by their source with escapes and character
+/// references resolved and whitespace collapsed, so re-flowed lines match
+/// but dropped or changed words do not. Other node kinds, hard line breaks
+/// included, must match exactly, including link URLs, heading levels and
+/// code.
+pub fn spans_equivalent(a_src: &str, a: &[MarkdownSpan], b_src: &str, b: &[MarkdownSpan]) -> bool {
+    enum Shape<'a> {
+        Text(String),
+        Node(&'a NodeKind, &'a [MarkdownSpan]),
+    }
+    fn shape<'a>(src: &str, spans: &'a [MarkdownSpan], out: &mut Vec<Shape<'a>>) {
+        for span in spans {
+            let text = match &span.kind {
+                NodeKind::Text => text_literal(&src[span.source_range.0..span.source_range.1]),
+                // A soft break inside a run of text; empty blocks such as
+                // HTML comments fall through to the next arm.
+                NodeKind::Other if span.children.is_empty() && matches!(out.last(), Some(Shape::Text(_))) => {
+                    " ".to_string()
+                }
+                NodeKind::Other => {
+                    shape(src, &span.children, out);
+                    continue;
+                }
+                kind => {
+                    out.push(Shape::Node(kind, &span.children));
+                    continue;
+                }
+            };
+            match out.last_mut() {
+                Some(Shape::Text(run)) => run.push_str(&text),
+                _ => out.push(Shape::Text(text)),
+            }
+        }
+    }
+    let (mut left, mut right) = (Vec::new(), Vec::new());
+    shape(a_src, a, &mut left);
+    shape(b_src, b, &mut right);
+    left.len() == right.len()
+        && left.iter().zip(&right).all(|pair| match pair {
+            (Shape::Text(ta), Shape::Text(tb)) => ta.split_whitespace().eq(tb.split_whitespace()),
+            (Shape::Node(ka, ca), Shape::Node(kb, cb)) => ka == kb && spans_equivalent(a_src, ca, b_src, cb),
+            _ => false,
+        })
+}
+
+/// The text a `Text` node's source stands for: backslash escapes and
+/// character references resolved.
+fn text_literal(src: &str) -> String {
+    let mut out = String::with_capacity(src.len());
+    let mut rest = src;
+    while let Some(i) = rest.find('\\') {
+        out.push_str(&decode_entities(&rest[..i]));
+        match rest[i + 1..].chars().next() {
+            Some(c) if c.is_ascii_punctuation() => {
+                out.push(c);
+                rest = &rest[i + 2..];
+            }
+            _ => {
+                out.push('\\');
+                rest = &rest[i + 1..];
+            }
+        }
+    }
+    out.push_str(&decode_entities(rest));
+    out
+}
+
+/// Trim trailing whitespace from every line that doesn't end inside code.
+/// comrak leaves the continuation indent on blank lines in lists and the
+/// `> ` marker's space on blank lines in block quotes.
+fn strip_trailing_whitespace(text: &str) -> String {
+    let mut code = Vec::new();
+    code_ranges(&parse(text), &mut code);
+
+    let mut out = String::with_capacity(text.len());
+    let mut start = 0;
+    for line in text.split_inclusive('\n') {
+        let content = line.strip_suffix('\n').unwrap_or(line);
+        let end = start + content.len();
+        if code.iter().any(|&(s, e)| end > s && end < e) {
+            out.push_str(line);
+        } else {
+            out.push_str(content.trim_end());
+            out.push_str(&line[content.len()..]);
+        }
+        start += line.len();
+    }
+    out
+}
+
+/// Undo comrak's escaping of underscores inside words (`snake\\_case`),
+/// which can never form emphasis. Code is left untouched.
+fn unescape_intraword_underscores(text: &str) -> String {
+    let mut code = Vec::new();
+    code_ranges(&parse(text), &mut code);
+
+    let bytes = text.as_bytes();
+    let is_word = |b: Option<&u8>| b.is_some_and(|b| b.is_ascii_alphanumeric() || *b >= 0x80);
+    let mut out = String::with_capacity(text.len());
+    let mut last = 0;
+    for (i, _) in text.match_indices("\\_") {
+        let in_code = code.iter().any(|&(s, e)| i >= s && i < e);
+        if !in_code && is_word(i.checked_sub(1).and_then(|p| bytes.get(p))) && is_word(bytes.get(i + 2)) {
+            out.push_str(&text[last..i]);
+            last = i + 1;
+        }
+    }
+    out.push_str(&text[last..]);
+    out
+}
+
+/// Turn comrak's `[^x]:` + indented body into `[^x]: body` when the body is
+/// a single line.
+fn collapse_footnote_definitions(text: &str) -> String {
+    let lines: Vec<&str> = text.split('\n').collect();
+    let mut out = Vec::with_capacity(lines.len());
+    let mut i = 0;
+    while i < lines.len() {
+        let line = lines[i];
+        let is_label = line.starts_with("[^") && line.ends_with("]:");
+        let body = lines.get(i + 1).and_then(|l| l.strip_prefix("    "));
+        let single = lines.get(i + 2).is_none_or(|l| l.trim().is_empty());
+        match body {
+            Some(body) if is_label && single && !body.trim().is_empty() => {
+                out.push(format!("{} {}", line, body));
+                i += 2;
+            }
+            _ => {
+                out.push(line.to_string());
+                i += 1;
+            }
+        }
+    }
+    out.join("\n")
+}
+
+/// Remove comrak's `<!-- end list -->` separators between lists whose
+/// markers already differ (bullet vs. ordered, or different delimiters).
+fn drop_list_separators(text: &str) -> String {
+    const SEPARATOR: &str = "<!-- end list -->";
+    let family = |line: &str| {
+        parse_list_marker(line).and_then(|m| match m.kind {
+            ListKind::Bullet(c) | ListKind::Task { bullet: c, .. } => Some(('b', c)),
+            ListKind::Ordered { delimiter, .. } => Some(('o', delimiter)),
+            ListKind::Quote => None,
+        })
+    };
+
+    let lines: Vec<&str> = text.split('\n').collect();
+    let mut drop = vec![false; lines.len()];
+    for (i, line) in lines.iter().enumerate() {
+        if *line != SEPARATOR {
+            continue;
+        }
+        let before = lines[..i].iter().rev().find(|l| leading_ws(l) == 0 && !l.is_empty()).and_then(|l| family(l));
+        let after = lines[i + 1..].iter().find(|l| !l.trim().is_empty()).and_then(|l| family(l));
+        if before.is_some() && after.is_some() && before != after {
+            drop[i] = true;
+            // Also drop the blank line that followed the separator.
+            if lines.get(i + 1).is_some_and(|l| l.is_empty()) {
+                drop[i + 1] = true;
+            }
+        }
+    }
+    lines
+        .iter()
+        .zip(&drop)
+        .filter(|(_, &d)| !d)
+        .map(|(l, _)| *l)
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Re-pad every top-level table so its pipes line up.
+fn pad_tables(text: &str) -> String {
+    let spans = parse(text);
+    let mut out = text.to_string();
+    // Walk tables back to front so earlier offsets stay valid.
+    for span in spans.iter().rev().filter(|s| s.kind == NodeKind::Table) {
+        let (start, _) = line_bounds(text, span.source_range.0);
+        if start != span.source_range.0 {
+            continue;
+        }
+        let (_, end) = line_bounds(text, span.source_range.1.saturating_sub(1));
+        if let Some(grid) = MarkdownTable::from_span(text, span) {
+            out.replace_range(start..end, &grid.to_markdown());
+        }
+    }
+    out
+}
+
+/// Rewrite every emphasis span to use `marker`, where that keeps it valid
+/// (underscores cannot open or close emphasis inside a word).
+fn set_emphasis_marker(text: &str, marker: char) -> String {
+    fn collect(spans: &[MarkdownSpan], out: &mut Vec<(usize, usize)>) {
+        for span in spans {
+            if span.kind == NodeKind::Emph {
+                out.push(span.source_range);
+            }
+            collect(&span.children, out);
+        }
+    }
+    let mut ranges = Vec::new();
+    collect(&parse(text), &mut ranges);
+
+    let mut bytes = text.as_bytes().to_vec();
+    let is_word = |i: Option<&u8>| i.is_some_and(|b| b.is_ascii_alphanumeric() || *b >= 0x80);
+    for (start, end) in ranges {
+        let (open, close) = (start, end - 1);
+        if bytes[open] == marker as u8 || !matches!(bytes[open], b'*' | b'_') {
+            continue;
+        }
+        let intraword = is_word(start.checked_sub(1).and_then(|i| bytes.get(i))) || is_word(bytes.get(end));
+        if marker == '_' && intraword {
+            continue;
+        }
+        bytes[open] = marker as u8;
+        bytes[close] = marker as u8;
+    }
+    String::from_utf8(bytes).unwrap_or_else(|_| text.to_string())
+}
+
+// ---------------------------------------------------------------------------
+// Paragraph reflow
+// ---------------------------------------------------------------------------
+
+/// Default column for [`reflow_paragraphs_edit`].
+pub const DEFAULT_WRAP_COLUMN: usize = 80;
+
+/// Hard-wrap every paragraph touched by `selection` so lines fit in `column`.
+///
+/// Code spans, links, images, math and inline HTML are never split, hard
+/// line breaks are kept, and continuation lines repeat the blockquote
+/// markers and list indentation of the paragraph's first line. A word that
+/// would start a new block at the beginning of a line (`-`, `1.`, `#`, ...)
+/// stays on the previous line even if that line runs long.
+///
+/// Returns `None` when there is no paragraph to change.
+pub fn reflow_paragraphs_edit(text: &str, selection: (usize, usize), column: usize) -> Option<TextEdit> {
+    rewrap_paragraphs(text, selection, Some(column))
+}
+
+/// Join the lines of every paragraph touched by `selection` into one line.
+///
+/// Hard line breaks are kept. Returns `None` when nothing would change.
+pub fn unwrap_paragraphs_edit(text: &str, selection: (usize, usize)) -> Option<TextEdit> {
+    rewrap_paragraphs(text, selection, None)
+}
+
+/// Shared implementation of reflow (`Some(column)`) and unwrap (`None`).
+fn rewrap_paragraphs(text: &str, selection: (usize, usize), column: Option<usize>) -> Option<TextEdit> {
+    fn paragraphs<'a>(spans: &'a [MarkdownSpan], range: (usize, usize), out: &mut Vec<&'a MarkdownSpan>) {
+        for span in spans {
+            if span.source_range.0 > range.1 || span.source_range.1 < range.0 {
+                continue;
+            }
+            match span.kind {
+                NodeKind::Paragraph => out.push(span),
+                NodeKind::Table | NodeKind::CodeBlock { .. } => {}
+                _ => paragraphs(&span.children, range, out),
+            }
+        }
+    }
+    let spans = parse(text);
+    let mut found = Vec::new();
+    // Match whole lines so a caret before a `> ` or `- ` marker still picks
+    // up the paragraph after it.
+    let lines = (line_bounds(text, selection.0).0, line_bounds(text, selection.1).1);
+    paragraphs(&spans, lines, &mut found);
+
+    let rewrapped: Vec<RewrappedParagraph> =
+        found.iter().map(|p| rewrap_paragraph(text, p, column, selection.0)).collect();
+    let first = rewrapped.first()?;
+    let last = rewrapped.last()?;
+    let range = (first.range.0, last.range.1);
+
+    let mut replacement = String::new();
+    let mut cursor = range.0;
+    let mut caret = None;
+    for para in &rewrapped {
+        replacement.push_str(&text[cursor..para.range.0]);
+        if let Some(offset) = para.caret {
+            caret = Some(range.0 + replacement.len() + offset);
+        }
+        replacement.push_str(&para.text);
+        cursor = para.range.1;
+    }
+    if replacement == text[range.0..range.1] {
+        return None;
+    }
+
+    let new_text = format!("{}{}{}", &text[..range.0], replacement, &text[range.1..]);
+    if !spans_equivalent(text, &spans, &new_text, &parse(&new_text)) {
+        return None;
+    }
+    let caret = caret.unwrap_or(if selection.0 < range.0 { selection.0 } else { range.0 + replacement.len() });
+    Some(TextEdit { range, replacement, caret })
+}
+
+/// One paragraph's new text, covering whole source lines.
+struct RewrappedParagraph {
+    range: (usize, usize),
+    text: String,
+    /// Where `caret` lands within `text`, if it was inside the paragraph.
+    caret: Option<usize>,
+}
+
+fn rewrap_paragraph(text: &str, para: &MarkdownSpan, column: Option<usize>, caret: usize) -> RewrappedParagraph {
+    fn protected(spans: &[MarkdownSpan], out: &mut Vec<(usize, usize)>) {
+        for span in spans {
+            match span.kind {
+                NodeKind::Code
+                | NodeKind::Link { .. }
+                | NodeKind::Image { .. }
+                | NodeKind::Math
+                | NodeKind::HtmlInline
+                | NodeKind::Footnote => out.push(span.source_range),
+                _ => protected(&span.children, out),
+            }
+        }
+    }
+    let mut keep_whole = Vec::new();
+    protected(&para.children, &mut keep_whole);
+    let in_protected = |pos: usize| keep_whole.iter().any(|&(s, e)| pos > s && pos < e);
+
+    let (para_start, para_end) = para.source_range;
+    let (line_start, _) = line_bounds(text, para_start);
+    let (_, line_end) = line_bounds(text, para_end.saturating_sub(1).max(para_start));
+    let first_prefix = &text[line_start..para_start];
+    let cont_prefix: String = first_prefix
+        .chars()
+        .map(|c| if c == '>' || c == '\t' { c } else { ' ' })
+        .collect();
+
+    // ── Split the source into words (absolute ranges) and hard breaks ──────
+    // Newlines inside protected spans stay part of the word; the container
+    // prefix on each following line is skipped.
+    let mut words: Vec<(usize, usize)> = Vec::new();
+    let mut hard_break_after: Vec<bool> = Vec::new();
+    let mut word_start: Option<usize> = None;
+    let mut skipping_prefix = false;
+    for (offset, ch) in text[para_start..line_end].char_indices() {
+        let at = para_start + offset;
+        if skipping_prefix {
+            if matches!(ch, ' ' | '\t' | '>') {
+                continue;
+            }
+            skipping_prefix = false;
+        }
+        if in_protected(at) {
+            word_start.get_or_insert(at);
+            continue;
+        }
+        if ch.is_whitespace() {
+            if let Some(start) = word_start.take() {
+                words.push((start, at));
+                hard_break_after.push(false);
+            }
+            if ch == '\n' {
+                let before = &text[..at];
+                if before.ends_with('\\') || before.ends_with("  ") {
+                    if let Some(last) = hard_break_after.last_mut() {
+                        *last = true;
+                    }
+                }
+                skipping_prefix = true;
+            }
+        } else {
+            word_start.get_or_insert(at);
+        }
+    }
+    if let Some(start) = word_start {
+        words.push((start, line_end));
+        hard_break_after.push(false);
+    }
+
+    // ── Lay the words out again ────────────────────────────────────────────
+    let normalize = |w: &str| -> String {
+        w.split('\n')
+            .enumerate()
+            .map(|(i, part)| if i == 0 { part } else { part.trim_start_matches([' ', '\t', '>']) })
+            .collect::<Vec<_>>()
+            .join(" ")
+    };
+    let mut out = String::from(first_prefix);
+    let mut line_width = first_prefix.chars().count();
+    let mut line_has_word = false;
+    let mut new_caret = None;
+    for (i, &(start, end)) in words.iter().enumerate() {
+        let word = normalize(&text[start..end]);
+        let width = word.chars().count();
+        if line_has_word {
+            let overflow = column.is_some_and(|col| line_width + 1 + width > col);
+            if overflow && !starts_block(&word) {
+                out.push('\n');
+                out.push_str(&cont_prefix);
+                line_width = cont_prefix.chars().count();
+            } else {
+                out.push(' ');
+                line_width += 1;
+            }
+        }
+        if caret >= start && caret <= end {
+            new_caret = Some(out.len() + (caret - start).min(word.len()));
+        } else if caret > end && words.get(i + 1).is_none_or(|&(next, _)| caret < next) && caret <= para_end {
+            new_caret = Some(out.len() + word.len());
+        }
+        out.push_str(&word);
+        line_width += width;
+        line_has_word = true;
+
+        if hard_break_after[i] && i + 1 < words.len() {
+            if !word.ends_with('\\') {
+                out.push_str("  ");
+            }
+            out.push('\n');
+            out.push_str(&cont_prefix);
+            line_width = cont_prefix.chars().count();
+            line_has_word = false;
+        }
+    }
+
+    RewrappedParagraph { range: (line_start, line_end), text: out, caret: new_caret }
+}
+
+/// Whether `word` at the start of a line would begin a new block
+/// (list item, heading, blockquote, fence, setext underline or HTML block).
+fn starts_block(word: &str) -> bool {
+    let digits = word.bytes().take_while(u8::is_ascii_digit).count();
+    matches!(word, "-" | "+" | "*")
+        || (word.len() <= 6 && word.bytes().all(|b| b == b'#'))
+        || ((1..=9).contains(&digits) && matches!(&word[digits..], "." | ")"))
+        || word.starts_with('>')
+        || word.starts_with('<')
+        || word.starts_with("```")
+        || word.starts_with("~~~")
+        || word.bytes().all(|b| b == b'=')
+        || word.bytes().all(|b| b == b'-')
+}
@@ -0,0 +1,886 @@
+//! HTML → Markdown conversion for smart paste.
+//!
+//! Browsers, Confluence and Google Docs put an HTML flavour on the
+//! pasteboard next to the plain string. This module turns that HTML back into
+//! Markdown in mdit's dialect so headings, links, lists and tables survive a
+//! paste. The tokenizer is deliberately forgiving: clipboard HTML is usually
+//! a fragment with unclosed tags, inline styles and stray markup.
+//!
+//! Pure Rust, no AppKit — the `paste:` override in `text_view.rs` feeds it.
+
+use crate::editor::formatting::{link_destination, ColumnAlign, MarkdownTable};
+
+// ---------------------------------------------------------------------------
+// Public API
+// ---------------------------------------------------------------------------
+
+/// Convert an HTML document or fragment into Markdown.
+///
+/// Blocks are separated by a blank line; the result has no leading or
+/// trailing whitespace.
+pub fn html_to_markdown(html: &str) -> String {
+    let root = build_tree(html);
+    let mut blocks = Vec::new();
+    render_blocks(&root.children, &mut blocks);
+    blocks.iter().map(|b| b.text.as_str()).collect::<Vec<_>>().join("\n\n")
+}
+
+/// The Markdown to paste for clipboard `html`, or `None` to fall back to a
+/// plain-text paste.
+///
+/// The plain-text flavour wins when the conversion adds no Markdown
+/// structure — e.g. code copied from an editor, which ships as a pile of
+/// styled `<span>`s.
+pub fn html_paste_markdown(html: &str, plain: Option<&str>) -> Option<String> {
+    let markdown = html_to_markdown(html);
+    if markdown.is_empty() {
+        return None;
+    }
+    let squash = |s: &str| {
+        s.split_whitespace()
+            .collect::<String>()
+            .replace('\\', "")
+    };
+    match plain {
+        Some(plain) if squash(plain) == squash(&markdown) => None,
+        _ => Some(markdown),
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tokenizer and tree builder
+// ---------------------------------------------------------------------------
+
+#[derive(Debug)]
+enum Node {
+    Element(Element),
+    Text(String),
+}
+
+#[derive(Debug, Default)]
+struct Element {
+    name: String,
+    attrs: Vec<(String, String)>,
+    children: Vec<Node>,
+}
+
+impl Element {
+    fn attr(&self, name: &str) -> Option<&str> {
+        self.attrs
+            .iter()
+            .find(|(k, _)| k == name)
+            .map(|(_, v)| v.trim())
+            .filter(|v| !v.is_empty())
+    }
+
+    /// The lower-cased value of CSS `property` in the inline `style` attribute.
+    fn style(&self, property: &str) -> Option<String> {
+        self.attr("style")?.split(';').find_map(|decl| {
+            let (key, value) = decl.split_once(':')?;
+            (key.trim().eq_ignore_ascii_case(property)).then(|| value.trim().to_ascii_lowercase())
+        })
+    }
+}
+
+/// Elements that never have children.
+const VOID: &[&str] = &[
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "param",
+    "source", "track", "wbr",
+];
+
+/// Elements whose contents are raw text that must not be parsed as markup.
+const RAW_TEXT: &[&str] = &["script", "style", "textarea", "title"];
+
+/// Elements dropped together with everything inside them.
+const SKIPPED: &[&str] = &[
+    "head", "script", "style", "title", "template", "noscript", "textarea", "select", "button",
+    "svg", "math", "iframe", "object",
+];
+
+/// Block-level elements: they end the current paragraph.
+const BLOCK: &[&str] = &[
+    "address", "article", "aside", "blockquote", "body", "center", "dd", "details", "dialog",
+    "div", "dl", "dt", "fieldset", "figcaption", "figure", "footer", "form", "h1", "h2", "h3",
+    "h4", "h5", "h6", "header", "hgroup", "hr", "html", "li", "main", "nav", "ol", "p", "pre",
+    "section", "summary", "table", "tbody", "td", "tfoot", "th", "thead", "tr", "ul",
+];
+
+fn is_block(name: &str) -> bool {
+    BLOCK.contains(&name)
+}
+
+/// Parse `html` into an element tree rooted at a nameless element.
+///
+/// Recovers from bad markup the way browsers roughly do: unknown end tags are
+/// ignored, an end tag closes everything opened after its start tag, and
+/// `<li>`, `<p>`, `<tr>` and table cells close their open predecessors.
+fn build_tree(html: &str) -> Element {
+    let mut stack = vec![Element::default()];
+    let mut rest = html;
+
+    while !rest.is_empty() {
+        let Some(lt) = rest.find('<') else {
+            push_text(&mut stack, rest);
+            break;
+        };
+        push_text(&mut stack, &rest[..lt]);
+        rest = &rest[lt..];
+
+        if let Some(after) = rest.strip_prefix("<!--") {
+            rest = after.find("-->").map_or("", |end| &after[end + 3..]);
+        } else if rest.starts_with("<!") || rest.starts_with("<?") {
+            rest = rest.find('>').map_or("", |end| &rest[end + 1..]);
+        } else if let Some((name, after)) = rest.strip_prefix("</").and_then(tag_name) {
+            close_element(&mut stack, &name);
+            rest = after.find('>').map_or("", |end| &after[end + 1..]);
+        } else if let Some((name, after)) = rest.strip_prefix('<').and_then(tag_name) {
+            let (attrs, self_closing, after) = parse_attrs(after);
+            rest = after;
+            open_element(&mut stack, Element { name: name.clone(), attrs, children: Vec::new() });
+            if self_closing || VOID.contains(&name.as_str()) {
+                pop_element(&mut stack);
+            } else if RAW_TEXT.contains(&name.as_str()) {
+                let end = find_ignore_case(rest, &format!("</{name}")).unwrap_or(rest.len());
+                push_text(&mut stack, &rest[..end]);
+                rest = &rest[end..];
+            }
+        } else {
+            push_text(&mut stack, "<");
+            rest = &rest[1..];
+        }
+    }
+
+    while stack.len() > 1 {
+        pop_element(&mut stack);
+    }
+    stack.pop().unwrap_or_default()
+}
+
+/// Split a tag name off the front of `s`, lower-cased.
+fn tag_name(s: &str) -> Option<(String, &str)> {
+    if !s.starts_with(|c: char| c.is_ascii_alphabetic()) {
+        return None;
+    }
+    let len = s
+        .find(|c: char| !(c.is_ascii_alphanumeric() || c == '-' || c == ':'))
+        .unwrap_or(s.len());
+    Some((s[..len].to_ascii_lowercase(), &s[len..]))
+}
+
+/// Parse attributes up to the closing `>`; returns them, whether the tag
+/// was self-closing, and the input after the tag.
+fn parse_attrs(mut s: &str) -> (Vec<(String, String)>, bool, &str) {
+    let mut attrs = Vec::new();
+    loop {
+        s = s.trim_start();
+        if let Some(after) = s.strip_prefix("/>") {
+            return (attrs, true, after);
+        }
+        if let Some(after) = s.strip_prefix('>') {
+            return (attrs, false, after);
+        }
+        if s.is_empty() {
+            return (attrs, false, s);
+        }
+        let name_len = s
+            .find(|c: char| c.is_whitespace() || c == '=' || c == '>' || c == '/')
+            .unwrap_or(s.len())
+            .max(1);
+        let name = s[..name_len].to_ascii_lowercase();
+        s = s[name_len..].trim_start();
+        let mut value = String::new();
+        if let Some(after) = s.strip_prefix('=') {
+            let after = after.trim_start();
+            let (raw, rest) = match after.chars().next() {
+                Some(q @ ('"' | '\'')) => {
+                    let body = &after[1..];
+                    let end = body.find(q).unwrap_or(body.len());
+                    (&body[..end], body.get(end + 1..).unwrap_or(""))
+                }
+                _ => {
+                    let end = after
+                        .find(|c: char| c.is_whitespace() || c == '>')
+                        .unwrap_or(after.len());
+                    (&after[..end], &after[end..])
+                }
+            };
+            value = decode_entities(raw);
+            s = rest;
+        }
+        if name != "/" {
+            attrs.push((name, value));
+        }
+    }
+}
+
+fn find_ignore_case(haystack: &str, needle: &str) -> Option<usize> {
+    haystack
+        .to_ascii_lowercase()
+        .find(&needle.to_ascii_lowercase())
+}
+
+fn push_text(stack: &mut [Element], raw: &str) {
+    if raw.is_empty() {
+        return;
+    }
+    let text = decode_entities(raw);
+    if let Some(parent) = stack.last_mut() {
+        match parent.children.last_mut() {
+            Some(Node::Text(prev)) => prev.push_str(&text),
+            _ => parent.children.push(Node::Text(text)),
+        }
+    }
+}
+
+fn pop_element(stack: &mut Vec<Element>) {
+    if stack.len() > 1 {
+        let element = stack.pop().unwrap_or_default();
+        if let Some(parent) = stack.last_mut() {
+            parent.children.push(Node::Element(element));
+        }
+    }
+}
+
+/// Pop up to and including the innermost open `names` element, provided no
+/// element in `boundaries` sits between it and the top of the stack.
+fn close_implied(stack: &mut Vec<Element>, names: &[&str], boundaries: &[&str]) {
+    for i in (1..stack.len()).rev() {
+        let name = stack[i].name.as_str();
+        if names.contains(&name) {
+            while stack.len() > i {
+                pop_element(stack);
+            }
+            return;
+        }
+        if boundaries.contains(&name) {
+            return;
+        }
+    }
+}
+
+fn open_element(stack: &mut Vec<Element>, element: Element) {
+    match element.name.as_str() {
+        "li" => close_implied(stack, &["li"], &["ul", "ol"]),
+        "dt" | "dd" => close_implied(stack, &["dt", "dd"], &["dl"]),
+        "tr" => close_implied(stack, &["tr"], &["table"]),
+        "td" | "th" => close_implied(stack, &["td", "th"], &["tr", "table"]),
+        "thead" | "tbody" | "tfoot" => {
+            close_implied(stack, &["thead", "tbody", "tfoot"], &["table"])
+        }
+        _ => {}
+    }
+    // A block start ends an open paragraph, unless a block sits in between.
+    if is_block(&element.name) {
+        let paragraph = stack.iter().rposition(|e| e.name == "p");
+        if let Some(i) = paragraph {
+            if !stack[i + 1..].iter().any(|e| is_block(&e.name)) {
+                while stack.len() > i {
+                    pop_element(stack);
+                }
+            }
+        }
+    }
+    stack.push(element);
+}
+
+fn close_element(stack: &mut Vec<Element>, name: &str) {
+    if let Some(i) = stack.iter().skip(1).rposition(|e| e.name == name) {
+        while stack.len() > i + 1 {
+            pop_element(stack);
+        }
+    }
+}
+
+/// Decode character references: the common named entities plus numeric ones.
+/// Unknown entities are kept verbatim.
+pub(crate) fn decode_entities(s: &str) -> String {
+    if !s.contains('&') {
+        return s.to_string();
+    }
+    let mut out = String::with_capacity(s.len());
+    let mut rest = s;
+    while let Some(amp) = rest.find('&') {
+        out.push_str(&rest[..amp]);
+        rest = &rest[amp..];
+        let decoded = rest[1..]
+            .find(';')
+            .filter(|&end| end <= 10)
+            .and_then(|end| decode_entity(&rest[1..=end]).map(|c| (c, end + 2)));
+        match decoded {
+            Some((c, len)) => {
+                out.push(c);
+                rest = &rest[len..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+fn decode_entity(name: &str) -> Option<char> {
+    if let Some(num) = name.strip_prefix('#') {
+        let code = match num.strip_prefix(['x', 'X']) {
+            Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+            None => num.parse().ok()?,
+        };
+        return char::from_u32(code);
+    }
+    Some(match name {
+        "amp" => '&',
+        "lt" => '<',
+        "gt" => '>',
+        "quot" => '"',
+        "apos" => '\'',
+        "nbsp" => '\u{a0}',
+        "ensp" => '\u{2002}',
+        "emsp" => '\u{2003}',
+        "thinsp" => '\u{2009}',
+        "ndash" => '–',
+        "mdash" => '—',
+        "hellip" => '…',
+        "lsquo" => '‘',
+        "rsquo" => '’',
+        "ldquo" => '“',
+        "rdquo" => '”',
+        "laquo" => '«',
+        "raquo" => '»',
+        "bull" => '•',
+        "middot" => '·',
+        "times" => '×',
+        "le" => '≤',
+        "ge" => '≥',
+        "ne" => '≠',
+        "larr" => '←',
+        "rarr" => '→',
+        "copy" => '©',
+        "reg" => '®',
+        "trade" => '™',
+        "deg" => '°',
+        "euro" => '€',
+        "zwj" => '\u{200d}',
+        "zwnj" => '\u{200c}',
+        _ => return None,
+    })
+}
+
+// ---------------------------------------------------------------------------
+// Block rendering
+// ---------------------------------------------------------------------------
+
+/// A rendered Markdown block.
+struct Block {
+    text: String,
+    /// Lists may follow a paragraph inside a list item without a blank line.
+    is_list: bool,
+}
+
+impl Block {
+    fn new(text: String) -> Self {
+        Self { text, is_list: false }
+    }
+}
+
+fn render_blocks(nodes: &[Node], out: &mut Vec<Block>) {
+    let mut paragraph = String::new();
+    for node in nodes {
+        match node {
+            Node::Text(text) => paragraph.push_str(&inline_text(text, Style::default())),
+            Node::Element(e) if SKIPPED.contains(&e.name.as_str()) => {}
+            Node::Element(e) if is_block(&e.name) || contains_block(e) => {
+                flush_paragraph(&mut paragraph, out);
+                render_block(e, out);
+            }
+            Node::Element(e) => paragraph.push_str(&render_inline(e, Style::default())),
+        }
+    }
+    flush_paragraph(&mut paragraph, out);
+}
+
+/// An inline element wrapping blocks (Google Docs wraps the whole document
+/// in a `<b>`) is treated as a transparent container.
+fn contains_block(e: &Element) -> bool {
+    e.children.iter().any(|child| match child {
+        Node::Element(c) => is_block(&c.name) || contains_block(c),
+        Node::Text(_) => false,
+    })
+}
+
+fn flush_paragraph(paragraph: &mut String, out: &mut Vec<Block>) {
+    for lines in split_lines(paragraph) {
+        let lines: Vec<String> = lines.iter().map(|l| escape_line_start(l)).collect();
+        out.push(Block::new(lines.join("\\\n")));
+    }
+    paragraph.clear();
+}
+
+/// Normalise rendered inline content: collapse spaces, trim each line, and
+/// group the lines (separated by `<br>`) into paragraphs at empty lines.
+fn split_lines(inline: &str) -> Vec<Vec<String>> {
+    let mut groups = vec![Vec::new()];
+    for line in inline.replace('\u{a0}', " ").split('\n') {
+        let line = line.split(' ').filter(|w| !w.is_empty()).collect::<Vec<_>>().join(" ");
+        match groups.last_mut() {
+            Some(group) if !line.is_empty() => group.push(line),
+            Some(group) if !group.is_empty() => groups.push(Vec::new()),
+            _ => {}
+        }
+    }
+    groups.retain(|g| !g.is_empty());
+    groups
+}
+
+/// Inline content flattened onto a single line with `separator` between lines.
+fn single_line(inline: &str, separator: &str) -> String {
+    split_lines(inline)
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>()
+        .join(separator)
+}
+
+fn render_block(e: &Element, out: &mut Vec<Block>) {
+    match e.name.as_str() {
+        "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+            let level = (e.name.as_bytes()[1] - b'0') as usize;
+            let text = single_line(&render_children(e, Style::default()), " ");
+            if !text.is_empty() {
+                out.push(Block::new(format!("{} {}", "#".repeat(level), text)));
+            }
+        }
+        "hr" => out.push(Block::new("---".to_string())),
+        "pre" => out.push(Block::new(code_block(e))),
+        "ul" | "ol" => {
+            let list = render_list(e);
+            if !list.is_empty() {
+                out.push(Block { text: list, is_list: true });
+            }
+        }
+        "table" => out.extend(render_table(e).map(Block::new)),
+        "blockquote" => {
+            let mut inner = Vec::new();
+            render_blocks(&e.children, &mut inner);
+            if !inner.is_empty() {
+                let body = inner.iter().map(|b| b.text.as_str()).collect::<Vec<_>>().join("\n\n");
+                let quoted: Vec<String> = body
+                    .lines()
+                    .map(|l| if l.is_empty() { ">".to_string() } else { format!("> {l}") })
+                    .collect();
+                out.push(Block::new(quoted.join("\n")));
+            }
+        }
+        _ => render_blocks(&e.children, out),
+    }
+}
+
+fn join_blocks(blocks: &[Block], separator: &str) -> String {
+    let mut text = String::new();
+    for (i, block) in blocks.iter().enumerate() {
+        if i > 0 {
+            text.push_str(if block.is_list { "\n" } else { separator });
+        }
+        text.push_str(&block.text);
+    }
+    text
+}
+
+/// A fenced code block, with the language taken from the usual class names.
+fn code_block(pre: &Element) -> String {
+    let mut code = String::new();
+    raw_text(&pre.children, &mut code);
+    let code = code.replace("\r\n", "\n").replace('\u{a0}', " ");
+    let code = code.strip_prefix('\n').unwrap_or(&code).trim_end();
+
+    let inner_code = pre.children.iter().find_map(|c| match c {
+        Node::Element(e) if e.name == "code" => Some(e),
+        _ => None,
+    });
+    let language = inner_code
+        .and_then(code_language)
+        .or_else(|| code_language(pre))
+        .unwrap_or_default();
+
+    let longest_run = code
+        .split(|c| c != '`')
+        .map(str::len)
+        .max()
+        .unwrap_or(0);
+    let fence = "`".repeat((longest_run + 1).max(3));
+    format!("{fence}{language}\n{code}\n{fence}")
+}
+
+fn code_language(e: &Element) -> Option<String> {
+    let from_class = e.attr("class").and_then(|class| {
+        class.split_whitespace().find_map(|c| {
+            ["language-", "lang-", "highlight-source-"]
+                .iter()
+                .find_map(|prefix| c.strip_prefix(prefix))
+        })
+    });
+    // Confluence's code macro: data-syntaxhighlighter-params="brush: java; gutter: false"
+    let from_brush = || {
+        e.attr("data-syntaxhighlighter-params")?
+            .split(';')
+            .find_map(|p| p.trim().strip_prefix("brush:"))
+            .map(str::trim)
+    };
+    from_class
+        .or_else(|| e.attr("data-lang"))
+        .or_else(from_brush)
+        .filter(|l| l.chars().all(|c| c.is_ascii_alphanumeric() || "+-#_.".contains(c)))
+        .map(str::to_ascii_lowercase)
+}
+
+fn raw_text(nodes: &[Node], out: &mut String) {
+    for node in nodes {
+        match node {
+            Node::Text(t) => out.push_str(t),
+            Node::Element(e) if e.name == "br" => out.push('\n'),
+            Node::Element(e) if SKIPPED.contains(&e.name.as_str()) => {}
+            Node::Element(e) => {
+                raw_text(&e.children, out);
+                if matches!(e.name.as_str(), "div" | "p" | "li" | "tr") && !out.ends_with('\n') {
+                    out.push('\n');
+                }
+            }
+        }
+    }
+}
+
+fn render_list(list: &Element) -> String {
+    let ordered = list.name == "ol";
+    let mut number: usize = list.attr("start").and_then(|s| s.parse().ok()).unwrap_or(1);
+    let mut items: Vec<Vec<Block>> = Vec::new();
+
+    for child in &list.children {
+        match child {
+            Node::Element(li) if li.name == "li" => {
+                let mut blocks = Vec::new();
+                render_blocks(&li.children, &mut blocks);
+                items.push(blocks);
+            }
+            // A nested list written as a sibling of its parent item.
+            Node::Element(e) if (e.name == "ul" || e.name == "ol") && !items.is_empty() => {
+                let nested = render_list(e);
+                if let (Some(last), false) = (items.last_mut(), nested.is_empty()) {
+                    last.push(Block { text: nested, is_list: true });
+                }
+            }
+            Node::Text(t) if t.trim().is_empty() => {}
+            other => {
+                let mut blocks = Vec::new();
+                render_blocks(std::slice::from_ref(other), &mut blocks);
+                if !blocks.is_empty() {
+                    items.push(blocks);
+                }
+            }
+        }
+    }
+
+    let mut lines = Vec::new();
+    for blocks in items {
+        let marker = if ordered { format!("{number}. ") } else { "- ".to_string() };
+        number += 1;
+        let indent = " ".repeat(marker.len());
+        let body = join_blocks(&blocks, "\n\n");
+        let mut body_lines = body.split('\n');
+        let first = body_lines.next().unwrap_or_default();
+        lines.push(format!("{marker}{first}").trim_end().to_string());
+        for line in body_lines {
+            lines.push(if line.is_empty() { String::new() } else { format!("{indent}{line}") });
+        }
+    }
+    lines.join("\n")
+}
+
+fn render_table(table: &Element) -> Option<String> {
+    let mut rows: Vec<&Element> = Vec::new();
+    collect_rows(table, &mut rows);
+
+    let mut records = Vec::new();
+    let mut alignments = Vec::new();
+    for (i, row) in rows.iter().enumerate() {
+        let mut record = Vec::new();
+        for cell in row.children.iter().filter_map(|c| match c {
+            Node::Element(e) if e.name == "td" || e.name == "th" => Some(e),
+            _ => None,
+        }) {
+            if i == 0 {
+                alignments.push(cell_alignment(cell));
+            }
+            record.push(render_cell(cell));
+            let span: usize = cell.attr("colspan").and_then(|s| s.parse().ok()).unwrap_or(1);
+            for _ in 1..span.min(64) {
+                if i == 0 {
+                    alignments.push(ColumnAlign::None);
+                }
+                record.push(String::new());
+            }
+        }
+        if !record.is_empty() {
+            records.push(record);
+        }
+    }
+
+    let mut md = MarkdownTable::from_records(&records)?;
+    alignments.resize(md.column_count(), ColumnAlign::None);
+    md.alignments = alignments;
+    Some(md.to_markdown())
+}
+
+fn collect_rows<'a>(e: &'a Element, rows: &mut Vec<&'a Element>) {
+    for child in &e.children {
+        if let Node::Element(c) = child {
+            match c.name.as_str() {
+                "tr" => rows.push(c),
+                "thead" | "tbody" | "tfoot" => collect_rows(c, rows),
+                _ => {}
+            }
+        }
+    }
+}
+
+fn cell_alignment(cell: &Element) -> ColumnAlign {
+    let align = cell
+        .attr("align")
+        .map(str::to_ascii_lowercase)
+        .or_else(|| cell.style("text-align"));
+    match align.as_deref() {
+        Some("left") => ColumnAlign::Left,
+        Some("center") => ColumnAlign::Center,
+        Some("right") => ColumnAlign::Right,
+        _ => ColumnAlign::None,
+    }
+}
+
+/// Cell content on one line; line breaks and paragraphs become `<br>`.
+/// Pipes are escaped later by [`MarkdownTable::from_records`].
+fn render_cell(cell: &Element) -> String {
+    let mut blocks = Vec::new();
+    render_blocks(&cell.children, &mut blocks);
+    blocks
+        .iter()
+        .map(|b| b.text.replace("\\\n", "<br>").replace('\n', " "))
+        .collect::<Vec<_>>()
+        .join("<br>")
+}
+
+// ---------------------------------------------------------------------------
+// Inline rendering
+// ---------------------------------------------------------------------------
+
+/// Formatting already in effect, so nested `<b><b>` does not double up.
+#[derive(Clone, Copy, Default)]
+struct Style {
+    strong: bool,
+    emph: bool,
+    strike: bool,
+    underline: bool,
+    highlight: bool,
+    code: bool,
+    link: bool,
+}
+
+/// Inline text with HTML whitespace collapsed and Markdown syntax escaped.
+/// Line breaks in the output come only from `<br>`.
+fn inline_text(text: &str, style: Style) -> String {
+    let collapsed: String = text
+        .split(|c: char| c.is_ascii_whitespace())
+        .collect::<Vec<_>>()
+        .join(" ");
+    if style.code {
+        collapsed
+    } else {
+        escape_inline(&collapsed)
+    }
+}
+
+fn render_children(e: &Element, style: Style) -> String {
+    e.children
+        .iter()
+        .map(|child| match child {
+            Node::Text(t) => inline_text(t, style),
+            Node::Element(c) => render_inline(c, style),
+        })
+        .collect()
+}
+
+fn render_inline(e: &Element, style: Style) -> String {
+    if SKIPPED.contains(&e.name.as_str()) {
+        return String::new();
+    }
+    if style.code {
+        return match e.name.as_str() {
+            "br" => " ".to_string(),
+            _ => render_children(e, style),
+        };
+    }
+    match e.name.as_str() {
+        "br" => "\n".to_string(),
+        "img" => image(e),
+        "input" if e.attr("type") == Some("checkbox") => {
+            let checked = e.attrs.iter().any(|(k, _)| k == "checked");
+            if checked { "[x] " } else { "[ ] " }.to_string()
+        }
+        "code" | "kbd" | "samp" | "tt" => {
+            code_span(&render_children(e, Style { code: true, ..style }))
+        }
+        "a" => link(e, style),
+        name => {
+            let weight = e.style("font-weight");
+            let bold_weight = weight.as_deref().map(|w| {
+                w.starts_with("bold") || w.parse::<u32>().is_ok_and(|n| n >= 600)
+            });
+            let strong = match name {
+                "b" | "strong" => bold_weight.unwrap_or(true),
+                _ => bold_weight.unwrap_or(false),
+            };
+            let emph = matches!(name, "i" | "em" | "cite" | "dfn" | "var")
+                || e.style("font-style").is_some_and(|s| s == "italic");
+            let strike = matches!(name, "s" | "del" | "strike")
+                || e.style("text-decoration").is_some_and(|s| s.contains("line-through"));
+            let underline = matches!(name, "u" | "ins");
+            let highlight = name == "mark";
+
+            let inner_style = Style {
+                strong: style.strong || strong,
+                emph: style.emph || emph,
+                strike: style.strike || strike,
+                underline: style.underline || underline,
+                highlight: style.highlight || highlight,
+                ..style
+            };
+            let mut text = render_children(e, inner_style);
+            for (on, already, marker) in [
+                (emph, style.emph, "_"),
+                (strong, style.strong, "**"),
+                (underline, style.underline, "__"),
+                (highlight, style.highlight, "=="),
+                (strike, style.strike, "~~"),
+                (name == "sub", false, "~"),
+                (name == "sup", false, "^"),
+            ] {
+                if on && !already {
+                    text = wrap(&text, marker);
+                }
+            }
+            // Blocks flattened into inline context (a `<p>` inside a link)
+            // still need a word break around them.
+            if is_block(name) {
+                text = format!(" {text} ");
+            }
+            text
+        }
+    }
+}
+
+/// Wrap `text` in `marker`, keeping surrounding whitespace outside the
+/// delimiters so they stay left/right-flanking.
+fn wrap(text: &str, marker: &str) -> String {
+    let is_space = |c: char| c == ' ' || c == '\n' || c == '\u{a0}';
+    let core = text.trim_matches(is_space);
+    if core.is_empty() {
+        return text.to_string();
+    }
+    let start = text.len() - text.trim_start_matches(is_space).len();
+    let end = start + core.len();
+    format!("{}{marker}{core}{marker}{}", &text[..start], &text[end..])
+}
+
+fn code_span(code: &str) -> String {
+    let code = code.replace('\u{a0}', " ");
+    let code = code.trim();
+    if code.is_empty() {
+        return String::new();
+    }
+    let longest_run = code.split(|c| c != '`').map(str::len).max().unwrap_or(0);
+    let fence = "`".repeat(longest_run + 1);
+    let pad = if code.starts_with('`') || code.ends_with('`') { " " } else { "" };
+    format!("{fence}{pad}{code}{pad}{fence}")
+}
+
+fn link(a: &Element, style: Style) -> String {
+    let text = render_children(a, Style { link: true, ..style });
+    let href = a.attr("href").unwrap_or("");
+    let useless = href.is_empty()
+        || href.starts_with('#')
+        || href.to_ascii_lowercase().starts_with("javascript:");
+    if style.link || useless {
+        return text;
+    }
+    let core = text.trim();
+    if core.is_empty() {
+        return text;
+    }
+    let absolute = href.contains("://") || href.starts_with("mailto:");
+    let inner = format!("[{core}]({}{})", link_destination(href), title(a));
+    let rendered = if absolute && (core == href || href.strip_prefix("mailto:") == Some(core)) {
+        format!("<{href}>")
+    } else {
+        inner
+    };
+    text.replacen(core, &rendered, 1)
+}
+
+fn image(img: &Element) -> String {
+    let alt = escape_inline(img.attr("alt").unwrap_or(""));
+    match img.attr("src") {
+        Some(src) if !src.starts_with("data:") => {
+            format!("![{alt}]({}{})", link_destination(src), title(img))
+        }
+        _ => alt,
+    }
+}
+
+fn title(e: &Element) -> String {
+    e.attr("title")
+        .map(|t| format!(" \"{}\"", t.replace('"', "\\\"")))
+        .unwrap_or_default()
+}
+
+/// Backslash-escape characters that would otherwise start Markdown syntax.
+fn escape_inline(text: &str) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let mut out = String::with_capacity(text.len());
+    for (i, &c) in chars.iter().enumerate() {
+        let prev = i.checked_sub(1).map(|p| chars[p]);
+        let next = chars.get(i + 1).copied();
+        let word = |c: Option<char>| c.is_some_and(char::is_alphanumeric);
+        let escape = match c {
+            '\\' | '*' | '`' | '[' | ']' => true,
+            '_' => !(word(prev) && word(next)),
+            '<' => next.is_some_and(|n| n.is_ascii_alphabetic() || "/!?".contains(n)),
+            '~' | '^' => next.is_some_and(|n| !n.is_whitespace()),
+            '=' => next == Some('=') && prev != Some('='),
+            '$' => next.is_some_and(|n| !n.is_whitespace() && !n.is_ascii_digit()),
+            _ => false,
+        };
+        if escape {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out
+}
+
+/// Escape a line start that would otherwise open a block construct.
+fn escape_line_start(line: &str) -> String {
+    if line.starts_with(['#', '>', '|'])
+        || ["- ", "+ ", "= ", "---"].iter().any(|p| line.starts_with(p))
+        || line == "-"
+        || line == "+"
+    {
+        return format!("\\{line}");
+    }
+    let digits = line.bytes().take_while(u8::is_ascii_digit).count();
+    if (1..=9).contains(&digits) {
+        let after = &line[digits..];
+        if after.starts_with(". ") || after.starts_with(") ") || after == "." || after == ")" {
+            return format!("{}\\{}", &line[..digits], after);
+        }
+    }
+    line.to_string()
+}
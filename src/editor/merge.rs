@@ -0,0 +1,139 @@
+//! Three-way line merge of two edited versions of a common base.
+//!
+//! Used when a document with unsaved changes is also changed on disk: the
+//! base is the text last saved (or loaded), "mine" the buffer and "theirs"
+//! the new file. Changes on only one side are taken as they are; where both
+//! sides changed the same lines differently the result holds both versions
+//! between conflict markers, as `git merge` writes them.
+
+use crate::editor::diff::{line_diff, lines, DiffLine};
+
+pub const MARKER_MINE: &str = "<<<<<<< mine";
+pub const MARKER_SEPARATOR: &str = "=======";
+pub const MARKER_THEIRS: &str = ">>>>>>> on disk";
+
+/// The merged text and how many conflicts it contains.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Merge {
+    pub text: String,
+    pub conflicts: usize,
+}
+
+/// One side's replacement of base lines `start..end`.
+#[derive(Debug)]
+struct Hunk<'a> {
+    start: usize,
+    end: usize,
+    lines: Vec<&'a str>,
+}
+
+/// Merge the changes `mine` and `theirs` each made to `base`.
+pub fn merge3(base: &str, mine: &str, theirs: &str) -> Merge {
+    let base_lines = lines(base);
+    let ours = hunks(&line_diff(base, mine));
+    let other = hunks(&line_diff(base, theirs));
+
+    let mut text = String::with_capacity(mine.len().max(theirs.len()));
+    let mut conflicts = 0;
+    let mut pos = 0;
+    let (mut i, mut j) = (0, 0);
+    while i < ours.len() || j < other.len() {
+        // Gather the next cluster of hunks that touch or overlap.
+        let first_start = match (ours.get(i), other.get(j)) {
+            (Some(a), Some(b)) => a.start.min(b.start),
+            (Some(a), None) => a.start,
+            (None, Some(b)) => b.start,
+            (None, None) => unreachable!(),
+        };
+        let (start, mut end) = (first_start, first_start);
+        let (i0, j0) = (i, j);
+        loop {
+            if let Some(h) = ours.get(i).filter(|h| h.start <= end) {
+                end = end.max(h.end);
+                i += 1;
+            } else if let Some(h) = other.get(j).filter(|h| h.start <= end) {
+                end = end.max(h.end);
+                j += 1;
+            } else {
+                break;
+            }
+        }
+
+        text.extend(base_lines[pos..start].iter().copied());
+        let (ours, other) = (&ours[i0..i], &other[j0..j]);
+        let mine_part = apply(&base_lines, start, end, ours);
+        let theirs_part = apply(&base_lines, start, end, other);
+        if other.is_empty() || mine_part == theirs_part {
+            text.push_str(&mine_part);
+        } else if ours.is_empty() {
+            text.push_str(&theirs_part);
+        } else {
+            conflicts += 1;
+            push_line(&mut text, MARKER_MINE);
+            push_block(&mut text, &mine_part);
+            push_line(&mut text, MARKER_SEPARATOR);
+            push_block(&mut text, &theirs_part);
+            push_line(&mut text, MARKER_THEIRS);
+        }
+        pos = end;
+    }
+    text.extend(base_lines[pos..].iter().copied());
+    Merge { text, conflicts }
+}
+
+/// The changed runs of a diff, located in the base (old) text.
+fn hunks<'a>(diff: &[DiffLine<'a>]) -> Vec<Hunk<'a>> {
+    let mut out: Vec<Hunk> = Vec::new();
+    let mut base = 0;
+    let mut open = false;
+    for line in diff {
+        match *line {
+            DiffLine::Same(_) => {
+                base += 1;
+                open = false;
+            }
+            DiffLine::Removed(_) | DiffLine::Added(_) => {
+                if !open {
+                    out.push(Hunk { start: base, end: base, lines: Vec::new() });
+                    open = true;
+                }
+                let hunk = out.last_mut().expect("hunk opened above");
+                match *line {
+                    DiffLine::Removed(_) => {
+                        base += 1;
+                        hunk.end = base;
+                    }
+                    DiffLine::Added(l) => hunk.lines.push(l),
+                    DiffLine::Same(_) => {}
+                }
+            }
+        }
+    }
+    out
+}
+
+/// Base lines `start..end` with `hunks` (all inside that range) applied.
+fn apply(base: &[&str], start: usize, end: usize, hunks: &[Hunk]) -> String {
+    let mut out = String::new();
+    let mut pos = start;
+    for hunk in hunks {
+        out.extend(base[pos..hunk.start].iter().copied());
+        out.extend(hunk.lines.iter().copied());
+        pos = hunk.end;
+    }
+    out.extend(base[pos..end].iter().copied());
+    out
+}
+
+fn push_line(out: &mut String, line: &str) {
+    out.push_str(line);
+    out.push('\n');
+}
+
+/// Append `block`, ending it with a newline so a marker can follow.
+fn push_block(out: &mut String, block: &str) {
+    out.push_str(block);
+    if !block.is_empty() && !block.ends_with('\n') {
+        out.push('\n');
+    }
+}
@@ -4,9 +4,20 @@ pub mod renderer;
 pub mod editor_renderer;
 pub mod apply;
 pub mod cursor_tracker;
+pub mod block_move;
+pub mod snippets;
+pub mod tasks;
+pub mod search;
+pub mod folder_search;
+pub mod session;
+pub mod diff;
+pub mod recovery;
+pub mod merge;
+pub mod file_watcher;
 pub mod image_handler;
 pub mod math_view;
 pub mod document_state;
 pub mod formatting;
+pub mod html_to_markdown;
 pub mod tab_manager;
 pub mod view_mode;
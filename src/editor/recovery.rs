@@ -0,0 +1,133 @@
+//! Crash recovery: autosaved snapshots of documents with unsaved changes.
+//!
+//! Each dirty document is periodically written to its own file in the
+//! recovery directory, named after the document's recovery id:
+//!
+//! ```text
+//! mdit-recovery 1
+//! saved 1760000000
+//! path /Users/me/notes/todo.md
+//!
+//! …the unsaved text, verbatim…
+//! ```
+//!
+//! `saved` is a Unix timestamp; `path` is omitted for untitled documents.
+//! The header ends at the first blank line. A snapshot is deleted once its
+//! document is saved or discarded, so whatever is left at launch was lost
+//! by a crash (or a quit that didn't save).
+
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use uuid::Uuid;
+
+use crate::editor::diff::{line_diff, render_diff};
+
+const HEADER: &str = "mdit-recovery 1";
+const EXTENSION: &str = "mdrecovery";
+
+/// One autosaved document.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Snapshot {
+    /// The file the document belongs to; `None` when untitled.
+    pub original: Option<PathBuf>,
+    /// Seconds since the Unix epoch.
+    pub saved_at: u64,
+    pub text: String,
+}
+
+/// `~/Library/Application Support/mdit/Recovery`.
+pub fn recovery_dir() -> Option<PathBuf> {
+    let home = std::env::var_os("HOME")?;
+    Some(PathBuf::from(home).join("Library/Application Support/mdit/Recovery"))
+}
+
+/// A fresh recovery id for a new document.
+pub fn new_id() -> String {
+    Uuid::new_v4().to_string()
+}
+
+/// Where the snapshot of document `id` lives inside `dir`.
+pub fn snapshot_path(dir: &Path, id: &str) -> PathBuf {
+    dir.join(format!("{id}.{EXTENSION}"))
+}
+
+/// Seconds since the Unix epoch.
+pub fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs())
+}
+
+/// Every readable snapshot in `dir` with its file, newest first.
+pub fn load_all(dir: &Path) -> Vec<(PathBuf, Snapshot)> {
+    let Ok(entries) = std::fs::read_dir(dir) else { return Vec::new() };
+    let mut found: Vec<(PathBuf, Snapshot)> = entries
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| p.extension().is_some_and(|e| e == EXTENSION))
+        .filter_map(|p| {
+            let snapshot = Snapshot::parse(&std::fs::read_to_string(&p).ok()?)?;
+            Some((p, snapshot))
+        })
+        .collect();
+    found.sort_by(|(pa, a), (pb, b)| b.saved_at.cmp(&a.saved_at).then_with(|| pa.cmp(pb)));
+    found
+}
+
+impl Snapshot {
+    /// The recovery file's contents. A path containing a newline can't be
+    /// recorded; such a document is recovered as untitled.
+    pub fn serialize(&self) -> String {
+        let mut out = format!("{HEADER}\nsaved {}\n", self.saved_at);
+        if let Some(path) = &self.original {
+            let path = path.to_string_lossy();
+            if !path.contains('\n') {
+                out.push_str(&format!("path {path}\n"));
+            }
+        }
+        out.push('\n');
+        out.push_str(&self.text);
+        out
+    }
+
+    /// Parse a recovery file. `None` unless it has the recovery header and
+    /// a complete header block.
+    pub fn parse(src: &str) -> Option<Self> {
+        let rest = src.strip_prefix(HEADER)?.strip_prefix('\n')?;
+        let (header, text) = rest.split_once("\n\n").or_else(|| {
+            // No header fields at all: the blank line follows the first line.
+            rest.strip_prefix('\n').map(|text| ("", text))
+        })?;
+        let mut snapshot = Snapshot { original: None, saved_at: 0, text: text.to_string() };
+        for line in header.lines() {
+            match line.split_once(' ') {
+                Some(("saved", secs)) => snapshot.saved_at = secs.parse().unwrap_or(0),
+                Some(("path", path)) if !path.is_empty() => {
+                    snapshot.original = Some(PathBuf::from(path));
+                }
+                _ => {}
+            }
+        }
+        Some(snapshot)
+    }
+
+    /// The name shown for the document: its file name, or "Untitled".
+    pub fn title(&self) -> String {
+        self.original
+            .as_deref()
+            .and_then(|p| p.file_name())
+            .map_or_else(|| "Untitled".to_string(), |n| n.to_string_lossy().into_owned())
+    }
+}
+
+/// What the recovery prompt says about a snapshot: a diff against the file
+/// on disk (`on_disk`, if it could be read), or why there is none.
+pub fn describe_changes(snapshot: &Snapshot, on_disk: Option<&str>) -> String {
+    let lines = snapshot.text.lines().count();
+    let noun = if lines == 1 { "line" } else { "lines" };
+    match (&snapshot.original, on_disk) {
+        (None, _) => format!("Untitled document, {lines} {noun}.\n"),
+        (Some(_), None) => format!("The file is no longer on disk; {lines} {noun} recovered.\n"),
+        (Some(_), Some(disk)) if disk == snapshot.text => "Same as the file on disk.\n".to_string(),
+        (Some(_), Some(disk)) => render_diff(&line_diff(disk, &snapshot.text), 2),
+    }
+}
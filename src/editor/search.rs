@@ -0,0 +1,391 @@
+//! Find and replace over the document source.
+//!
+//! Every query — literal or regex — is compiled to a `regex::Regex`, so
+//! case folding, whole-word matching and `$1`-style replacement templates
+//! behave the same in all modes. Match ranges are UTF-8 bytes; each match
+//! also carries its UTF-16 `(location, length)` for the text view.
+//!
+//! A [`SearchScope`] narrows matches to one kind of Markdown content using
+//! the parsed span tree. In Viewer mode the query runs over a
+//! [`VisibleText`] projection instead, so hidden syntax markers neither
+//! match nor break up a phrase.
+//!
+//! With `preserve_case` set, each replacement takes on the capitalization
+//! of the text it replaces (see [`match_case`]).
+
+use regex::{Regex, RegexBuilder};
+
+use crate::editor::cursor_tracker::find_containing_span;
+use crate::editor::formatting::TextEdit;
+use crate::editor::renderer::AttributeRun;
+use crate::markdown::attributes::TextAttribute;
+use crate::markdown::parser::{MarkdownSpan, NodeKind};
+
+/// The find bar toggles.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct SearchOptions {
+    pub case_sensitive: bool,
+    pub whole_word: bool,
+    pub regex: bool,
+    pub scope: SearchScope,
+    /// Replacements copy the capitalization of the match.
+    pub preserve_case: bool,
+}
+
+/// How many recent search and replace strings are kept.
+pub const HISTORY_LIMIT: usize = 20;
+
+/// Which part of the document a search looks at.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SearchScope {
+    #[default]
+    All,
+    /// Everything except code, math, link URLs and inline HTML.
+    Prose,
+    Headings,
+    /// Code spans, code blocks and math.
+    Code,
+    /// The label of links and the alt text of images.
+    LinkText,
+    /// Link and image destinations, including autolinks.
+    LinkUrls,
+}
+
+impl SearchScope {
+    /// Every scope, in menu order.
+    pub const ALL: [SearchScope; 6] = [
+        SearchScope::All,
+        SearchScope::Prose,
+        SearchScope::Headings,
+        SearchScope::Code,
+        SearchScope::LinkText,
+        SearchScope::LinkUrls,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            SearchScope::All => "All Text",
+            SearchScope::Prose => "Prose",
+            SearchScope::Headings => "Headings",
+            SearchScope::Code => "Code",
+            SearchScope::LinkText => "Link Text",
+            SearchScope::LinkUrls => "Link URLs",
+        }
+    }
+
+    /// Whether a match over `range` of `text` falls inside this scope.
+    pub fn includes(self, text: &str, spans: &[MarkdownSpan], range: (usize, usize)) -> bool {
+        let in_code = || {
+            find_containing_span(spans, range.0).is_some_and(|span| {
+                matches!(span.kind, NodeKind::Code | NodeKind::CodeBlock { .. } | NodeKind::Math)
+                    && range.1 <= span.source_range.1
+            })
+        };
+        match self {
+            SearchScope::All => true,
+            SearchScope::Prose => {
+                let ancestors = ancestors(spans, range);
+                !in_code()
+                    && !ancestors.iter().any(|s| s.kind == NodeKind::HtmlInline)
+                    && !ancestors.iter().any(|s| {
+                        matches!(link_part(text, s, range), Some(LinkPart::Url | LinkPart::Autolink))
+                    })
+            }
+            SearchScope::Headings => {
+                ancestors(spans, range).iter().any(|s| matches!(s.kind, NodeKind::Heading { .. }))
+            }
+            SearchScope::Code => in_code(),
+            SearchScope::LinkText | SearchScope::LinkUrls => {
+                let want = if self == SearchScope::LinkText { LinkPart::Text } else { LinkPart::Url };
+                ancestors(spans, range).iter().any(|s| match link_part(text, s, range) {
+                    Some(LinkPart::Autolink) => true,
+                    part => part == Some(want),
+                })
+            }
+        }
+    }
+}
+
+/// One match: byte range into the text plus the same range in UTF-16.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SearchMatch {
+    pub range: (usize, usize),
+    pub utf16: (usize, usize),
+}
+
+/// A compiled query.
+#[derive(Debug, Clone)]
+pub struct Searcher {
+    regex: Regex,
+    options: SearchOptions,
+}
+
+impl Searcher {
+    /// Compile `query`. Returns a one-line message for an invalid regex.
+    pub fn new(query: &str, options: SearchOptions) -> Result<Self, String> {
+        let pattern = if options.regex { query.to_string() } else { regex::escape(query) };
+        let regex = RegexBuilder::new(&pattern)
+            .case_insensitive(!options.case_sensitive)
+            .multi_line(true)
+            .build()
+            .map_err(|e| error_message(&e))?;
+        Ok(Self { regex, options })
+    }
+
+    pub fn options(&self) -> SearchOptions {
+        self.options
+    }
+
+    /// All non-empty matches in `text` that fall inside the scope, in order.
+    /// `spans` must come from parsing `text`; it is only consulted when the
+    /// scope is not [`SearchScope::All`].
+    pub fn find_all(&self, text: &str, spans: &[MarkdownSpan]) -> Vec<SearchMatch> {
+        self.collect(text, spans, self.raw_ranges(text))
+    }
+
+    /// Like [`Searcher::find_all`], but matching against what the viewer
+    /// shows. The returned ranges are mapped back onto `text`, so a match
+    /// may cover hidden markers it stepped over (`**bold** text`).
+    pub fn find_visible(&self, text: &str, spans: &[MarkdownSpan], visible: &VisibleText) -> Vec<SearchMatch> {
+        let ranges = self.raw_ranges(visible.as_str()).into_iter().map(|r| visible.to_source(r));
+        self.collect(text, spans, ranges.collect())
+    }
+
+    /// Non-empty, whole-word-checked match ranges in `haystack`.
+    fn raw_ranges(&self, haystack: &str) -> Vec<(usize, usize)> {
+        self.regex
+            .find_iter(haystack)
+            .filter(|m| !m.is_empty())
+            .filter(|m| !self.options.whole_word || is_whole_word(haystack, m.start(), m.end()))
+            .map(|m| (m.start(), m.end()))
+            .collect()
+    }
+
+    /// Apply the scope to ascending source ranges and attach UTF-16 ranges.
+    fn collect(&self, text: &str, spans: &[MarkdownSpan], ranges: Vec<(usize, usize)>) -> Vec<SearchMatch> {
+        let mut matches = Vec::new();
+        // Running UTF-16 position, advanced incrementally between matches.
+        let (mut byte, mut utf16) = (0, 0);
+        for (start, end) in ranges {
+            if !self.options.scope.includes(text, spans, (start, end)) {
+                continue;
+            }
+            utf16 += utf16_len(&text[byte..start]);
+            let len = utf16_len(&text[start..end]);
+            matches.push(SearchMatch { range: (start, end), utf16: (utf16, len) });
+            utf16 += len;
+            byte = end;
+        }
+        matches
+    }
+
+    /// The text that replaces `m`. In regex mode `$1`, `${name}` and `$0`
+    /// in `template` expand to the match's groups (`$$` is a literal `$`);
+    /// in literal mode the template is used verbatim. With `preserve_case`
+    /// the result then follows the match's capitalization.
+    pub fn replacement(&self, text: &str, m: &SearchMatch, template: &str) -> String {
+        let caps = if self.options.regex { self.regex.captures_at(text, m.range.0) } else { None };
+        let out = match caps {
+            Some(caps) if caps.get(0).map(|c| (c.start(), c.end())) == Some(m.range) => {
+                let mut out = String::new();
+                caps.expand(template, &mut out);
+                out
+            }
+            _ => template.to_string(),
+        };
+        if self.options.preserve_case {
+            match_case(&text[m.range.0..m.range.1], &out)
+        } else {
+            out
+        }
+    }
+
+    /// Replace every match as one edit running from the first match to the
+    /// last, so the whole replacement is a single undo step. `None` when
+    /// nothing matches.
+    pub fn replace_all_edit(&self, text: &str, spans: &[MarkdownSpan], template: &str) -> Option<TextEdit> {
+        let matches = self.find_all(text, spans);
+        let (first, last) = (matches.first()?, matches.last()?);
+        let mut replacement = String::new();
+        let mut pos = first.range.0;
+        for m in &matches {
+            replacement.push_str(&text[pos..m.range.0]);
+            replacement.push_str(&self.replacement(text, m, template));
+            pos = m.range.1;
+        }
+        let caret = first.range.0 + replacement.len();
+        Some(TextEdit { range: (first.range.0, last.range.1), replacement, caret })
+    }
+}
+
+/// `replacement` with the capitalization of `matched`: upper-cased when
+/// every letter of the match is upper case (`WIDGET`), capitalized when
+/// only its first letter is (`Widget`, `W`), and unchanged otherwise —
+/// so `widget` → `macOS` keeps the casing as typed.
+pub fn match_case(matched: &str, replacement: &str) -> String {
+    let letters: Vec<char> = matched.chars().filter(|c| c.is_uppercase() || c.is_lowercase()).collect();
+    let Some((first, rest)) = letters.split_first() else { return replacement.to_string() };
+    if !first.is_uppercase() {
+        return replacement.to_string();
+    }
+    if !rest.is_empty() && rest.iter().all(|c| c.is_uppercase()) {
+        return replacement.to_uppercase();
+    }
+    if rest.iter().all(|c| c.is_lowercase()) {
+        let mut chars = replacement.chars();
+        return match chars.next() {
+            Some(c) => c.to_uppercase().chain(chars).collect(),
+            None => String::new(),
+        };
+    }
+    replacement.to_string()
+}
+
+/// Put `entry` at the front of a recent-strings list, dropping an earlier
+/// copy and anything past `limit`. Empty entries are ignored.
+pub fn remember(history: &mut Vec<String>, entry: &str, limit: usize) {
+    if entry.is_empty() {
+        return;
+    }
+    history.retain(|e| e != entry);
+    history.insert(0, entry.to_string());
+    history.truncate(limit);
+}
+
+/// The match after match `current` of tab `tab` (before it when `forward`
+/// is false), continuing into the next tab with matches, wrapping around,
+/// once this tab runs out. `counts` holds each tab's match count. Returns
+/// `(tab, match index)`, or `None` when no tab has a match.
+pub fn step_across_tabs(counts: &[usize], (tab, current): (usize, usize), forward: bool) -> Option<(usize, usize)> {
+    let here = counts.get(tab).copied().unwrap_or(0);
+    if forward && current + 1 < here {
+        return Some((tab, current + 1));
+    }
+    if !forward && here > 0 && current > 0 {
+        return Some((tab, (current - 1).min(here - 1)));
+    }
+    let n = counts.len();
+    (1..=n)
+        .map(|step| if forward { (tab + step) % n } else { (tab + n - step) % n })
+        .find(|&t| counts[t] > 0)
+        .map(|t| (t, if forward { 0 } else { counts[t] - 1 }))
+}
+
+/// The source text with every `Hidden` run removed, as Viewer mode shows it.
+#[derive(Debug, Clone, Default)]
+pub struct VisibleText {
+    text: String,
+    /// `(visible start, source start, len)` for each visible stretch, in order.
+    segments: Vec<(usize, usize, usize)>,
+}
+
+impl VisibleText {
+    /// Build the projection of `source` from its viewer attribute runs.
+    pub fn new(source: &str, runs: &[AttributeRun]) -> Self {
+        let mut hidden: Vec<(usize, usize)> = runs
+            .iter()
+            .filter(|r| r.attrs.contains(&TextAttribute::Hidden))
+            .map(|r| (r.range.0.min(source.len()), r.range.1.min(source.len())))
+            .collect();
+        hidden.sort_unstable();
+
+        let mut projection = Self::default();
+        let mut pos = 0;
+        for (start, end) in hidden.into_iter().chain([(source.len(), source.len())]) {
+            if start > pos {
+                projection.segments.push((projection.text.len(), pos, start - pos));
+                projection.text.push_str(&source[pos..start]);
+            }
+            pos = pos.max(end);
+        }
+        projection
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.text
+    }
+
+    /// Map a non-empty range of the projection back to the source range
+    /// running from its first to its last visible byte.
+    pub fn to_source(&self, (start, end): (usize, usize)) -> (usize, usize) {
+        let source_of = |pos: usize| {
+            let i = self.segments.partition_point(|&(vis, _, _)| vis <= pos) - 1;
+            let (vis, src, _) = self.segments[i];
+            src + (pos - vis)
+        };
+        (source_of(start), source_of(end - 1) + 1)
+    }
+}
+
+/// Every span whose range covers `range`, outermost first.
+fn ancestors(spans: &[MarkdownSpan], range: (usize, usize)) -> Vec<&MarkdownSpan> {
+    let mut out = Vec::new();
+    let mut level = spans;
+    while let Some(span) = level
+        .iter()
+        .find(|s| s.source_range.0 <= range.0 && range.1 <= s.source_range.1)
+    {
+        out.push(span);
+        level = &span.children;
+    }
+    out
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum LinkPart {
+    Text,
+    Url,
+    /// `<https://…>` and bare URLs, where the text is the URL.
+    Autolink,
+}
+
+/// Which part of a link or image `span` the range lies in, if it is one.
+/// The label is the extent of the span's children; anything else inside
+/// the span (the `](url "title")` tail) counts as the destination.
+fn link_part(text: &str, span: &MarkdownSpan, range: (usize, usize)) -> Option<LinkPart> {
+    if !matches!(span.kind, NodeKind::Link { .. } | NodeKind::Image { .. }) {
+        return None;
+    }
+    let start = span.source_range.0;
+    if !text[start..].starts_with(['[', '!']) {
+        return Some(LinkPart::Autolink);
+    }
+    // `[](url)` has no children: its empty label sits after the `[`.
+    let label = match (span.children.first(), span.children.last()) {
+        (Some(first), Some(last)) => (first.source_range.0, last.source_range.1),
+        _ => (start + 1, start + 1),
+    };
+    if label.0 <= range.0 && range.1 <= label.1 {
+        Some(LinkPart::Text)
+    } else if range.0 >= label.1 {
+        Some(LinkPart::Url)
+    } else {
+        None
+    }
+}
+
+/// Whether `start..end` is bounded by non-word characters (or the text edges).
+fn is_whole_word(text: &str, start: usize, end: usize) -> bool {
+    let is_word = |c: char| c.is_alphanumeric() || c == '_';
+    !text[..start].chars().next_back().is_some_and(is_word)
+        && !text[end..].chars().next().is_some_and(is_word)
+}
+
+fn utf16_len(s: &str) -> usize {
+    s.chars().map(char::len_utf16).sum()
+}
+
+/// The regex crate's syntax errors span several lines with a caret diagram;
+/// keep just the description for the find bar.
+fn error_message(err: &regex::Error) -> String {
+    match err {
+        regex::Error::Syntax(msg) => msg
+            .lines()
+            .rev()
+            .find_map(|line| line.strip_prefix("error: "))
+            .unwrap_or(msg)
+            .to_string(),
+        regex::Error::CompiledTooBig(_) => "pattern is too large".to_string(),
+        _ => err.to_string(),
+    }
+}
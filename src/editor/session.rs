@@ -0,0 +1,156 @@
+//! Session persistence: the open documents, their modes, caret and scroll
+//! positions, the active tab and the font size.
+//!
+//! The session is a small line-based text file:
+//!
+//! ```text
+//! mdit-session 1
+//! font-size 16
+//! active 1
+//! tab editor 120 340.5 /Users/me/notes/todo.md
+//! tab viewer 0 0 /Users/me/notes/meeting notes.md
+//! ```
+//!
+//! Each `tab` line holds the mode, the caret (a UTF-16 offset, as the text
+//! view reports it), the vertical scroll offset in points, and the path,
+//! which runs to the end of the line so it may contain spaces. Unknown and
+//! malformed lines are skipped, so an older or damaged file still restores
+//! what it can. Untitled documents have no path and are not part of the
+//! session.
+
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use crate::editor::view_mode::ViewMode;
+
+const HEADER: &str = "mdit-session 1";
+
+/// One open document.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TabSession {
+    pub path: PathBuf,
+    pub mode: ViewMode,
+    /// Caret position as a UTF-16 offset.
+    pub caret: usize,
+    /// Vertical scroll offset in points.
+    pub scroll: f64,
+}
+
+/// Everything needed to reopen the working set.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Session {
+    pub tabs: Vec<TabSession>,
+    /// Index into `tabs`.
+    pub active: usize,
+    pub font_size: f64,
+}
+
+/// `~/Library/Application Support/mdit/session.txt`.
+pub fn session_path() -> Option<PathBuf> {
+    let home = std::env::var_os("HOME")?;
+    Some(PathBuf::from(home).join("Library/Application Support/mdit/session.txt"))
+}
+
+impl Session {
+    /// The session file's contents. Paths containing a newline can't be
+    /// represented and are left out.
+    pub fn serialize(&self) -> String {
+        let mut out = format!("{HEADER}\nfont-size {}\n", self.font_size);
+        let mut active = 0;
+        let mut written = 0;
+        for (i, tab) in self.tabs.iter().enumerate() {
+            let path = tab.path.to_string_lossy();
+            if path.contains('\n') {
+                continue;
+            }
+            if i == self.active {
+                active = written;
+            }
+            let mode = match tab.mode {
+                ViewMode::Viewer => "viewer",
+                ViewMode::Editor => "editor",
+            };
+            out.push_str(&format!("tab {mode} {} {} {path}\n", tab.caret, tab.scroll));
+            written += 1;
+        }
+        out.push_str(&format!("active {active}\n"));
+        out
+    }
+
+    /// Parse a session file. `None` unless it starts with the session header.
+    pub fn parse(src: &str) -> Option<Self> {
+        let mut lines = src.lines();
+        if lines.next()?.trim_end() != HEADER {
+            return None;
+        }
+        let mut session = Session { tabs: Vec::new(), active: 0, font_size: 0.0 };
+        for line in lines {
+            let Some((key, rest)) = line.split_once(' ') else { continue };
+            match key {
+                "font-size" => {
+                    if let Ok(size) = rest.trim().parse::<f64>() {
+                        session.font_size = size;
+                    }
+                }
+                "active" => {
+                    if let Ok(active) = rest.trim().parse() {
+                        session.active = active;
+                    }
+                }
+                "tab" => session.tabs.extend(parse_tab(rest)),
+                _ => {}
+            }
+        }
+        session.active = session.active.min(session.tabs.len().saturating_sub(1));
+        Some(session)
+    }
+
+    /// Drop tabs whose file fails `exists`. The active tab stays active if
+    /// it survives; otherwise the nearest surviving tab before it takes over.
+    pub fn retain_existing(&mut self, exists: impl Fn(&Path) -> bool) {
+        let mut active = 0;
+        let mut kept = Vec::with_capacity(self.tabs.len());
+        for (i, tab) in self.tabs.drain(..).enumerate() {
+            if !exists(&tab.path) {
+                continue;
+            }
+            if i <= self.active {
+                active = kept.len();
+            }
+            kept.push(tab);
+        }
+        self.tabs = kept;
+        self.active = active;
+    }
+}
+
+/// Write `contents` to `path` through a temporary file in the same
+/// directory and a rename, so readers never see a half-written file.
+/// Creates the parent directory if needed.
+pub fn write_atomic(path: &Path, contents: &str) -> std::io::Result<()> {
+    let dir = path.parent().unwrap_or(Path::new("."));
+    std::fs::create_dir_all(dir)?;
+    let name = path.file_name().map_or_else(|| "mdit".into(), |n| n.to_string_lossy());
+    let tmp = dir.join(format!(".{name}.{}.tmp", std::process::id()));
+    let result = std::fs::File::create(&tmp)
+        .and_then(|mut f| f.write_all(contents.as_bytes()).and_then(|()| f.sync_all()))
+        .and_then(|()| std::fs::rename(&tmp, path));
+    if result.is_err() {
+        let _ = std::fs::remove_file(&tmp);
+    }
+    result
+}
+
+/// `<mode> <caret> <scroll> <path>`.
+fn parse_tab(rest: &str) -> Option<TabSession> {
+    let mut fields = rest.splitn(4, ' ');
+    let mode = match fields.next()? {
+        "viewer" => ViewMode::Viewer,
+        "editor" => ViewMode::Editor,
+        _ => return None,
+    };
+    let caret = fields.next()?.parse().ok()?;
+    let scroll = fields.next()?.parse::<f64>().ok().filter(|s| s.is_finite())?;
+    let path = fields.next().filter(|p| !p.is_empty())?;
+    Some(TabSession { path: PathBuf::from(path), mode, caret, scroll })
+}
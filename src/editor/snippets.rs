@@ -0,0 +1,384 @@
+//! Snippets: `/trigger` expansions with tab-stop placeholders.
+//!
+//! Bodies use the TextMate syntax: `$1` or `${1}` is a tab stop,
+//! `${1:text}` a stop with placeholder text, `$0` the final caret, and
+//! `$NAME` / `${NAME}` a variable (`DATE`, `TIME`, `CURRENT_YEAR`, …)
+//! resolved by the caller. `\$`, `\}` and `\\` escape. A stop index used
+//! twice repeats its placeholder text but only the first copy is visited.
+//!
+//! Offsets are UTF-8 bytes. [`SnippetSession`] only does arithmetic on
+//! them, so it stays valid as long as edits are reported in the same unit.
+
+use std::cell::RefCell;
+use std::path::PathBuf;
+use std::rc::Rc;
+use std::time::SystemTime;
+
+/// A named template inserted by typing `/trigger`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Snippet {
+    pub trigger: String,
+    pub description: String,
+    pub body: String,
+}
+
+impl Snippet {
+    fn new(trigger: &str, description: &str, body: &str) -> Self {
+        Self { trigger: trigger.into(), description: description.into(), body: body.into() }
+    }
+}
+
+/// The snippets shipped with mdit.
+pub fn builtin_snippets() -> Vec<Snippet> {
+    vec![
+        Snippet::new("table", "Table", "| ${1:Column} | ${2:Column} |\n| --- | --- |\n| $3 | $4 |\n$0"),
+        Snippet::new("code", "Code block", "```${1:language}\n$0\n```"),
+        Snippet::new("date", "Today's date", "$DATE"),
+        Snippet::new("time", "Current time", "$TIME"),
+        Snippet::new("todo", "Task", "- [ ] $0"),
+        Snippet::new("link", "Link", "[${1:text}](${2:url})$0"),
+        Snippet::new("image", "Image", "![${1:alt text}](${2:url})$0"),
+        Snippet::new("quote", "Block quote", "> $0"),
+        Snippet::new("math", "Math block", "$$\n$0\n$$"),
+        Snippet::new(
+            "details",
+            "Collapsible section",
+            "<details>\n<summary>${1:Summary}</summary>\n\n$0\n\n</details>",
+        ),
+        Snippet::new("hr", "Horizontal rule", "---\n$0"),
+    ]
+}
+
+// ---------------------------------------------------------------------------
+// User snippets
+// ---------------------------------------------------------------------------
+
+/// `~/Library/Application Support/mdit/snippets.txt`.
+pub fn user_snippets_path() -> Option<PathBuf> {
+    let home = std::env::var_os("HOME")?;
+    Some(PathBuf::from(home).join("Library/Application Support/mdit/snippets.txt"))
+}
+
+/// Parse a user snippet file. Each snippet is written as
+///
+/// ```text
+/// snippet meeting Meeting notes
+/// ## ${1:Topic}
+///
+/// - $0
+/// endsnippet
+/// ```
+///
+/// The first word after `snippet` is the trigger and the rest of the line
+/// its description. Body lines are taken verbatim. Lines outside a snippet
+/// (blank lines, `#` comments) are ignored, as is a `snippet` line without
+/// a trigger; an unterminated snippet runs to the end of the file.
+pub fn parse_user_snippets(src: &str) -> Vec<Snippet> {
+    let mut snippets = Vec::new();
+    let mut lines = src.lines();
+    while let Some(line) = lines.next() {
+        let Some(header) = line.strip_prefix("snippet ") else {
+            continue;
+        };
+        let (trigger, description) = header.trim().split_once(char::is_whitespace).unwrap_or((header.trim(), ""));
+        let trigger = trigger.trim_start_matches('/');
+        let body: Vec<&str> = lines.by_ref().take_while(|l| l.trim_end() != "endsnippet").collect();
+        if !trigger.is_empty() {
+            snippets.push(Snippet::new(trigger, description.trim(), &body.join("\n")));
+        }
+    }
+    snippets
+}
+
+/// Built-in snippets plus the user's, which win on a shared trigger.
+#[derive(Debug, Clone, Default)]
+pub struct SnippetLibrary {
+    snippets: Vec<Snippet>,
+}
+
+thread_local! {
+    /// The library from the last [`SnippetLibrary::load_cached`] and the
+    /// user snippet file's modification time when it was read.
+    static CACHED: RefCell<Option<(Option<SystemTime>, Rc<SnippetLibrary>)>> = const { RefCell::new(None) };
+}
+
+impl SnippetLibrary {
+    pub fn new(user: Vec<Snippet>) -> Self {
+        let mut snippets = builtin_snippets();
+        for snippet in user {
+            match snippets.iter_mut().find(|s| s.trigger == snippet.trigger) {
+                Some(existing) => *existing = snippet,
+                None => snippets.push(snippet),
+            }
+        }
+        Self { snippets }
+    }
+
+    /// The built-ins plus the user snippet file, read fresh so edits to it
+    /// apply without restarting. A missing or unreadable file is ignored.
+    pub fn load() -> Self {
+        let user = user_snippets_path()
+            .and_then(|path| std::fs::read_to_string(path).ok())
+            .map(|src| parse_user_snippets(&src))
+            .unwrap_or_default();
+        Self::new(user)
+    }
+
+    /// Like [`load`](Self::load), but the file is only read again once its
+    /// modification time changes (or it appears or goes away), so this is
+    /// cheap enough to call on every keystroke.
+    pub fn load_cached() -> Rc<Self> {
+        let modified = user_snippets_path()
+            .and_then(|path| std::fs::metadata(path).ok())
+            .and_then(|meta| meta.modified().ok());
+        CACHED.with(|cached| {
+            let mut cached = cached.borrow_mut();
+            match &*cached {
+                Some((stamp, library)) if *stamp == modified => Rc::clone(library),
+                _ => {
+                    let library = Rc::new(Self::load());
+                    *cached = Some((modified, Rc::clone(&library)));
+                    library
+                }
+            }
+        })
+    }
+
+    pub fn get(&self, trigger: &str) -> Option<&Snippet> {
+        self.snippets.iter().find(|s| s.trigger == trigger)
+    }
+
+    /// Snippets whose trigger starts with `query` (ignoring case), an exact
+    /// match first.
+    pub fn matching(&self, query: &str) -> Vec<&Snippet> {
+        let query = query.to_lowercase();
+        let mut found: Vec<&Snippet> =
+            self.snippets.iter().filter(|s| s.trigger.to_lowercase().starts_with(&query)).collect();
+        found.sort_by_key(|s| s.trigger.to_lowercase() != query);
+        found
+    }
+}
+
+/// The `/query` being typed before `caret`: a slash at the start of a line
+/// or after whitespace, followed by word characters up to the caret.
+/// Returns the slash's offset and the query after it.
+pub fn slash_query(text: &str, caret: usize) -> Option<(usize, &str)> {
+    let before = text.get(..caret)?;
+    let word_start = before
+        .rfind(|c: char| !(c.is_alphanumeric() || c == '-' || c == '_'))
+        .map_or(0, |i| i + before[i..].chars().next().map_or(1, char::len_utf8));
+    let slash = word_start.checked_sub(1)?;
+    if text.as_bytes()[slash] != b'/' {
+        return None;
+    }
+    if !before[..slash].chars().next_back().is_none_or(char::is_whitespace) {
+        return None;
+    }
+    Some((slash, &before[word_start..]))
+}
+
+// ---------------------------------------------------------------------------
+// Expansion
+// ---------------------------------------------------------------------------
+
+/// A snippet body with placeholders filled in.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Expansion {
+    pub text: String,
+    /// Tab stops in visiting order (`$1`, `$2`, …). The last one is the
+    /// final caret: `$0`, or the end of the text when there is none.
+    pub stops: Vec<(usize, usize)>,
+}
+
+/// Expand `body`, indenting every line after the first with `indent` so
+/// multi-line snippets line up under a list item. Variables come from
+/// `variable`; unknown ones are kept as written.
+pub fn expand(body: &str, indent: &str, variable: &dyn Fn(&str) -> Option<String>) -> Expansion {
+    let mut text = String::new();
+    let mut stops: Vec<(u32, (usize, usize))> = Vec::new();
+    let mut defaults: Vec<(u32, String)> = Vec::new();
+    let mut chars = body.char_indices().peekable();
+
+    let push = |text: &mut String, s: &str| {
+        for (i, line) in s.split('\n').enumerate() {
+            if i > 0 {
+                text.push('\n');
+                text.push_str(indent);
+            }
+            text.push_str(line);
+        }
+    };
+
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '\\' if matches!(chars.peek(), Some((_, '$' | '}' | '\\'))) => {
+                let (_, escaped) = chars.next().unwrap();
+                text.push(escaped);
+            }
+            '$' => {
+                let rest = &body[i + 1..];
+                let Some((token, len)) = parse_token(rest) else {
+                    text.push('$');
+                    continue;
+                };
+                while chars.next_if(|&(j, _)| j <= i + len).is_some() {}
+                match token {
+                    Token::Stop(index, placeholder) => {
+                        let placeholder = match placeholder {
+                            Some(p) => {
+                                defaults.push((index, p.clone()));
+                                p
+                            }
+                            None => defaults.iter().find(|(n, _)| *n == index).map(|(_, p)| p.clone()).unwrap_or_default(),
+                        };
+                        let start = text.len();
+                        push(&mut text, &placeholder);
+                        if !stops.iter().any(|(n, _)| *n == index) {
+                            stops.push((index, (start, text.len())));
+                        }
+                    }
+                    Token::Variable(name) => match variable(name) {
+                        Some(value) => push(&mut text, &value),
+                        None => text.push_str(&body[i..i + 1 + len]),
+                    },
+                }
+            }
+            '\n' => push(&mut text, "\n"),
+            _ => text.push(c),
+        }
+    }
+
+    // Visit $1, $2, … in order, then $0 (or the end) last.
+    stops.sort_by_key(|&(n, _)| if n == 0 { u32::MAX } else { n });
+    if stops.last().is_none_or(|&(n, _)| n != 0) {
+        stops.push((0, (text.len(), text.len())));
+    }
+    Expansion { text, stops: stops.into_iter().map(|(_, range)| range).collect() }
+}
+
+enum Token<'a> {
+    Stop(u32, Option<String>),
+    Variable(&'a str),
+}
+
+/// Parse what follows a `$`, returning the token and its byte length.
+fn parse_token(rest: &str) -> Option<(Token<'_>, usize)> {
+    let is_name = |c: char| c.is_ascii_uppercase() || c.is_ascii_digit() || c == '_';
+    if let Some(inner) = rest.strip_prefix('{') {
+        let digits = inner.bytes().take_while(u8::is_ascii_digit).count();
+        if digits > 0 {
+            let index = inner[..digits].parse().ok()?;
+            let after = &inner[digits..];
+            if after.starts_with('}') {
+                return Some((Token::Stop(index, None), 1 + digits + 1));
+            }
+            let body = after.strip_prefix(':')?;
+            let (placeholder, len) = placeholder_text(body)?;
+            return Some((Token::Stop(index, Some(placeholder)), 1 + digits + 1 + len));
+        }
+        let name_len = inner.find(|c: char| !is_name(c))?;
+        if name_len == 0 || !inner[name_len..].starts_with('}') {
+            return None;
+        }
+        return Some((Token::Variable(&inner[..name_len]), 1 + name_len + 1));
+    }
+    let digits = rest.bytes().take_while(u8::is_ascii_digit).count();
+    if digits > 0 {
+        return Some((Token::Stop(rest[..digits].parse().ok()?, None), digits));
+    }
+    let name_len = rest.find(|c: char| !is_name(c)).unwrap_or(rest.len());
+    if name_len == 0 || !rest.starts_with(|c: char| c.is_ascii_uppercase()) {
+        return None;
+    }
+    Some((Token::Variable(&rest[..name_len]), name_len))
+}
+
+/// Placeholder text up to the closing `}`, unescaped, and the byte length
+/// consumed including the brace.
+fn placeholder_text(body: &str) -> Option<(String, usize)> {
+    let mut out = String::new();
+    let mut chars = body.char_indices();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '}' => return Some((out, i + 1)),
+            '\\' => match chars.next() {
+                Some((_, e @ ('$' | '}' | '\\'))) => out.push(e),
+                Some((_, e)) => {
+                    out.push('\\');
+                    out.push(e);
+                }
+                None => out.push('\\'),
+            },
+            _ => out.push(c),
+        }
+    }
+    None
+}
+
+// ---------------------------------------------------------------------------
+// Tab-stop session
+// ---------------------------------------------------------------------------
+
+/// The tab stops of an inserted snippet while the user fills them in.
+///
+/// Typing inside the current stop grows it and shifts the stops after it;
+/// any edit outside the current stop ends the session.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SnippetSession {
+    stops: Vec<(usize, usize)>,
+    current: usize,
+}
+
+impl SnippetSession {
+    /// Start a session for `expansion` inserted at `at`. Returns `None`
+    /// when the snippet has no stops besides the final caret.
+    pub fn start(at: usize, expansion: &Expansion) -> Option<Self> {
+        if expansion.stops.len() < 2 {
+            return None;
+        }
+        let stops = expansion.stops.iter().map(|&(s, e)| (at + s, at + e)).collect();
+        Some(Self { stops, current: 0 })
+    }
+
+    /// The range of the stop being filled in.
+    pub fn current(&self) -> (usize, usize) {
+        self.stops[self.current]
+    }
+
+    /// Whether the current stop is the final caret, after which the
+    /// session is over.
+    pub fn is_finished(&self) -> bool {
+        self.current + 1 == self.stops.len()
+    }
+
+    /// Move to the next (or previous) stop and return its range. Going back
+    /// from the first stop stays on it.
+    pub fn advance(&mut self, backwards: bool) -> (usize, usize) {
+        self.current = if backwards {
+            self.current.saturating_sub(1)
+        } else {
+            (self.current + 1).min(self.stops.len() - 1)
+        };
+        self.current()
+    }
+
+    /// Account for `range` being replaced by `new_len` bytes. Returns
+    /// `false` when the edit breaks the snippet and the session should end.
+    pub fn edit(&mut self, range: (usize, usize), new_len: usize) -> bool {
+        let (start, end) = range;
+        let delta = new_len as isize - (end - start) as isize;
+        let shift = |pos: usize| (pos as isize + delta) as usize;
+        let (cur_start, cur_end) = self.current();
+        if start < cur_start || end > cur_end {
+            return false;
+        }
+        for (i, stop) in self.stops.iter_mut().enumerate() {
+            if i == self.current {
+                stop.1 = shift(stop.1);
+            } else if stop.0 >= end && !(stop.0 == start && i < self.current) {
+                *stop = (shift(stop.0), shift(stop.1));
+            }
+        }
+        true
+    }
+}
@@ -0,0 +1,342 @@
+//! Task list commands: toggling checkboxes from the keyboard, counting
+//! progress, and tidying completed tasks.
+//!
+//! Everything here works from the `NodeKind::TaskItem` spans the parser
+//! produces, so task markers inside code blocks are never touched. Offsets
+//! are UTF-8 bytes.
+
+use crate::editor::block_move::{section_range, selected_lines, span_lines};
+use crate::editor::formatting::{line_bounds, parse_list_marker, ListKind, TextEdit};
+use crate::markdown::parser::{MarkdownSpan, NodeKind};
+
+/// Completed and total task counts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct TaskProgress {
+    pub done: usize,
+    pub total: usize,
+}
+
+impl TaskProgress {
+    fn count(spans: &[MarkdownSpan]) -> Self {
+        let mut progress = Self::default();
+        for span in spans {
+            if let NodeKind::TaskItem { checked } = span.kind {
+                progress.total += 1;
+                progress.done += usize::from(checked);
+            }
+            let nested = Self::count(&span.children);
+            progress.total += nested.total;
+            progress.done += nested.done;
+        }
+        progress
+    }
+
+    /// e.g. "7/12 done".
+    pub fn label(&self) -> String {
+        format!("{}/{} done", self.done, self.total)
+    }
+}
+
+/// Progress over every task in the document.
+pub fn document_progress(spans: &[MarkdownSpan]) -> TaskProgress {
+    TaskProgress::count(spans)
+}
+
+/// Task counts for the document and each top-level list, computed once per
+/// edit so the status bar can follow the caret without walking the spans.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TaskSummary {
+    document: TaskProgress,
+    /// Source range and progress of each top-level list that has tasks.
+    lists: Vec<((usize, usize), TaskProgress)>,
+}
+
+impl TaskSummary {
+    pub fn new(spans: &[MarkdownSpan]) -> Self {
+        let lists = top_level_lists(spans)
+            .map(|list| (list.source_range, TaskProgress::count(std::slice::from_ref(list))))
+            .filter(|(_, progress)| progress.total > 0)
+            .collect();
+        Self { document: document_progress(spans), lists }
+    }
+
+    /// Rewrite the list ranges with `f`, e.g. from UTF-8 bytes to the UTF-16
+    /// offsets a text view reports, so [`label`](Self::label) can take those.
+    pub fn map_offsets(mut self, f: impl Fn(usize) -> usize) -> Self {
+        for (range, _) in &mut self.lists {
+            *range = (f(range.0), f(range.1));
+        }
+        self
+    }
+
+    /// Status bar text: the list at `pos` and the whole document, e.g.
+    /// "2/5 in list · 7/12 done", or just "7/12 done" when `pos` is outside
+    /// a task list or that list holds every task. `None` without tasks.
+    pub fn label(&self, pos: usize) -> Option<String> {
+        if self.document.total == 0 {
+            return None;
+        }
+        let list = self.lists.iter().find(|((start, end), _)| pos >= *start && pos <= *end);
+        match list {
+            Some((_, list)) if list.total < self.document.total => {
+                Some(format!("{}/{} in list · {}", list.done, list.total, self.document.label()))
+            }
+            _ => Some(self.document.label()),
+        }
+    }
+}
+
+/// Lists at the top level of the document.
+fn top_level_lists(spans: &[MarkdownSpan]) -> impl Iterator<Item = &MarkdownSpan> {
+    spans.iter().filter(|span| span.kind == NodeKind::List)
+}
+
+/// Every task item, outermost first, in document order.
+fn task_items<'a>(spans: &'a [MarkdownSpan], out: &mut Vec<&'a MarkdownSpan>) {
+    for span in spans {
+        if matches!(span.kind, NodeKind::TaskItem { .. }) {
+            out.push(span);
+        }
+        task_items(&span.children, out);
+    }
+}
+
+fn is_checked(item: &MarkdownSpan) -> bool {
+    matches!(item.kind, NodeKind::TaskItem { checked: true })
+}
+
+/// Byte offset of the character inside a task item's `[ ]` box.
+fn checkbox_offset(text: &str, item: &MarkdownSpan) -> Option<usize> {
+    let (start, end) = line_bounds(text, item.source_range.0);
+    let open = start + text[start..end].find('[')?;
+    Some(open + 1)
+}
+
+/// Toggle the tasks on the selected lines (or the caret line). If any of
+/// them is open they are all checked, otherwise all are unchecked. Only the
+/// box characters change, so the selection stays valid. Returns `None` when
+/// no task starts on those lines.
+pub fn toggle_tasks_edit(text: &str, spans: &[MarkdownSpan], selection: (usize, usize)) -> Option<TextEdit> {
+    let (first_line, last_line) = selected_lines(text, selection);
+    let mut items = Vec::new();
+    task_items(spans, &mut items);
+    let boxes: Vec<(usize, bool)> = items
+        .into_iter()
+        .filter(|item| (first_line..=last_line).contains(&line_bounds(text, item.source_range.0).0))
+        .filter_map(|item| Some((checkbox_offset(text, item)?, is_checked(item))))
+        .collect();
+    let check = boxes.iter().any(|&(_, checked)| !checked);
+    let (start, end) = (boxes.first()?.0, boxes.last()?.0 + 1);
+
+    let mut replacement = text[start..end].to_string();
+    for &(offset, _) in &boxes {
+        let at = offset - start;
+        replacement.replace_range(at..at + 1, if check { "x" } else { " " });
+    }
+    Some(TextEdit { range: (start, end), replacement, caret: selection.1 })
+}
+
+// ---------------------------------------------------------------------------
+// Tidying completed tasks
+// ---------------------------------------------------------------------------
+
+/// What `tidy_list` does with completed tasks.
+enum Tidy {
+    /// Move them below the open tasks of the same list.
+    Sort,
+    /// Remove them, collecting their text (dedented to column 0).
+    Archive(Vec<String>),
+}
+
+/// Rewrite the whole-line range of `list`, applying `tidy` to it and its
+/// nested lists. Returns an empty string when every item was archived.
+fn tidy_list(text: &str, list: &MarkdownSpan, tidy: &mut Tidy) -> String {
+    let ranges: Vec<(usize, usize)> = list.children.iter().map(|item| span_lines(text, item)).collect();
+    let gaps: Vec<&str> = ranges.windows(2).map(|w| &text[w[0].1..w[1].0]).collect();
+
+    let mut chunks = Vec::new();
+    for (item, &(start, end)) in list.children.iter().zip(&ranges) {
+        let checked = is_checked(item);
+        if checked {
+            if let Tidy::Archive(archived) = tidy {
+                archived.push(dedent(&text[start..end]));
+                continue;
+            }
+        }
+        let mut chunk = String::new();
+        let mut pos = start;
+        for sub in item.children.iter().filter(|c| c.kind == NodeKind::List) {
+            let (sub_start, sub_end) = span_lines(text, sub);
+            chunk.push_str(&text[pos..sub_start]);
+            let tidied = tidy_list(text, sub, tidy);
+            if tidied.is_empty() {
+                chunk.truncate(chunk.trim_end().len());
+            }
+            chunk.push_str(&tidied);
+            pos = sub_end;
+        }
+        chunk.push_str(&text[pos..end]);
+        chunks.push((checked, start, chunk));
+    }
+
+    match tidy {
+        Tidy::Sort => {
+            chunks.sort_by_key(|&(checked, _, _)| checked);
+            let ordered = parse_list_marker(&text[ranges[0].0..ranges[0].1])
+                .is_some_and(|m| matches!(m.kind, ListKind::Ordered { .. }));
+            let mut out = String::new();
+            for (i, (_, origin, chunk)) in chunks.iter().enumerate() {
+                if ordered {
+                    // Numbers stay in place; only the item text moves.
+                    let marker = |at: usize| {
+                        let line = &text[at..line_bounds(text, at).1];
+                        parse_list_marker(line).map_or(0, |m| m.content_start)
+                    };
+                    out.push_str(&text[ranges[i].0..ranges[i].0 + marker(ranges[i].0)]);
+                    out.push_str(&chunk[marker(*origin)..]);
+                } else {
+                    out.push_str(chunk);
+                }
+                if let Some(gap) = gaps.get(i) {
+                    out.push_str(gap);
+                }
+            }
+            out
+        }
+        Tidy::Archive(_) => {
+            let gap = gaps.first().copied().unwrap_or("\n");
+            chunks.iter().map(|(_, _, chunk)| chunk.as_str()).collect::<Vec<_>>().join(gap)
+        }
+    }
+}
+
+/// Strip the first line's indentation from every line of an item.
+fn dedent(item: &str) -> String {
+    let indent = item.len() - item.trim_start_matches(' ').len();
+    item.lines()
+        .map(|line| {
+            let spaces = line.len() - line.trim_start_matches(' ').len();
+            &line[spaces.min(indent)..]
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// The smallest edit turning `text` into `new`, with the caret kept on the
+/// same text where possible.
+fn minimal_edit(text: &str, new: &str, caret: usize) -> Option<TextEdit> {
+    if text == new {
+        return None;
+    }
+    let mut prefix = text.bytes().zip(new.bytes()).take_while(|(a, b)| a == b).count();
+    while !text.is_char_boundary(prefix) || !new.is_char_boundary(prefix) {
+        prefix -= 1;
+    }
+    let max_suffix = text.len().min(new.len()) - prefix;
+    let mut suffix = text.bytes().rev().zip(new.bytes().rev()).take(max_suffix).take_while(|(a, b)| a == b).count();
+    while !text.is_char_boundary(text.len() - suffix) || !new.is_char_boundary(new.len() - suffix) {
+        suffix -= 1;
+    }
+    let (start, end) = (prefix, text.len() - suffix);
+    let replacement = new[prefix..new.len() - suffix].to_string();
+    let caret = if caret < start {
+        caret
+    } else if caret >= end {
+        caret - end + start + replacement.len()
+    } else {
+        start
+    };
+    Some(TextEdit { range: (start, end), replacement, caret })
+}
+
+/// Move completed tasks below the open ones in the list under the caret,
+/// or in every list when the caret is not in one. Nested lists are sorted
+/// too; completed tasks keep their relative order. Ordered lists keep
+/// their numbering.
+pub fn sort_completed_tasks_edit(text: &str, spans: &[MarkdownSpan], caret: usize) -> Option<TextEdit> {
+    let lists: Vec<&MarkdownSpan> = top_level_lists(spans).collect();
+    let at_caret: Vec<&MarkdownSpan> = lists
+        .iter()
+        .copied()
+        .filter(|l| caret >= l.source_range.0 && caret <= l.source_range.1)
+        .collect();
+    let targets = if at_caret.is_empty() { lists } else { at_caret };
+
+    let mut new = String::with_capacity(text.len());
+    let mut pos = 0;
+    for list in targets {
+        let (start, end) = span_lines(text, list);
+        new.push_str(&text[pos..start]);
+        new.push_str(&tidy_list(text, list, &mut Tidy::Sort));
+        pos = end;
+    }
+    new.push_str(&text[pos..]);
+    minimal_edit(text, &new, caret)
+}
+
+/// Heading text without ATX markers, for matching the "Done" heading.
+fn heading_title<'a>(text: &'a str, heading: &MarkdownSpan) -> &'a str {
+    let (start, end) = line_bounds(text, heading.source_range.0);
+    text[start..end].trim().trim_start_matches('#').trim_end_matches('#').trim()
+}
+
+/// Move every completed task (with its nested items) to a "Done" section.
+///
+/// The tasks are appended to the list at the end of an existing heading
+/// titled "Done" (any level, any case); otherwise a `## Done` section is
+/// added at the end of the document. Tasks already under that heading stay
+/// where they are, and lists left empty are removed with their blank lines.
+pub fn archive_completed_tasks_edit(text: &str, spans: &[MarkdownSpan], caret: usize) -> Option<TextEdit> {
+    let done = spans
+        .iter()
+        .filter(|s| matches!(s.kind, NodeKind::Heading { .. }))
+        .find(|s| heading_title(text, s).eq_ignore_ascii_case("done"))
+        .and_then(|h| section_range(text, spans, h.source_range.0))
+        .map(|(range, _)| range);
+    let in_done = |span: &MarkdownSpan| done.is_some_and(|(s, e)| span.source_range.0 >= s && span.source_range.0 < e);
+
+    let mut tidy = Tidy::Archive(Vec::new());
+    let mut changes = Vec::new();
+    for list in top_level_lists(spans).filter(|l| !in_done(l)) {
+        let (start, mut end) = span_lines(text, list);
+        let tidied = tidy_list(text, list, &mut tidy);
+        if tidied.is_empty() {
+            end += text[end..].len() - text[end..].trim_start_matches(['\n', ' ', '\t']).len();
+        }
+        changes.push(((start, end), tidied));
+    }
+    let Tidy::Archive(archived) = tidy else { unreachable!() };
+    if archived.is_empty() {
+        return None;
+    }
+    let archived = archived.join("\n");
+
+    if let Some((heading, end)) = done {
+        // Continue the section's trailing list, or start one below it.
+        let after_list = spans
+            .iter()
+            .any(|s| s.kind == NodeKind::List && s.source_range.0 >= heading && span_lines(text, s).1 == end);
+        let separator = if after_list { "\n" } else { "\n\n" };
+        changes.push(((end, end), format!("{separator}{archived}")));
+        changes.sort_by_key(|&((start, _), _)| start);
+    }
+
+    let mut new = String::with_capacity(text.len() + archived.len());
+    let mut pos = 0;
+    for ((start, end), tidied) in changes {
+        new.push_str(&text[pos..start]);
+        new.push_str(&tidied);
+        pos = end;
+    }
+    new.push_str(&text[pos..]);
+    if done.is_none() {
+        new.truncate(new.trim_end().len());
+        if !new.is_empty() {
+            new.push_str("\n\n");
+        }
+        new.push_str("## Done\n\n");
+        new.push_str(&archived);
+        new.push('\n');
+    }
+    minimal_edit(text, &new, caret)
+}
@@ -1,22 +1,38 @@
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
 
 use objc2::rc::Retained;
-use objc2::runtime::ProtocolObject;
+use objc2::runtime::{AnyClass, AnyObject, ProtocolObject};
 use objc2::{define_class, msg_send, DefinedClass, MainThreadOnly};
 use objc2_app_kit::{
-    NSAutoresizingMaskOptions, NSBezierPath, NSColor, NSFont, NSFontAttributeName,
-    NSFontWeightRegular, NSForegroundColorAttributeName, NSImage, NSPasteboard,
-    NSPasteboardTypeString, NSRectFill, NSScrollView, NSTextView,
+    NSAlert, NSAutoresizingMaskOptions, NSBezierPath, NSColor, NSEvent, NSEventModifierFlags,
+    NSFont, NSFontAttributeName, NSFontWeightRegular, NSForegroundColorAttributeName, NSImage,
+    NSPasteboard, NSPasteboardTypeHTML, NSPasteboardTypeString, NSRectFill, NSScrollView,
+    NSTextView,
 };
 use objc2_foundation::{
-    MainThreadMarker, NSObjectProtocol, NSPoint, NSRange, NSRect, NSSize, NSString,
+    MainThreadMarker, NSArray, NSObjectProtocol, NSPoint, NSRange, NSRect, NSSize, NSString,
 };
 
 use super::text_storage::MditEditorDelegate;
 use crate::editor::apply::TableGrid;
+use crate::editor::block_move::move_block_edit;
+use crate::editor::formatting::{
+    auto_pair_edit, delimited_to_table, insert_block_edit, is_auto_pair_char, line_bounds,
+    link_paste_edit, list_newline_edit, pair_backspace_edit, parse_list_marker,
+    shift_list_item_edit, table_tab_edit, ListKind, TextEdit,
+};
+use crate::editor::html_to_markdown::html_paste_markdown;
+use crate::editor::renderer::compute_attribute_runs;
+use crate::editor::snippets::{expand, slash_query, SnippetLibrary, SnippetSession};
 use crate::editor::view_mode::ViewMode;
+use crate::markdown::parser::{MarkdownSpan, NodeKind};
 use crate::ui::appearance::ColorScheme;
 
+/// `NSReturnTextMovement` / `NSTabTextMovement`: the completion popup was
+/// accepted with Return or Tab.
+const NS_RETURN_TEXT_MOVEMENT: isize = 0x10;
+const NS_TAB_TEXT_MOVEMENT: isize = 0x11;
+
 // Visual constants for code-block overlay drawing.
 const CODE_BLOCK_HEADER_H: f64 = 22.0; // height of the language title row
 const CODE_BLOCK_STRIPE_W: f64 = 3.0; // width of the left accent stripe
@@ -46,6 +62,11 @@ pub struct MditTextViewIvars {
     /// Task-list checkbox rects computed each draw cycle: (rect, byte_offset).
     /// Populated in draw_checkboxes(), read in mouseDown:.
     checkbox_rects: RefCell<Vec<(NSRect, usize)>>,
+    /// Tab stops of the snippet being filled in, if any.
+    snippet: RefCell<Option<SnippetSession>>,
+    /// Set while the snippet popup is being driven, so its own insertions
+    /// don't reopen it.
+    completing: Cell<bool>,
 }
 
 // ---------------------------------------------------------------------------
@@ -103,6 +124,102 @@ fn fill_vline(x: f64, y: f64, height: f64) {
     ));
 }
 
+// ---------------------------------------------------------------------------
+// Free functions — text edits
+// ---------------------------------------------------------------------------
+
+/// Resolve a snippet variable such as `DATE` from the local date and time.
+fn snippet_variable(name: &str) -> Option<String> {
+    let format = match name {
+        "DATE" => "yyyy-MM-dd",
+        "TIME" => "HH:mm",
+        "CURRENT_YEAR" => "yyyy",
+        "CURRENT_MONTH" => "MM",
+        "CURRENT_DATE" => "dd",
+        "CURRENT_HOUR" => "HH",
+        "CURRENT_MINUTE" => "mm",
+        "CURRENT_SECOND" => "ss",
+        _ => return None,
+    };
+    let formatter_cls = AnyClass::get(c"NSDateFormatter")?;
+    let date_cls = AnyClass::get(c"NSDate")?;
+    unsafe {
+        let formatter: Retained<AnyObject> = msg_send![formatter_cls, new];
+        let _: () = msg_send![&*formatter, setDateFormat: &*NSString::from_str(format)];
+        let now: Retained<AnyObject> = msg_send![date_cls, date];
+        let value: Retained<NSString> = msg_send![&*formatter, stringFromDate: &*now];
+        Some(value.to_string())
+    }
+}
+
+/// Convert a UTF-8 byte offset into a UTF-16 offset within `text`.
+fn byte_to_utf16(text: &str, byte_pos: usize) -> usize {
+    text[..byte_pos.min(text.len())].encode_utf16().count()
+}
+
+/// Convert a UTF-16 offset into a UTF-8 byte offset within `text`.
+///
+/// Offsets that fall inside a surrogate pair snap to the following character.
+fn utf16_to_byte(text: &str, utf16_pos: usize) -> usize {
+    let mut units = 0;
+    for (byte_idx, ch) in text.char_indices() {
+        if units >= utf16_pos {
+            return byte_idx;
+        }
+        units += ch.len_utf16();
+    }
+    text.len()
+}
+
+/// Return the text view's contents and its selection as UTF-8 byte offsets.
+pub fn text_and_selection(tv: &NSTextView) -> Option<(String, (usize, usize))> {
+    let sel: NSRange = unsafe { msg_send![tv, selectedRange] };
+    let storage = unsafe { tv.textStorage() }?;
+    let text = storage.string().to_string();
+    let start = utf16_to_byte(&text, sel.location);
+    let end = utf16_to_byte(&text, sel.location + sel.length);
+    Some((text, (start, end)))
+}
+
+/// Apply a [`TextEdit`] computed against `text` to the text view.
+///
+/// Goes through `insertText:replacementRange:` so the change is undoable,
+/// then moves the caret to the edit's target position.
+pub fn apply_text_edit(tv: &NSTextView, text: &str, edit: &TextEdit) {
+    replace_and_select(tv, text, edit.range, &edit.replacement, (edit.caret, edit.caret));
+}
+
+/// Replace the byte range `range` of `text` with `replacement` (undoably),
+/// then select `selection`, given as byte offsets into the edited text.
+pub fn replace_and_select(
+    tv: &NSTextView,
+    text: &str,
+    range: (usize, usize),
+    replacement: &str,
+    selection: (usize, usize),
+) {
+    let (start, end) = range;
+    let ns_range = NSRange {
+        location: byte_to_utf16(text, start),
+        length: byte_to_utf16(text, end) - byte_to_utf16(text, start),
+    };
+    let ns = NSString::from_str(replacement);
+    let _: () = unsafe { msg_send![tv, insertText: &*ns, replacementRange: ns_range] };
+
+    let new_text = format!("{}{}{}", &text[..start], replacement, &text[end..]);
+    let sel_start = byte_to_utf16(&new_text, selection.0);
+    let sel = NSRange { location: sel_start, length: byte_to_utf16(&new_text, selection.1) - sel_start };
+    let _: () = unsafe { msg_send![tv, setSelectedRange: sel] };
+}
+
+/// Select the byte range `range` of `text` and scroll it into view.
+pub fn select_and_reveal(tv: &NSTextView, text: &str, range: (usize, usize)) {
+    let start = byte_to_utf16(text, range.0);
+    let sel = NSRange { location: start, length: byte_to_utf16(text, range.1) - start };
+    let _: () = unsafe { msg_send![tv, setSelectedRange: sel] };
+    let _: () = unsafe { msg_send![tv, scrollRangeToVisible: sel] };
+}
+
 // ---------------------------------------------------------------------------
 // SeparatorAxis — axis selector for draw_table_separators
 // ---------------------------------------------------------------------------
@@ -197,6 +314,8 @@ define_class!(
                             let range = NSRange { location: utf16_pos, length: 1 };
                             let ns_str = NSString::from_str(replacement);
                             storage.replaceCharactersInRange_withString(range, &ns_str);
+                            // Let the delegate mark the tab dirty and refresh task progress.
+                            let _: () = unsafe { msg_send![self, didChangeText] };
                         }
                     }
                     return;
@@ -241,6 +360,196 @@ define_class!(
             // Not a copy-button click — pass to standard text-view handling.
             let _: () = unsafe { msg_send![super(self), mouseDown: event] };
         }
+
+        /// Option+Up / Option+Down: move the line, list item or section at
+        /// the caret in Editor mode. Handled here rather than as a menu key
+        /// equivalent so the find bar and Viewer mode keep their default.
+        #[unsafe(method(keyDown:))]
+        fn key_down(&self, event: &NSEvent) {
+            if !self.is_viewer_mode() && self.move_block_for_key(event) {
+                return;
+            }
+            let _: () = unsafe { msg_send![super(self), keyDown: event] };
+        }
+
+        /// Return: continue (or end) lists and blockquotes in Editor mode.
+        #[unsafe(method(insertNewline:))]
+        fn insert_newline(&self, sender: Option<&objc2::runtime::AnyObject>) {
+            if !self.is_viewer_mode() && self.continue_list() {
+                return;
+            }
+            let _: () = unsafe { msg_send![super(self), insertNewline: sender] };
+        }
+
+        /// Tab: next snippet stop, next table cell, or indent the list item
+        /// under the caret, in Editor mode.
+        #[unsafe(method(insertTab:))]
+        fn insert_tab(&self, sender: Option<&objc2::runtime::AnyObject>) {
+            if !self.is_viewer_mode()
+                && (self.select_snippet_stop(false)
+                    || self.navigate_table(false)
+                    || self.shift_list_item(false))
+            {
+                return;
+            }
+            let _: () = unsafe { msg_send![super(self), insertTab: sender] };
+        }
+
+        /// Shift-Tab: previous snippet stop, previous table cell, or outdent
+        /// the list item, in Editor mode.
+        #[unsafe(method(insertBacktab:))]
+        fn insert_backtab(&self, sender: Option<&objc2::runtime::AnyObject>) {
+            if !self.is_viewer_mode()
+                && (self.select_snippet_stop(true)
+                    || self.navigate_table(true)
+                    || self.shift_list_item(true))
+            {
+                return;
+            }
+            let _: () = unsafe { msg_send![super(self), insertBacktab: sender] };
+        }
+
+        /// Paste in Editor mode: offer to convert CSV/TSV clipboard data into a
+        /// table, turn a URL pasted onto a selection into a link, and convert
+        /// HTML (web pages, Confluence, Google Docs) into Markdown. Paste as
+        /// Plain Text (`pasteAsPlainText:`) bypasses all three.
+        #[unsafe(method(paste:))]
+        fn paste(&self, sender: Option<&objc2::runtime::AnyObject>) {
+            if !self.is_viewer_mode()
+                && (self.paste_delimited_as_table()
+                    || self.paste_url_as_link()
+                    || self.paste_html_as_markdown())
+            {
+                return;
+            }
+            let _: () = unsafe { msg_send![super(self), paste: sender] };
+        }
+
+        /// Typing in Editor mode: auto-pair brackets, quotes and inline
+        /// markers, step over closers and wrap the selection. Programmatic
+        /// insertions pass an explicit range and are left alone.
+        #[unsafe(method(insertText:replacementRange:))]
+        fn insert_text(&self, string: &AnyObject, replacement_range: NSRange) {
+            let marked: bool = unsafe { msg_send![self, hasMarkedText] };
+            let handled = replacement_range.location >= usize::MAX / 2
+                && !marked
+                && !self.is_viewer_mode()
+                && string
+                    .downcast_ref::<NSString>()
+                    .is_some_and(|s| self.auto_pair(&s.to_string()));
+            if !handled {
+                let _: () = unsafe {
+                    msg_send![super(self), insertText: string, replacementRange: replacement_range]
+                };
+            }
+        }
+
+        /// Backspace between an empty pair deletes both halves in Editor mode.
+        #[unsafe(method(deleteBackward:))]
+        fn delete_backward(&self, sender: Option<&AnyObject>) {
+            if !self.is_viewer_mode() && self.delete_empty_pair() {
+                return;
+            }
+            let _: () = unsafe { msg_send![super(self), deleteBackward: sender] };
+        }
+
+        /// Escape: leave the snippet being filled in; otherwise the default
+        /// (which opens word completion).
+        #[unsafe(method(cancelOperation:))]
+        fn cancel_operation(&self, sender: Option<&AnyObject>) {
+            if self.ivars().snippet.borrow_mut().take().is_some() {
+                return;
+            }
+            let _: () = unsafe { msg_send![super(self), cancelOperation: sender] };
+        }
+
+        /// Keep the active snippet's tab stops in step with typing inside the
+        /// current stop; any other edit ends the snippet.
+        #[unsafe(method(shouldChangeTextInRange:replacementString:))]
+        fn should_change_text(&self, range: NSRange, replacement: Option<&NSString>) -> bool {
+            let allowed: bool = unsafe {
+                msg_send![super(self), shouldChangeTextInRange: range, replacementString: replacement]
+            };
+            let mut slot = self.ivars().snippet.borrow_mut();
+            if let (true, Some(session), Some(replacement)) = (allowed, slot.as_mut(), replacement) {
+                let text = unsafe { self.textStorage() }.map(|s| s.string().to_string()).unwrap_or_default();
+                let start = utf16_to_byte(&text, range.location);
+                let end = utf16_to_byte(&text, range.location + range.length);
+                if !session.edit((start, end), replacement.to_string().len()) {
+                    *slot = None;
+                }
+            }
+            allowed
+        }
+
+        /// Open the snippet popup while a `/trigger` is being typed in Editor mode.
+        #[unsafe(method(didChangeText))]
+        fn did_change_text(&self) {
+            let _: () = unsafe { msg_send![super(self), didChangeText] };
+            if self.ivars().completing.get() {
+                return;
+            }
+            let has_matches = self.snippet_query().is_some_and(|(text, slash, caret)| {
+                !SnippetLibrary::load_cached().matching(&text[slash + 1..caret]).is_empty()
+            });
+            if has_matches {
+                self.ivars().completing.set(true);
+                let _: () = unsafe { msg_send![self, complete: None::<&AnyObject>] };
+                self.ivars().completing.set(false);
+            }
+        }
+
+        /// Complete from the `/` so the whole trigger is replaced.
+        #[unsafe(method(rangeForUserCompletion))]
+        fn range_for_user_completion(&self) -> NSRange {
+            if let Some((text, slash, caret)) = self.snippet_query() {
+                let start = byte_to_utf16(&text, slash);
+                return NSRange { location: start, length: byte_to_utf16(&text, caret) - start };
+            }
+            unsafe { msg_send![super(self), rangeForUserCompletion] }
+        }
+
+        /// Offer the snippets matching the `/query` as completions.
+        #[unsafe(method(completionsForPartialWordRange:indexOfSelectedItem:))]
+        fn completions_for_partial_word_range(
+            &self,
+            range: NSRange,
+            index: *mut isize,
+        ) -> Option<Retained<NSArray<NSString>>> {
+            if let Some((text, slash, caret)) = self.snippet_query() {
+                if byte_to_utf16(&text, slash) == range.location {
+                    let library = SnippetLibrary::load_cached();
+                    let words: Vec<Retained<NSString>> = library
+                        .matching(&text[slash + 1..caret])
+                        .iter()
+                        .map(|s| NSString::from_str(&format!("/{}", s.trigger)))
+                        .collect();
+                    if !index.is_null() {
+                        unsafe { *index = 0 };
+                    }
+                    return Some(NSArray::from_retained_slice(&words));
+                }
+            }
+            unsafe { msg_send![super(self), completionsForPartialWordRange: range, indexOfSelectedItem: index] }
+        }
+
+        /// Accepting a `/trigger` with Return or Tab expands its snippet.
+        #[unsafe(method(insertCompletion:forPartialWordRange:movement:isFinal:))]
+        fn insert_completion(&self, word: &NSString, range: NSRange, movement: isize, is_final: bool) {
+            self.ivars().completing.set(true);
+            let _: () = unsafe {
+                msg_send![super(self),
+                    insertCompletion: word,
+                    forPartialWordRange: range,
+                    movement: movement,
+                    isFinal: is_final]
+            };
+            self.ivars().completing.set(false);
+            let accepted = is_final && matches!(movement, NS_RETURN_TEXT_MOVEMENT | NS_TAB_TEXT_MOVEMENT);
+            if let Some(trigger) = word.to_string().strip_prefix('/').filter(|_| accepted) {
+                self.expand_snippet(trigger);
+            }
+        }
     }
 );
 
@@ -253,6 +562,8 @@ impl MditTextView {
                 feedback: None,
             }),
             checkbox_rects: RefCell::new(Vec::new()),
+            snippet: RefCell::new(None),
+            completing: Cell::new(false),
         });
         unsafe { msg_send![super(this), initWithFrame: frame] }
     }
@@ -272,6 +583,315 @@ impl MditTextView {
         }
     }
 
+    /// The full document text and the caret's byte offset, or `None` when
+    /// there is a non-empty selection (list keys then keep their default).
+    fn text_and_caret(&self) -> Option<(String, usize)> {
+        match text_and_selection(self)? {
+            (text, (start, end)) if start == end => Some((text, start)),
+            _ => None,
+        }
+    }
+
+    /// Auto-pair a single typed character. Returns `true` if it was handled.
+    fn auto_pair(&self, typed: &str) -> bool {
+        let mut chars = typed.chars();
+        let (Some(c), None) = (chars.next(), chars.next()) else {
+            return false;
+        };
+        if !is_auto_pair_char(c) {
+            return false;
+        }
+        let Some((text, selection)) = text_and_selection(self) else {
+            return false;
+        };
+        let Some(delegate) = self.ivars().delegate.borrow().clone() else {
+            return false;
+        };
+        match auto_pair_edit(&text, &delegate.spans(), selection, c) {
+            Some(edit) => {
+                replace_and_select(self, &text, edit.range, &edit.replacement, edit.selection);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Handle Backspace between an empty pair. Returns `true` if consumed.
+    fn delete_empty_pair(&self) -> bool {
+        let Some((text, caret)) = self.text_and_caret() else {
+            return false;
+        };
+        if !text[..caret].chars().next_back().is_some_and(is_auto_pair_char) {
+            return false;
+        }
+        let Some(delegate) = self.ivars().delegate.borrow().clone() else {
+            return false;
+        };
+        match pair_backspace_edit(&text, &delegate.spans(), caret) {
+            Some(edit) => {
+                apply_text_edit(self, &text, &edit);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// The `/query` being typed before the caret in Editor mode, outside
+    /// code: `(text, slash offset, caret)`.
+    fn snippet_query(&self) -> Option<(String, usize, usize)> {
+        if self.is_viewer_mode() {
+            return None;
+        }
+        let (text, caret) = self.text_and_caret()?;
+        let (slash, _) = slash_query(&text, caret)?;
+        if self.in_code(slash) {
+            return None;
+        }
+        Some((text, slash, caret))
+    }
+
+    /// Replace the `/trigger` before the caret with its snippet and select
+    /// the first tab stop.
+    fn expand_snippet(&self, trigger: &str) {
+        let Some((text, slash, caret)) = self.snippet_query() else {
+            return;
+        };
+        let library = SnippetLibrary::load_cached();
+        let Some(snippet) = library.get(trigger) else {
+            return;
+        };
+        // Continuation lines line up with the text after any list marker.
+        let line_start = line_bounds(&text, slash).0;
+        let indent: String = text[line_start..slash]
+            .chars()
+            .map(|c| if c == '\t' { '\t' } else { ' ' })
+            .collect();
+        let expansion = expand(&snippet.body, &indent, &snippet_variable);
+        let (start, end) = expansion.stops[0];
+        replace_and_select(self, &text, (slash, caret), &expansion.text, (slash + start, slash + end));
+        *self.ivars().snippet.borrow_mut() = SnippetSession::start(slash, &expansion);
+    }
+
+    /// Select the next or previous tab stop of the active snippet. Returns
+    /// `false` when no snippet is being filled in.
+    fn select_snippet_stop(&self, backwards: bool) -> bool {
+        let Some(storage) = (unsafe { self.textStorage() }) else {
+            return false;
+        };
+        let text = storage.string().to_string();
+        let mut slot = self.ivars().snippet.borrow_mut();
+        let Some(session) = slot.as_mut() else {
+            return false;
+        };
+        let (start, end) = session.advance(backwards);
+        if end > text.len() || !text.is_char_boundary(start) || !text.is_char_boundary(end) {
+            *slot = None;
+            return false;
+        }
+        if session.is_finished() {
+            *slot = None;
+        }
+        drop(slot);
+        let location = byte_to_utf16(&text, start);
+        let range = NSRange { location, length: byte_to_utf16(&text, end) - location };
+        let _: () = unsafe { msg_send![self, setSelectedRange: range] };
+        let _: () = unsafe { msg_send![self, scrollRangeToVisible: range] };
+        true
+    }
+
+    /// Handle Return inside a list item. Returns `true` if the key was consumed.
+    fn continue_list(&self) -> bool {
+        let Some((text, caret)) = self.text_and_caret() else {
+            return false;
+        };
+        if self.in_code(caret) {
+            return false;
+        }
+        match list_newline_edit(&text, caret) {
+            Some(edit) => {
+                apply_text_edit(self, &text, &edit);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Handle Tab / Shift-Tab on a list item. Returns `true` if the key was
+    /// consumed — including when the item cannot move, so no stray tab
+    /// character ends up inside the list.
+    fn shift_list_item(&self, outdent: bool) -> bool {
+        let Some((text, caret)) = self.text_and_caret() else {
+            return false;
+        };
+        if self.in_code(caret) {
+            return false;
+        }
+        let (line_start, line_end) = line_bounds(&text, caret);
+        let is_item = parse_list_marker(&text[line_start..line_end])
+            .is_some_and(|m| m.kind != ListKind::Quote);
+        if !is_item {
+            return false;
+        }
+        if let Some(edit) = shift_list_item_edit(&text, caret, outdent) {
+            apply_text_edit(self, &text, &edit);
+        }
+        true
+    }
+
+    /// Handle Option+Up / Option+Down. Returns `true` if the key was
+    /// consumed — including when the block is already first or last.
+    fn move_block_for_key(&self, event: &NSEvent) -> bool {
+        let flags = event.modifierFlags().0
+            & NSEventModifierFlags::DeviceIndependentFlagsMask.0
+            & !(NSEventModifierFlags::CapsLock.0
+                | NSEventModifierFlags::NumericPad.0
+                | NSEventModifierFlags::Function.0);
+        if flags != NSEventModifierFlags::Option.0 {
+            return false;
+        }
+        let up = match event.charactersIgnoringModifiers().map(|s| s.to_string()).as_deref() {
+            Some("\u{F700}") => true,
+            Some("\u{F701}") => false,
+            _ => return false,
+        };
+        let Some((text, selection)) = text_and_selection(self) else {
+            return false;
+        };
+        let Some(delegate) = self.ivars().delegate.borrow().clone() else {
+            return false;
+        };
+        if let Some(edit) = move_block_edit(&text, &delegate.spans(), selection, up) {
+            replace_and_select(self, &text, edit.range, &edit.replacement, edit.selection);
+        }
+        true
+    }
+
+    /// Handle Tab / Shift-Tab inside a table. Returns `true` if the key was consumed.
+    ///
+    /// Editor mode keeps no renderer output around, so the table under the
+    /// caret is located by running the renderer on demand.
+    fn navigate_table(&self, backwards: bool) -> bool {
+        let Some((text, caret)) = self.text_and_caret() else {
+            return false;
+        };
+        let Some(delegate) = self.ivars().delegate.borrow().clone() else {
+            return false;
+        };
+        let output = compute_attribute_runs(&text, &delegate.spans(), Some(caret), delegate.base_size());
+        let Some(info) = output.table_infos.iter().find(|t| t.cursor_inside) else {
+            return false;
+        };
+        match table_tab_edit(&text, info, caret, backwards) {
+            Some(edit) => {
+                apply_text_edit(self, &text, &edit);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Offer to convert CSV/TSV on the clipboard into a Markdown table.
+    ///
+    /// Returns `true` if the table was inserted; `false` lets the normal
+    /// paste proceed (including when the user picks "Paste as Text").
+    fn paste_delimited_as_table(&self) -> bool {
+        let Some((_, selection)) = text_and_selection(self) else {
+            return false;
+        };
+        if self.in_code(selection.0) {
+            return false;
+        }
+        let clip = unsafe {
+            NSPasteboard::generalPasteboard().stringForType(NSPasteboardTypeString)
+        };
+        let Some(table) = clip.and_then(|c| delimited_to_table(&c.to_string())) else {
+            return false;
+        };
+
+        let alert = NSAlert::new(self.mtm());
+        alert.setMessageText(&NSString::from_str("Convert pasted data to a table?"));
+        alert.setInformativeText(&NSString::from_str(
+            "The clipboard contains comma- or tab-separated data.",
+        ));
+        alert.addButtonWithTitle(&NSString::from_str("Convert to Table")); // 1000
+        alert.addButtonWithTitle(&NSString::from_str("Paste as Text")); // 1001
+        if alert.runModal() != 1000 {
+            return false;
+        }
+
+        let Some((text, selection)) = text_and_selection(self) else {
+            return false;
+        };
+        apply_text_edit(self, &text, &insert_block_edit(&text, selection, &table));
+        true
+    }
+
+    /// Whether byte offset `pos` lies in a code span, code block or math,
+    /// where pasted text must stay literal.
+    fn in_code(&self, pos: usize) -> bool {
+        fn walk(spans: &[MarkdownSpan], pos: usize) -> bool {
+            spans.iter().any(|span| {
+                let (start, end) = span.source_range;
+                (start..end).contains(&pos)
+                    && match span.kind {
+                        NodeKind::Code | NodeKind::CodeBlock { .. } | NodeKind::Math => true,
+                        _ => walk(&span.children, pos),
+                    }
+            })
+        }
+        let delegate = self.ivars().delegate.borrow();
+        delegate.as_ref().is_some_and(|d| walk(&d.spans(), pos))
+    }
+
+    /// Paste a clipboard URL onto the selection as `[selection](url)`.
+    /// Returns `true` if the link was inserted.
+    fn paste_url_as_link(&self) -> bool {
+        let clip = unsafe {
+            NSPasteboard::generalPasteboard().stringForType(NSPasteboardTypeString)
+        };
+        let Some(clip) = clip.map(|c| c.to_string()) else {
+            return false;
+        };
+        let Some((text, selection)) = text_and_selection(self) else {
+            return false;
+        };
+        if self.in_code(selection.0) {
+            return false;
+        }
+        match link_paste_edit(&text, selection, &clip) {
+            Some(edit) => {
+                apply_text_edit(self, &text, &edit);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Paste the clipboard's HTML flavour converted to Markdown.
+    ///
+    /// Returns `false` (plain paste) when there is no HTML, when the
+    /// conversion adds no structure, or when the caret is inside code, where
+    /// Markdown would be pasted literally.
+    fn paste_html_as_markdown(&self) -> bool {
+        let pb = NSPasteboard::generalPasteboard();
+        let Some(html) = (unsafe { pb.stringForType(NSPasteboardTypeHTML) }) else {
+            return false;
+        };
+        let plain = unsafe { pb.stringForType(NSPasteboardTypeString) }.map(|s| s.to_string());
+        let Some(markdown) = html_paste_markdown(&html.to_string(), plain.as_deref()) else {
+            return false;
+        };
+        let Some((text, selection)) = text_and_selection(self) else {
+            return false;
+        };
+        if self.in_code(selection.0) {
+            return false;
+        }
+        let caret = selection.0 + markdown.len();
+        replace_and_select(self, &text, selection, &markdown, (caret, caret));
+        true
+    }
+
     /// Acquire the layout manager and text container in a single call.
     ///
     /// Both are required by most drawing methods. Returns `None` if either
@@ -1,5 +1,10 @@
 mod app;
+mod cli;
 
 fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if let Some(code) = cli::run(&args) {
+        std::process::exit(code);
+    }
     app::run();
 }
@@ -31,6 +31,8 @@ pub enum NodeKind {
     BlockQuote,
     ThematicBreak,
     Paragraph,
+    /// A hard line break (`\` or two spaces before a newline).
+    LineBreak,
     HtmlInline,
     Other,
 }
@@ -47,7 +49,8 @@ pub struct MarkdownSpan {
 // Options
 // ---------------------------------------------------------------------------
 
-fn make_options() -> Options<'static> {
+/// comrak options for the Markdown dialect mdit parses (GFM plus extensions).
+pub(crate) fn make_options() -> Options<'static> {
     let mut opts = Options::default();
     opts.extension.strikethrough = true;
     opts.extension.table = true;
@@ -58,6 +61,7 @@ fn make_options() -> Options<'static> {
     opts.extension.superscript = true;
     opts.extension.underline = true;
     opts.extension.tasklist = true;
+    opts.extension.autolink = true;
     opts
 }
 
@@ -151,6 +155,7 @@ fn node_to_span<'a>(
         NodeValue::BlockQuote => NodeKind::BlockQuote,
         NodeValue::ThematicBreak => NodeKind::ThematicBreak,
         NodeValue::Paragraph => NodeKind::Paragraph,
+        NodeValue::LineBreak => NodeKind::LineBreak,
         NodeValue::Text(_) => NodeKind::Text,
         NodeValue::HtmlInline(_) => NodeKind::HtmlInline,
         _ => NodeKind::Other,
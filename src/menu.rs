@@ -10,6 +10,8 @@ use objc2::MainThreadOnly;
 use objc2_app_kit::{NSApplication, NSEventModifierFlags, NSMenu, NSMenuItem};
 use objc2_foundation::{MainThreadMarker, NSString};
 
+use crate::editor::search::SearchScope;
+
 // ---------------------------------------------------------------------------
 // Public entry point
 // ---------------------------------------------------------------------------
@@ -30,6 +32,76 @@ pub fn build_main_menu(app: &NSApplication, mtm: MainThreadMarker) {
     app.setMainMenu(Some(&bar));
 }
 
+/// Build the Table menu used by both Edit ▸ Table and the sidebar table button.
+///
+/// Items target `nil`, so they reach `AppDelegate` through the responder chain.
+pub fn table_menu(mtm: MainThreadMarker) -> Retained<NSMenu> {
+    let menu = new_menu("Table", mtm);
+
+    menu.addItem(&item("Insert Table", Some(sel!(insertTable:)), "", mtm));
+    menu.addItem(&NSMenuItem::separatorItem(mtm));
+    menu.addItem(&item("Insert Row Above", Some(sel!(tableInsertRowAbove:)), "", mtm));
+    menu.addItem(&item("Insert Row Below", Some(sel!(tableInsertRowBelow:)), "", mtm));
+    menu.addItem(&item("Delete Row", Some(sel!(tableDeleteRow:)), "", mtm));
+    menu.addItem(&NSMenuItem::separatorItem(mtm));
+    menu.addItem(&item("Insert Column Left", Some(sel!(tableInsertColumnLeft:)), "", mtm));
+    menu.addItem(&item("Insert Column Right", Some(sel!(tableInsertColumnRight:)), "", mtm));
+    menu.addItem(&item("Delete Column", Some(sel!(tableDeleteColumn:)), "", mtm));
+    menu.addItem(&item("Move Column Left", Some(sel!(tableMoveColumnLeft:)), "", mtm));
+    menu.addItem(&item("Move Column Right", Some(sel!(tableMoveColumnRight:)), "", mtm));
+    menu.addItem(&NSMenuItem::separatorItem(mtm));
+    menu.addItem(&with_cmd_opt(item("Format Table", Some(sel!(formatTable:)), "t", mtm)));
+    menu.addItem(&NSMenuItem::separatorItem(mtm));
+    menu.addItem(&item("Copy Table as CSV", Some(sel!(copyTableAsCSV:)), "", mtm));
+    menu.addItem(&item("Copy Table as TSV", Some(sel!(copyTableAsTSV:)), "", mtm));
+
+    menu
+}
+
+/// Build the search scope menu used by the find bar's scope button and
+/// Edit ▸ Find ▸ Search In. The scope's index in `SearchScope::ALL` is the
+/// item's tag; "All Open Tabs" toggles searching every tab.
+pub fn find_scope_menu(mtm: MainThreadMarker) -> Retained<NSMenu> {
+    let menu = new_menu("Search In", mtm);
+    for (i, scope) in SearchScope::ALL.iter().enumerate() {
+        let scope_item = item(scope.label(), Some(sel!(findBarSetScope:)), "", mtm);
+        scope_item.setTag(i as isize);
+        menu.addItem(&scope_item);
+        if *scope == SearchScope::All {
+            menu.addItem(&NSMenuItem::separatorItem(mtm));
+        }
+    }
+    menu.addItem(&NSMenuItem::separatorItem(mtm));
+    menu.addItem(&item("All Open Tabs", Some(sel!(findBarToggleAllTabs:)), "", mtm));
+    menu
+}
+
+/// Build a find bar history menu: one item per recent string (its index in
+/// `entries` is the item's tag) sending `use_action`, then a "Clear" item
+/// sending `clear_action`.
+pub fn history_menu(
+    entries: &[String],
+    use_action: Sel,
+    clear_action: Sel,
+    mtm: MainThreadMarker,
+) -> Retained<NSMenu> {
+    let menu = new_menu("Recent", mtm);
+    if entries.is_empty() {
+        let empty = item("No Recent Entries", None, "", mtm);
+        empty.setEnabled(false);
+        menu.addItem(&empty);
+        return menu;
+    }
+    for (i, entry) in entries.iter().enumerate() {
+        let entry_item = item(entry, Some(use_action), "", mtm);
+        entry_item.setTag(i as isize);
+        menu.addItem(&entry_item);
+    }
+    menu.addItem(&NSMenuItem::separatorItem(mtm));
+    menu.addItem(&item("Clear Recent Entries", Some(clear_action), "", mtm));
+    menu
+}
+
 // ---------------------------------------------------------------------------
 // Per-menu builders
 // ---------------------------------------------------------------------------
@@ -91,6 +163,12 @@ fn edit_menu(mtm: MainThreadMarker) -> Retained<NSMenuItem> {
     menu.addItem(&with_cmd(item("Cut", Some(sel!(cut:)), "x", mtm)));
     menu.addItem(&with_cmd(item("Copy", Some(sel!(copy:)), "c", mtm)));
     menu.addItem(&with_cmd(item("Paste", Some(sel!(paste:)), "v", mtm)));
+    // Paste as Plain Text — Cmd+Shift+Option+V; skips HTML and table conversion
+    let plain_item = item("Paste as Plain Text", Some(sel!(pasteAsPlainText:)), "v", mtm);
+    plain_item.setKeyEquivalentModifierMask(NSEventModifierFlags(
+        NSEventModifierFlags::Command.0 | NSEventModifierFlags::Shift.0 | NSEventModifierFlags::Option.0,
+    ));
+    menu.addItem(&plain_item);
     menu.addItem(&with_cmd(item("Select All", Some(sel!(selectAll:)), "a", mtm)));
     menu.addItem(&NSMenuItem::separatorItem(mtm));
 
@@ -112,6 +190,69 @@ fn edit_menu(mtm: MainThreadMarker) -> Retained<NSMenuItem> {
     menu.addItem(&with_cmd(item("Heading 1", Some(sel!(applyH1:)), "1", mtm)));
     menu.addItem(&with_cmd(item("Heading 2", Some(sel!(applyH2:)), "2", mtm)));
     menu.addItem(&with_cmd(item("Heading 3", Some(sel!(applyH3:)), "3", mtm)));
+    menu.addItem(&item("Promote Section", Some(sel!(promoteSection:)), "", mtm));
+    menu.addItem(&item("Demote Section", Some(sel!(demoteSection:)), "", mtm));
+    menu.addItem(&NSMenuItem::separatorItem(mtm));
+
+    // Move lines, list items and sections. Option+Up/Down is handled by the
+    // text view itself, so the keys keep working in the find bar.
+    menu.addItem(&item("Move Up", Some(sel!(moveBlockUp:)), "", mtm));
+    menu.addItem(&item("Move Down", Some(sel!(moveBlockDown:)), "", mtm));
+    menu.addItem(&NSMenuItem::separatorItem(mtm));
+
+    // Footnotes — Insert is Cmd+Option+F
+    menu.addItem(&with_cmd_opt(item("Insert Footnote", Some(sel!(insertFootnote:)), "f", mtm)));
+    menu.addItem(&item("Renumber Footnotes", Some(sel!(renumberFootnotes:)), "", mtm));
+
+    // Format Document — Cmd+Shift+Option+F
+    let format_item = item("Format Document", Some(sel!(formatDocument:)), "f", mtm);
+    format_item.setKeyEquivalentModifierMask(NSEventModifierFlags(
+        NSEventModifierFlags::Command.0 | NSEventModifierFlags::Shift.0 | NSEventModifierFlags::Option.0,
+    ));
+    menu.addItem(&format_item);
+
+    // Paragraph wrapping — Reflow is Cmd+Option+Q
+    menu.addItem(&with_cmd_opt(item(
+        "Reflow Paragraph",
+        Some(sel!(reflowParagraph:)),
+        "q",
+        mtm,
+    )));
+    menu.addItem(&item("Unwrap Paragraph", Some(sel!(unwrapParagraph:)), "", mtm));
+    let wrap_item = item("Wrap Column", None, "", mtm);
+    let wrap_menu = new_menu("Wrap Column", mtm);
+    for column in [72, 80, 100, 120] {
+        let i = item(&column.to_string(), Some(sel!(setWrapColumn:)), "", mtm);
+        i.setTag(column);
+        wrap_menu.addItem(&i);
+    }
+    wrap_item.setSubmenu(Some(&wrap_menu));
+    menu.addItem(&wrap_item);
+
+    // Tasks submenu — Toggle Task is Cmd+Return
+    let tasks_item = item("Tasks", None, "", mtm);
+    let tasks_menu = new_menu("Tasks", mtm);
+    tasks_menu.addItem(&with_cmd(item("Toggle Task", Some(sel!(toggleTask:)), "\r", mtm)));
+    tasks_menu.addItem(&NSMenuItem::separatorItem(mtm));
+    tasks_menu.addItem(&item(
+        "Move Completed to Bottom",
+        Some(sel!(sortCompletedTasks:)),
+        "",
+        mtm,
+    ));
+    tasks_menu.addItem(&item(
+        "Archive Completed Tasks",
+        Some(sel!(archiveCompletedTasks:)),
+        "",
+        mtm,
+    ));
+    tasks_item.setSubmenu(Some(&tasks_menu));
+    menu.addItem(&tasks_item);
+
+    // Table submenu — shared with the sidebar table button
+    let table_item = item("Table", None, "", mtm);
+    table_item.setSubmenu(Some(&table_menu(mtm)));
+    menu.addItem(&table_item);
     menu.addItem(&NSMenuItem::separatorItem(mtm));
 
     // Find submenu
@@ -120,6 +261,16 @@ fn edit_menu(mtm: MainThreadMarker) -> Retained<NSMenuItem> {
     find_menu.addItem(&with_cmd(item("Find\u{2026}", Some(sel!(openFindBar:)), "f", mtm)));
     find_menu.addItem(&with_cmd(item("Find Next", Some(sel!(findNext:)), "g", mtm)));
     find_menu.addItem(&with_cmd_shift(item("Find Previous", Some(sel!(findPrevious:)), "g", mtm)));
+    find_menu.addItem(&with_cmd_shift(item(
+        "Find in Folder\u{2026}",
+        Some(sel!(showFindInFolder:)),
+        "f",
+        mtm,
+    )));
+    find_menu.addItem(&NSMenuItem::separatorItem(mtm));
+    let scope_item = item("Search In", None, "", mtm);
+    scope_item.setSubmenu(Some(&find_scope_menu(mtm)));
+    find_menu.addItem(&scope_item);
     find_item.setSubmenu(Some(&find_menu));
     menu.addItem(&find_item);
 
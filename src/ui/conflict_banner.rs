@@ -0,0 +1,150 @@
+//! Banner shown above a document whose file changed on disk while it had
+//! unsaved edits.
+//!
+//! A message on the left and three buttons on the right: Reload
+//! (`conflictReload:`), Keep Mine (`conflictKeepMine:`) and Merge
+//! (`conflictMerge:`). The banner floats over the top edge of the text
+//! area; the app positions it and decides when it shows.
+
+use objc2::rc::Retained;
+use objc2::runtime::{AnyObject, Sel};
+use objc2::{msg_send, sel, MainThreadOnly};
+use objc2_app_kit::{
+    NSAutoresizingMaskOptions, NSBezelStyle, NSButton, NSColor, NSControl, NSFont, NSTextField,
+    NSView,
+};
+use objc2_core_graphics::CGColor;
+use objc2_foundation::{MainThreadMarker, NSPoint, NSRect, NSSize, NSString};
+
+pub const HEIGHT: f64 = 34.0;
+const PAD: f64 = 10.0;
+const BTN_H: f64 = 24.0;
+const GAP: f64 = 6.0;
+const RELOAD_W: f64 = 72.0;
+const KEEP_W: f64 = 88.0;
+const MERGE_W: f64 = 68.0;
+const LABEL_H: f64 = 16.0;
+
+pub struct ConflictBanner {
+    container: Retained<NSView>,
+    label: Retained<NSTextField>,
+    buttons: [Retained<NSButton>; 3],
+}
+
+impl ConflictBanner {
+    /// Create the banner, hidden.
+    pub fn new(mtm: MainThreadMarker, target: &AnyObject) -> Self {
+        let container = NSView::initWithFrame(
+            NSView::alloc(mtm),
+            NSRect::new(NSPoint::new(0.0, 0.0), NSSize::new(0.0, HEIGHT)),
+        );
+        container.setWantsLayer(true);
+        // Pale amber with a darker bottom border.
+        set_layer_bg(&container, (1.0, 0.957, 0.827));
+        let border = NSView::initWithFrame(
+            NSView::alloc(mtm),
+            NSRect::new(NSPoint::new(0.0, 0.0), NSSize::new(0.0, 1.0)),
+        );
+        border.setWantsLayer(true);
+        set_layer_bg(&border, (0.851, 0.749, 0.510));
+        border.setAutoresizingMask(NSAutoresizingMaskOptions::ViewWidthSizable);
+        container.addSubview(&border);
+
+        let label = NSTextField::initWithFrame(
+            NSTextField::alloc(mtm),
+            NSRect::new(NSPoint::new(PAD, (HEIGHT - LABEL_H) / 2.0), NSSize::new(0.0, LABEL_H)),
+        );
+        label.setEditable(false);
+        label.setSelectable(false);
+        label.setBordered(false);
+        label.setDrawsBackground(false);
+        label.setFont(Some(&NSFont::systemFontOfSize_weight(12.0, 0.0)));
+        label.setTextColor(Some(&NSColor::colorWithRed_green_blue_alpha(0.35, 0.27, 0.08, 1.0)));
+        container.addSubview(&label);
+
+        let font = NSFont::systemFontOfSize_weight(12.0, 0.0);
+        let buttons = [
+            ("Reload", RELOAD_W, sel!(conflictReload:)),
+            ("Keep Mine", KEEP_W, sel!(conflictKeepMine:)),
+            ("Merge", MERGE_W, sel!(conflictMerge:)),
+        ]
+        .map(|(title, w, action)| {
+            let btn = make_btn(mtm, title, NSSize::new(w, BTN_H), action, target, &font);
+            container.addSubview(&btn);
+            btn
+        });
+        let tips = [
+            "Discard your changes and load the file from disk",
+            "Keep your version; saving will overwrite the file",
+            "Combine both versions; overlapping edits are marked as conflicts",
+        ];
+        for (btn, tip) in buttons.iter().zip(tips) {
+            let tip = NSString::from_str(tip);
+            unsafe { let _: () = msg_send![&**btn, setToolTip: &*tip]; }
+        }
+
+        let banner = Self { container, label, buttons };
+        banner.set_visible(false);
+        banner
+    }
+
+    pub fn view(&self) -> &NSView {
+        &self.container
+    }
+
+    /// "“notes.md” was changed by another application."
+    pub fn set_file_name(&self, name: &str) {
+        self.label.setStringValue(&NSString::from_str(&format!(
+            "\u{201C}{name}\u{201D} was changed by another application."
+        )));
+    }
+
+    pub fn set_visible(&self, visible: bool) {
+        unsafe { let _: () = msg_send![&*self.container, setHidden: !visible]; }
+    }
+
+    /// Place the banner and lay out its contents for `frame`'s width.
+    pub fn set_frame(&self, frame: NSRect) {
+        self.container.setFrame(frame);
+        let w = frame.size.width;
+        let btn_y = (HEIGHT - BTN_H) / 2.0;
+        let mut x = w - PAD;
+        for btn in self.buttons.iter().rev() {
+            let bw = btn.frame().size.width;
+            x -= bw;
+            btn.setFrameOrigin(NSPoint::new(x, btn_y));
+            x -= GAP;
+        }
+        self.label.setFrameSize(NSSize::new((x - PAD).max(0.0), LABEL_H));
+    }
+}
+
+/// Create a rounded push button.
+fn make_btn(
+    mtm: MainThreadMarker,
+    title: &str,
+    size: NSSize,
+    action: Sel,
+    target: &AnyObject,
+    font: &NSFont,
+) -> Retained<NSButton> {
+    let btn = NSButton::initWithFrame(NSButton::alloc(mtm), NSRect::new(NSPoint::new(0.0, 0.0), size));
+    btn.setTitle(&NSString::from_str(title));
+    btn.setBezelStyle(NSBezelStyle::Rounded);
+    btn.setFont(Some(font));
+    unsafe {
+        NSControl::setTarget(&btn, Some(target));
+        NSControl::setAction(&btn, Some(action));
+    }
+    btn
+}
+
+/// Set the CALayer backgroundColor of an NSView.
+fn set_layer_bg(view: &NSView, (r, g, b): (f64, f64, f64)) {
+    if let Some(layer) = view.layer() {
+        let color = NSColor::colorWithRed_green_blue_alpha(r, g, b, 1.0);
+        let cg: *const CGColor = unsafe { msg_send![&*color, CGColor] };
+        let raw: *const AnyObject = Retained::as_ptr(&layer).cast();
+        let _: () = unsafe { msg_send![raw, setBackgroundColor: cg] };
+    }
+}
@@ -2,6 +2,7 @@
 //!
 //! Sits at the bottom of the editor window. Compact (30px) shows the find
 //! row only; expanded (56px) adds a replace row above the find row.
+//! A ▾ button after each field pops up its recent strings.
 
 use std::cell::Cell;
 
@@ -13,6 +14,7 @@ use objc2_app_kit::{
     NSBezelStyle, NSButton, NSButtonType, NSColor, NSControl, NSFont, NSTextField, NSView,
 };
 use super::NS_TEXT_ALIGNMENT_CENTER;
+use crate::editor::search::{SearchOptions, SearchScope};
 use objc2_foundation::{MainThreadMarker, NSPoint, NSRect, NSSize, NSString};
 
 // ---------------------------------------------------------------------------
@@ -30,29 +32,41 @@ const REPLACE_ROW_H: f64 = 26.0;
 const LEFT_PAD: f64 = 8.0;
 const RIGHT_PAD: f64 = 8.0;
 
+// History ▾ button after each field:
+const HISTORY_W: f64 = 18.0;
+const GAP_HISTORY: f64 = 2.0;
+
 // Fixed widths for find-row controls (right of search_field):
 const PREV_W: f64 = 26.0;
 const NEXT_W: f64 = 26.0;
 const AA_W: f64 = 32.0;
+const WORD_W: f64 = 28.0;
+const REGEX_W: f64 = 28.0;
+const SCOPE_W: f64 = 116.0;
 const COUNT_W: f64 = 60.0;
 const CLOSE_W: f64 = 28.0;
 // Gap constants
 const GAP_PREV: f64 = 4.0;
 const GAP_NEXT: f64 = 2.0;
 const GAP_AA: f64 = 4.0;
+const GAP_TOGGLE: f64 = 2.0;
 const GAP_COUNT: f64 = 4.0;
 const GAP_CLOSE: f64 = 6.0;
 
-// search_field width = width - LEFT_PAD - PREV_W - GAP_PREV - NEXT_W - GAP_NEXT
-//                           - AA_W - GAP_AA - COUNT_W - GAP_COUNT - GAP_CLOSE - CLOSE_W - RIGHT_PAD
-// = width - (LEFT_PAD + PREV_W + GAP_PREV + NEXT_W + GAP_NEXT + AA_W + GAP_AA + COUNT_W + GAP_COUNT + GAP_CLOSE + CLOSE_W + RIGHT_PAD)
+// search_field width = width - LEFT_PAD - GAP_HISTORY - HISTORY_W - PREV_W - GAP_PREV - NEXT_W - GAP_NEXT
+//                           - AA_W - GAP_AA - WORD_W - REGEX_W - 2 * GAP_TOGGLE - SCOPE_W - GAP_AA
+//                           - COUNT_W - GAP_COUNT - GAP_CLOSE - CLOSE_W - RIGHT_PAD
 
 fn search_field_w(width: f64) -> f64 {
     (width
         - LEFT_PAD
+        - GAP_HISTORY - HISTORY_W
         - GAP_PREV - PREV_W
         - GAP_NEXT - NEXT_W
         - GAP_AA - AA_W
+        - GAP_TOGGLE - WORD_W
+        - GAP_TOGGLE - REGEX_W
+        - GAP_AA - SCOPE_W
         - GAP_COUNT - COUNT_W
         - GAP_CLOSE - CLOSE_W
         - RIGHT_PAD)
@@ -60,12 +74,19 @@ fn search_field_w(width: f64) -> f64 {
 }
 
 // Replace row fixed widths
+const PRESERVE_W: f64 = 30.0;
 const REPLACE_BTN_W: f64 = 68.0;
 const REPLACE_ALL_W: f64 = 40.0;
 const REPLACE_GAP: f64 = 4.0;
 
 fn replace_field_w(width: f64) -> f64 {
-    (width - LEFT_PAD - REPLACE_GAP - REPLACE_BTN_W - REPLACE_GAP - REPLACE_ALL_W - RIGHT_PAD)
+    (width
+        - LEFT_PAD
+        - GAP_HISTORY - HISTORY_W
+        - REPLACE_GAP - PRESERVE_W
+        - REPLACE_GAP - REPLACE_BTN_W
+        - REPLACE_GAP - REPLACE_ALL_W
+        - RIGHT_PAD)
         .max(0.0)
 }
 
@@ -77,15 +98,26 @@ pub struct FindBar {
     container:       Retained<NSView>,
     border:          Retained<NSView>,
     search_field:    Retained<NSTextField>,
+    search_history_btn: Retained<NSButton>,
     prev_btn:        Retained<NSButton>,
     next_btn:        Retained<NSButton>,
     aa_btn:          Retained<NSButton>,
+    word_btn:        Retained<NSButton>,
+    regex_btn:       Retained<NSButton>,
+    scope_btn:       Retained<NSButton>,
     count_label:     Retained<NSTextField>,
     close_btn:       Retained<NSButton>,
     replace_field:   Retained<NSTextField>,
+    replace_history_btn: Retained<NSButton>,
+    preserve_btn:    Retained<NSButton>,
     replace_btn:     Retained<NSButton>,
     replace_all_btn: Retained<NSButton>,
     case_sensitive:  Cell<bool>,
+    whole_word:      Cell<bool>,
+    regex:           Cell<bool>,
+    scope:           Cell<SearchScope>,
+    all_tabs:        Cell<bool>,
+    preserve_case:   Cell<bool>,
 }
 
 impl FindBar {
@@ -130,8 +162,23 @@ impl FindBar {
         set_target_action(&search_field, target, objc2::sel!(findNext:));
         container.addSubview(&search_field);
 
+        // ── search_history_btn ▾ ──────────────────────────────────────────────
+        let search_history_btn = make_text_btn(
+            mtm,
+            "\u{25BE}",
+            NSRect::new(
+                NSPoint::new(LEFT_PAD + sf_w + GAP_HISTORY, 0.0),
+                NSSize::new(HISTORY_W, BTN_H),
+            ),
+            objc2::sel!(findBarShowSearchHistory:),
+            target,
+            &sys_12,
+        );
+        set_tool_tip(&search_history_btn, "Recent searches");
+        container.addSubview(&search_history_btn);
+
         // ── prev_btn ◂ ────────────────────────────────────────────────────────
-        let prev_x = LEFT_PAD + sf_w + GAP_PREV;
+        let prev_x = LEFT_PAD + sf_w + GAP_HISTORY + HISTORY_W + GAP_PREV;
         let prev_btn = make_text_btn(
             mtm,
             "\u{25C2}",
@@ -166,8 +213,47 @@ impl FindBar {
         );
         container.addSubview(&aa_btn);
 
+        // ── word_btn (whole-word toggle) ──────────────────────────────────────
+        let word_x = aa_x + AA_W + GAP_TOGGLE;
+        let word_btn = make_text_btn(
+            mtm,
+            "W",
+            NSRect::new(NSPoint::new(word_x, 0.0), NSSize::new(WORD_W, BTN_H)),
+            objc2::sel!(findBarToggleWholeWord:),
+            target,
+            &sys_12,
+        );
+        set_tool_tip(&word_btn, "Match whole words");
+        container.addSubview(&word_btn);
+
+        // ── regex_btn (.* / regular-expression toggle) ────────────────────────
+        let regex_x = word_x + WORD_W + GAP_TOGGLE;
+        let regex_btn = make_text_btn(
+            mtm,
+            ".*",
+            NSRect::new(NSPoint::new(regex_x, 0.0), NSSize::new(REGEX_W, BTN_H)),
+            objc2::sel!(findBarToggleRegex:),
+            target,
+            &sys_12,
+        );
+        set_tool_tip(&regex_btn, "Use regular expression");
+        container.addSubview(&regex_btn);
+
+        // ── scope_btn (pops up the search scope menu) ─────────────────────────
+        let scope_x = regex_x + REGEX_W + GAP_AA;
+        let scope_btn = make_text_btn(
+            mtm,
+            &scope_title(SearchScope::All, false),
+            NSRect::new(NSPoint::new(scope_x, 0.0), NSSize::new(SCOPE_W, BTN_H)),
+            objc2::sel!(findBarShowScopeMenu:),
+            target,
+            &sys_12,
+        );
+        set_tool_tip(&scope_btn, "Search in");
+        container.addSubview(&scope_btn);
+
         // ── count_label ───────────────────────────────────────────────────────
-        let count_x = aa_x + AA_W + GAP_COUNT;
+        let count_x = scope_x + SCOPE_W + GAP_COUNT;
         let count_label = NSTextField::initWithFrame(
             NSTextField::alloc(mtm),
             NSRect::new(NSPoint::new(count_x, 0.0), NSSize::new(COUNT_W, FIND_ROW_H)),
@@ -211,8 +297,36 @@ impl FindBar {
         replace_field.setHidden(true);
         container.addSubview(&replace_field);
 
+        // ── replace_history_btn ▾ ─────────────────────────────────────────────
+        let rh_x = LEFT_PAD + rf_w + GAP_HISTORY;
+        let replace_history_btn = make_text_btn(
+            mtm,
+            "\u{25BE}",
+            NSRect::new(NSPoint::new(rh_x, 0.0), NSSize::new(HISTORY_W, BTN_H)),
+            objc2::sel!(findBarShowReplaceHistory:),
+            target,
+            &sys_12,
+        );
+        set_tool_tip(&replace_history_btn, "Recent replacements");
+        replace_history_btn.setHidden(true);
+        container.addSubview(&replace_history_btn);
+
+        // ── preserve_btn (AB / preserve-case toggle) ──────────────────────────
+        let pc_x = rh_x + HISTORY_W + REPLACE_GAP;
+        let preserve_btn = make_text_btn(
+            mtm,
+            "AB",
+            NSRect::new(NSPoint::new(pc_x, 0.0), NSSize::new(PRESERVE_W, BTN_H)),
+            objc2::sel!(findBarTogglePreserveCase:),
+            target,
+            &sys_12,
+        );
+        set_tool_tip(&preserve_btn, "Preserve case");
+        preserve_btn.setHidden(true);
+        container.addSubview(&preserve_btn);
+
         // ── replace_btn ───────────────────────────────────────────────────────
-        let rb_x = LEFT_PAD + rf_w + REPLACE_GAP;
+        let rb_x = pc_x + PRESERVE_W + REPLACE_GAP;
         let replace_btn = make_text_btn(
             mtm,
             "Replace",
@@ -244,16 +358,28 @@ impl FindBar {
             container,
             border,
             search_field,
+            search_history_btn,
             prev_btn,
             next_btn,
             aa_btn,
+            word_btn,
+            regex_btn,
+            scope_btn,
             count_label,
             close_btn,
             replace_field,
+            replace_history_btn,
+            preserve_btn,
             replace_btn,
             replace_all_btn,
             case_sensitive: Cell::new(false),
+            whole_word: Cell::new(false),
+            regex: Cell::new(false),
+            scope: Cell::new(SearchScope::All),
+            all_tabs: Cell::new(false),
+            preserve_case: Cell::new(false),
         };
+        fb.update_toggle_visuals();
 
         // Position controls for initial compact height
         fb.set_height(FIND_H_COMPACT);
@@ -282,8 +408,13 @@ impl FindBar {
         sff.size.width = sf_w;
         self.search_field.setFrame(sff);
 
+        // Reposition search_history_btn
+        let mut shf = self.search_history_btn.frame();
+        shf.origin.x = LEFT_PAD + sf_w + GAP_HISTORY;
+        self.search_history_btn.setFrame(shf);
+
         // Reposition prev_btn
-        let prev_x = LEFT_PAD + sf_w + GAP_PREV;
+        let prev_x = LEFT_PAD + sf_w + GAP_HISTORY + HISTORY_W + GAP_PREV;
         let mut pf = self.prev_btn.frame();
         pf.origin.x = prev_x;
         self.prev_btn.setFrame(pf);
@@ -300,8 +431,24 @@ impl FindBar {
         af.origin.x = aa_x;
         self.aa_btn.setFrame(af);
 
+        // Reposition word_btn and regex_btn
+        let word_x = aa_x + AA_W + GAP_TOGGLE;
+        let mut wf = self.word_btn.frame();
+        wf.origin.x = word_x;
+        self.word_btn.setFrame(wf);
+        let regex_x = word_x + WORD_W + GAP_TOGGLE;
+        let mut rgf = self.regex_btn.frame();
+        rgf.origin.x = regex_x;
+        self.regex_btn.setFrame(rgf);
+
+        // Reposition scope_btn
+        let scope_x = regex_x + REGEX_W + GAP_AA;
+        let mut scf = self.scope_btn.frame();
+        scf.origin.x = scope_x;
+        self.scope_btn.setFrame(scf);
+
         // Reposition count_label
-        let count_x = aa_x + AA_W + GAP_COUNT;
+        let count_x = scope_x + SCOPE_W + GAP_COUNT;
         let mut cf = self.count_label.frame();
         cf.origin.x = count_x;
         self.count_label.setFrame(cf);
@@ -318,8 +465,18 @@ impl FindBar {
         rff.size.width = rf_w;
         self.replace_field.setFrame(rff);
 
+        // Reposition replace_history_btn and preserve_btn
+        let rh_x = LEFT_PAD + rf_w + GAP_HISTORY;
+        let mut rhf = self.replace_history_btn.frame();
+        rhf.origin.x = rh_x;
+        self.replace_history_btn.setFrame(rhf);
+        let pc_x = rh_x + HISTORY_W + REPLACE_GAP;
+        let mut pcf = self.preserve_btn.frame();
+        pcf.origin.x = pc_x;
+        self.preserve_btn.setFrame(pcf);
+
         // Reposition replace_btn
-        let rb_x = LEFT_PAD + rf_w + REPLACE_GAP;
+        let rb_x = pc_x + PRESERVE_W + REPLACE_GAP;
         let mut rbf = self.replace_btn.frame();
         rbf.origin.x = rb_x;
         self.replace_btn.setFrame(rbf);
@@ -355,9 +512,13 @@ impl FindBar {
 
         unsafe {
             reposition_y(&*self.search_field as *const _ as *const AnyObject, find_field_y);
+            reposition_y(&*self.search_history_btn as *const _ as *const AnyObject, find_btn_y);
             reposition_y(&*self.prev_btn     as *const _ as *const AnyObject, find_btn_y);
             reposition_y(&*self.next_btn     as *const _ as *const AnyObject, find_btn_y);
             reposition_y(&*self.aa_btn       as *const _ as *const AnyObject, find_btn_y);
+            reposition_y(&*self.word_btn     as *const _ as *const AnyObject, find_btn_y);
+            reposition_y(&*self.regex_btn    as *const _ as *const AnyObject, find_btn_y);
+            reposition_y(&*self.scope_btn    as *const _ as *const AnyObject, find_btn_y);
             reposition_y(&*self.count_label  as *const _ as *const AnyObject, find_field_y);
             reposition_y(&*self.close_btn    as *const _ as *const AnyObject, find_btn_y);
         }
@@ -368,6 +529,8 @@ impl FindBar {
 
         unsafe {
             reposition_y(&*self.replace_field   as *const _ as *const AnyObject, replace_field_y);
+            reposition_y(&*self.replace_history_btn as *const _ as *const AnyObject, replace_btn_y);
+            reposition_y(&*self.preserve_btn    as *const _ as *const AnyObject, replace_btn_y);
             reposition_y(&*self.replace_btn     as *const _ as *const AnyObject, replace_btn_y);
             reposition_y(&*self.replace_all_btn as *const _ as *const AnyObject, replace_btn_y);
         }
@@ -404,9 +567,25 @@ impl FindBar {
         }
     }
 
-    /// Show or hide the replace row (replace_field, replace_btn, replace_all_btn).
+    /// Show an invalid-pattern message in place of the match count (the full
+    /// text goes in the tooltip), or clear it with `None`.
+    pub fn set_error(&self, message: Option<&str>) {
+        match message {
+            Some(msg) => {
+                self.count_label.setStringValue(&NSString::from_str("Invalid"));
+                set_tool_tip(&self.count_label, msg);
+                self.set_no_match(true);
+            }
+            None => set_tool_tip(&self.count_label, ""),
+        }
+    }
+
+    /// Show or hide the replace row (replace_field, its history and
+    /// preserve-case buttons, replace_btn, replace_all_btn).
     pub fn show_replace_row(&self, visible: bool) {
         self.replace_field.setHidden(!visible);
+        self.replace_history_btn.setHidden(!visible);
+        self.preserve_btn.setHidden(!visible);
         self.replace_btn.setHidden(!visible);
         self.replace_all_btn.setHidden(!visible);
     }
@@ -421,6 +600,16 @@ impl FindBar {
         self.replace_field.stringValue().to_string()
     }
 
+    /// Fill the search field (e.g. from the history menu).
+    pub fn set_search_text(&self, text: &str) {
+        self.search_field.setStringValue(&NSString::from_str(text));
+    }
+
+    /// Fill the replace field (e.g. from the history menu).
+    pub fn set_replace_text(&self, text: &str) {
+        self.replace_field.setStringValue(&NSString::from_str(text));
+    }
+
     /// Whether the Aa button is in "case-sensitive" state (true = case-sensitive).
     pub fn is_case_sensitive(&self) -> bool {
         self.case_sensitive.get()
@@ -430,7 +619,58 @@ impl FindBar {
     pub fn toggle_case_sensitive(&self) {
         let new_val = !self.case_sensitive.get();
         self.case_sensitive.set(new_val);
-        self.update_aa_visual();
+        self.update_toggle_visuals();
+    }
+
+    /// Toggle whole-word matching.
+    pub fn toggle_whole_word(&self) {
+        self.whole_word.set(!self.whole_word.get());
+        self.update_toggle_visuals();
+    }
+
+    /// Toggle regular-expression mode.
+    pub fn toggle_regex(&self) {
+        self.regex.set(!self.regex.get());
+        self.update_toggle_visuals();
+    }
+
+    /// Toggle case-preserving replacement.
+    pub fn toggle_preserve_case(&self) {
+        self.preserve_case.set(!self.preserve_case.get());
+        self.update_toggle_visuals();
+    }
+
+    /// The current search scope.
+    pub fn scope(&self) -> SearchScope {
+        self.scope.get()
+    }
+
+    /// Set the search scope and show it on the scope button.
+    pub fn set_scope(&self, scope: SearchScope) {
+        self.scope.set(scope);
+        self.update_scope_btn();
+    }
+
+    /// Whether the search covers every open tab.
+    pub fn is_all_tabs(&self) -> bool {
+        self.all_tabs.get()
+    }
+
+    /// Toggle searching every open tab.
+    pub fn toggle_all_tabs(&self) {
+        self.all_tabs.set(!self.all_tabs.get());
+        self.update_scope_btn();
+    }
+
+    /// The current toggle states and scope as search options.
+    pub fn search_options(&self) -> SearchOptions {
+        SearchOptions {
+            case_sensitive: self.case_sensitive.get(),
+            whole_word: self.whole_word.get(),
+            regex: self.regex.get(),
+            scope: self.scope.get(),
+            preserve_case: self.preserve_case.get(),
+        }
     }
 
     /// Focus the search field and select all text.
@@ -459,20 +699,18 @@ impl FindBar {
 
     // ── Private helpers ───────────────────────────────────────────────────────
 
-    fn update_aa_visual(&self) {
-        if self.case_sensitive.get() {
-            // Active: orange text
-            let orange = NSColor::colorWithRed_green_blue_alpha(0.784, 0.475, 0.255, 1.0);
-            unsafe { let _: () = msg_send![&*self.aa_btn, setContentTintColor: &*orange]; }
-        } else {
-            // Inactive: secondary label color
-            unsafe {
-                let _: () = msg_send![
-                    &*self.aa_btn,
-                    setContentTintColor: &*NSColor::secondaryLabelColor()
-                ];
-            }
-        }
+    fn update_toggle_visuals(&self) {
+        set_toggle_tint(&self.aa_btn, self.case_sensitive.get());
+        set_toggle_tint(&self.word_btn, self.whole_word.get());
+        set_toggle_tint(&self.regex_btn, self.regex.get());
+        set_toggle_tint(&self.preserve_btn, self.preserve_case.get());
+        self.update_scope_btn();
+    }
+
+    fn update_scope_btn(&self) {
+        let (scope, all_tabs) = (self.scope.get(), self.all_tabs.get());
+        self.scope_btn.setTitle(&NSString::from_str(&scope_title(scope, all_tabs)));
+        set_toggle_tint(&self.scope_btn, scope != SearchScope::All || all_tabs);
     }
 }
 
@@ -490,6 +728,31 @@ fn set_layer_bg(view: &NSView, (r, g, b): (f64, f64, f64)) {
     }
 }
 
+/// Scope button title, e.g. "Headings ▾", "All Tabs ▾" or "Code · Tabs ▾".
+fn scope_title(scope: SearchScope, all_tabs: bool) -> String {
+    match (scope, all_tabs) {
+        (_, false) => format!("{} \u{25BE}", scope.label()),
+        (SearchScope::All, true) => "All Tabs \u{25BE}".to_string(),
+        (_, true) => format!("{} \u{00B7} Tabs \u{25BE}", scope.label()),
+    }
+}
+
+/// Tint a toggle button: orange when active, secondary label color when not.
+fn set_toggle_tint(btn: &NSButton, active: bool) {
+    let color = if active {
+        NSColor::colorWithRed_green_blue_alpha(0.784, 0.475, 0.255, 1.0)
+    } else {
+        NSColor::secondaryLabelColor()
+    };
+    unsafe { let _: () = msg_send![btn, setContentTintColor: &*color]; }
+}
+
+/// Set (or clear, with an empty string) a view's tooltip.
+fn set_tool_tip(view: &NSView, tip: &str) {
+    let tip = (!tip.is_empty()).then(|| NSString::from_str(tip));
+    unsafe { let _: () = msg_send![view, setToolTip: tip.as_deref()]; }
+}
+
 /// Create an editable text field styled as an input box (input background, Georgia font).
 fn make_input_field(
     mtm: MainThreadMarker,
@@ -0,0 +1,288 @@
+//! Find in Folder window.
+//!
+//! A query field, a folder button and a Stop button above a read-only list
+//! of results grouped by file. Each hit line is a link to `mdit-hit:<n>`,
+//! where `n` counts hits in the order they were added; clicking one reaches
+//! the results view's delegate through `textView:clickedOnLink:atIndex:`,
+//! and [`hit_index`] recovers `n`.
+
+use std::path::Path;
+
+use objc2::rc::Retained;
+use objc2::runtime::{AnyObject, Sel};
+use objc2::{msg_send, MainThreadOnly};
+use objc2_app_kit::{
+    NSAutoresizingMaskOptions, NSBackingStoreType, NSBezelStyle, NSButton, NSColor, NSControl,
+    NSFont, NSFontAttributeName, NSForegroundColorAttributeName, NSLinkAttributeName,
+    NSScrollView, NSTextField, NSTextView, NSWindow, NSWindowStyleMask,
+};
+use objc2_foundation::{MainThreadMarker, NSPoint, NSRange, NSRect, NSSize, NSString, NSURL};
+
+use crate::editor::folder_search::FileResult;
+
+const WIN_W: f64 = 560.0;
+const WIN_H: f64 = 440.0;
+const PAD: f64 = 12.0;
+const ROW_H: f64 = 24.0;
+const STATUS_H: f64 = 18.0;
+const FOLDER_W: f64 = 140.0;
+const STOP_W: f64 = 64.0;
+const GAP: f64 = 8.0;
+
+const HIT_SCHEME: &str = "mdit-hit:";
+
+/// The hit number carried by a result link, e.g. `mdit-hit:3` → 3.
+pub fn hit_index(link: &str) -> Option<usize> {
+    link.strip_prefix(HIT_SCHEME)?.parse().ok()
+}
+
+pub struct FolderSearchPanel {
+    window: Retained<NSWindow>,
+    query_field: Retained<NSTextField>,
+    folder_btn: Retained<NSButton>,
+    stop_btn: Retained<NSButton>,
+    status_label: Retained<NSTextField>,
+    results: Retained<NSTextView>,
+}
+
+impl FolderSearchPanel {
+    /// Build the window (not yet shown). Return in the query field sends
+    /// `folderSearchStart:`, the buttons `folderSearchChooseFolder:` and
+    /// `folderSearchStop:`, all to `target`, which is also the results
+    /// view's delegate.
+    pub fn new(mtm: MainThreadMarker, target: &AnyObject) -> Self {
+        let style = NSWindowStyleMask::Titled
+            | NSWindowStyleMask::Closable
+            | NSWindowStyleMask::Resizable;
+        let window = unsafe {
+            NSWindow::initWithContentRect_styleMask_backing_defer(
+                NSWindow::alloc(mtm),
+                NSRect::new(NSPoint::new(0.0, 0.0), NSSize::new(WIN_W, WIN_H)),
+                style,
+                NSBackingStoreType::Buffered,
+                false,
+            )
+        };
+        unsafe { window.setReleasedWhenClosed(false) };
+        window.setTitle(&NSString::from_str("Find in Folder"));
+        window.setContentMinSize(NSSize::new(380.0, 240.0));
+        let content = window.contentView().unwrap();
+
+        let pinned_top = NSAutoresizingMaskOptions::ViewMinYMargin;
+        let sys_12 = NSFont::systemFontOfSize_weight(12.0, 0.0);
+
+        // ── Top row: query, folder, stop ──────────────────────────────────
+        let row_y = WIN_H - PAD - ROW_H;
+        let query_w = WIN_W - 2.0 * PAD - FOLDER_W - STOP_W - 2.0 * GAP;
+        let query_field = NSTextField::initWithFrame(
+            NSTextField::alloc(mtm),
+            NSRect::new(NSPoint::new(PAD, row_y), NSSize::new(query_w, ROW_H - 2.0)),
+        );
+        query_field.setFont(Some(
+            &NSFont::fontWithName_size(&NSString::from_str("Georgia"), 13.0)
+                .unwrap_or_else(|| NSFont::systemFontOfSize_weight(13.0, 0.0)),
+        ));
+        unsafe {
+            let _: () = msg_send![&*query_field,
+                setPlaceholderString: &*NSString::from_str("Find in folder\u{2026}")];
+        }
+        set_target_action(&query_field, target, objc2::sel!(folderSearchStart:));
+        query_field.setAutoresizingMask(NSAutoresizingMaskOptions::ViewWidthSizable | pinned_top);
+        content.addSubview(&query_field);
+
+        let folder_btn = make_btn(
+            mtm,
+            "Choose Folder\u{2026}",
+            NSRect::new(
+                NSPoint::new(PAD + query_w + GAP, row_y),
+                NSSize::new(FOLDER_W, ROW_H),
+            ),
+            objc2::sel!(folderSearchChooseFolder:),
+            target,
+            &sys_12,
+        );
+        folder_btn.setAutoresizingMask(NSAutoresizingMaskOptions::ViewMinXMargin | pinned_top);
+        content.addSubview(&folder_btn);
+
+        let stop_btn = make_btn(
+            mtm,
+            "Stop",
+            NSRect::new(
+                NSPoint::new(WIN_W - PAD - STOP_W, row_y),
+                NSSize::new(STOP_W, ROW_H),
+            ),
+            objc2::sel!(folderSearchStop:),
+            target,
+            &sys_12,
+        );
+        stop_btn.setEnabled(false);
+        stop_btn.setAutoresizingMask(NSAutoresizingMaskOptions::ViewMinXMargin | pinned_top);
+        content.addSubview(&stop_btn);
+
+        // ── Status line ───────────────────────────────────────────────────
+        let status_y = row_y - GAP - STATUS_H;
+        let status_label = NSTextField::initWithFrame(
+            NSTextField::alloc(mtm),
+            NSRect::new(NSPoint::new(PAD, status_y), NSSize::new(WIN_W - 2.0 * PAD, STATUS_H)),
+        );
+        status_label.setEditable(false);
+        status_label.setSelectable(false);
+        status_label.setBordered(false);
+        status_label.setDrawsBackground(false);
+        status_label.setFont(Some(&NSFont::systemFontOfSize_weight(11.0, 0.0)));
+        status_label.setTextColor(Some(&NSColor::secondaryLabelColor()));
+        status_label.setStringValue(&NSString::from_str("No folder chosen"));
+        status_label.setAutoresizingMask(NSAutoresizingMaskOptions::ViewWidthSizable | pinned_top);
+        content.addSubview(&status_label);
+
+        // ── Results ───────────────────────────────────────────────────────
+        let scroll_frame = NSRect::new(
+            NSPoint::new(0.0, 0.0),
+            NSSize::new(WIN_W, status_y - GAP / 2.0),
+        );
+        let scroll = NSScrollView::initWithFrame(NSScrollView::alloc(mtm), scroll_frame);
+        scroll.setHasVerticalScroller(true);
+        scroll.setAutohidesScrollers(true);
+        scroll.setAutoresizingMask(
+            NSAutoresizingMaskOptions::ViewWidthSizable | NSAutoresizingMaskOptions::ViewHeightSizable,
+        );
+        let results = NSTextView::initWithFrame(
+            NSTextView::alloc(mtm),
+            NSRect::new(NSPoint::new(0.0, 0.0), scroll.contentSize()),
+        );
+        results.setEditable(false);
+        results.setSelectable(true);
+        results.setRichText(true);
+        results.setTextContainerInset(NSSize::new(PAD - 4.0, 6.0));
+        results.setAutoresizingMask(NSAutoresizingMaskOptions::ViewWidthSizable);
+        unsafe {
+            let _: () = msg_send![&*results, setDelegate: target];
+            // Hits keep their own colors instead of the default link styling.
+            let cls = objc2::runtime::AnyClass::get(c"NSDictionary").unwrap();
+            let empty: *mut AnyObject = msg_send![cls, new];
+            let _: () = msg_send![&*results, setLinkTextAttributes: empty];
+        }
+        scroll.setDocumentView(Some(&results));
+        content.addSubview(&scroll);
+
+        Self { window, query_field, folder_btn, stop_btn, status_label, results }
+    }
+
+    /// Bring the window forward and focus the query field.
+    pub fn show(&self) {
+        if !self.window.isVisible() {
+            self.window.center();
+        }
+        self.window.makeKeyAndOrderFront(None);
+        unsafe {
+            let _: () = msg_send![&*self.window, makeFirstResponder: &*self.query_field];
+            let _: () = msg_send![&*self.query_field, selectAll: std::ptr::null::<AnyObject>()];
+        }
+    }
+
+    /// The text in the query field.
+    pub fn query(&self) -> String {
+        self.query_field.stringValue().to_string()
+    }
+
+    /// Show the chosen folder's name on the folder button (full path in the tooltip).
+    pub fn set_folder(&self, root: &Path) {
+        let name = root.file_name().map_or_else(|| root.display().to_string(), |n| n.to_string_lossy().into_owned());
+        self.folder_btn.setTitle(&NSString::from_str(&format!("{name} \u{25BE}")));
+        let tip = NSString::from_str(&root.display().to_string());
+        unsafe { let _: () = msg_send![&*self.folder_btn, setToolTip: &*tip]; }
+    }
+
+    /// Enable the Stop button while a search runs.
+    pub fn set_searching(&self, searching: bool) {
+        self.stop_btn.setEnabled(searching);
+    }
+
+    /// Replace the status line under the query field.
+    pub fn set_status(&self, status: &str) {
+        self.status_label.setStringValue(&NSString::from_str(status));
+    }
+
+    /// Remove every result.
+    pub fn clear_results(&self) {
+        if let Some(storage) = unsafe { self.results.textStorage() } {
+            let all = NSRange { location: 0, length: storage.length() };
+            storage.replaceCharactersInRange_withString(all, &NSString::from_str(""));
+        }
+    }
+
+    /// Append one file's hits under a header with its path relative to
+    /// `root`. `first_hit` is the link number of the file's first hit.
+    pub fn append_file(&self, root: &Path, result: &FileResult, first_hit: usize) {
+        let rel = result.path.strip_prefix(root).unwrap_or(&result.path);
+        let bold = NSFont::systemFontOfSize_weight(12.0, 0.4);
+        let regular = NSFont::systemFontOfSize_weight(12.0, 0.0);
+        let digits = NSFont::monospacedDigitSystemFontOfSize_weight(11.0, 0.0);
+
+        let header = format!("{}  ({})\n", rel.display(), result.hits.len());
+        self.append(&header, &bold, &NSColor::labelColor(), None);
+        for (i, hit) in result.hits.iter().enumerate() {
+            let link = NSURL::URLWithString(&NSString::from_str(&format!("{HIT_SCHEME}{}", first_hit + i)));
+            let link = link.as_deref();
+            self.append(&format!("{:>6}  ", hit.line + 1), &digits, &NSColor::secondaryLabelColor(), link);
+            let (s, e) = hit.preview_range;
+            let p = &hit.preview;
+            self.append(&p[..s], &regular, &NSColor::labelColor(), link);
+            self.append(&p[s..e], &bold, &NSColor::controlAccentColor(), link);
+            self.append(&p[e..], &regular, &NSColor::labelColor(), link);
+            self.append("\n", &regular, &NSColor::labelColor(), None);
+        }
+        self.append("\n", &regular, &NSColor::labelColor(), None);
+    }
+
+    /// Whether `view` is the results text view.
+    pub fn is_results_view(&self, view: &AnyObject) -> bool {
+        std::ptr::eq(view, Retained::as_ptr(&self.results).cast())
+    }
+
+    // ── Private helpers ───────────────────────────────────────────────────
+
+    fn append(&self, text: &str, font: &NSFont, color: &NSColor, link: Option<&NSURL>) {
+        if text.is_empty() { return; }
+        let Some(storage) = (unsafe { self.results.textStorage() }) else { return };
+        let location = storage.length();
+        let end = NSRange { location, length: 0 };
+        storage.replaceCharactersInRange_withString(end, &NSString::from_str(text));
+        let range = NSRange { location, length: text.encode_utf16().count() };
+        unsafe {
+            storage.addAttribute_value_range(NSFontAttributeName, font, range);
+            storage.addAttribute_value_range(NSForegroundColorAttributeName, color, range);
+            if let Some(url) = link {
+                storage.addAttribute_value_range(NSLinkAttributeName, url, range);
+            }
+        }
+    }
+}
+
+/// Create a rounded push button.
+fn make_btn(
+    mtm: MainThreadMarker,
+    title: &str,
+    frame: NSRect,
+    action: Sel,
+    target: &AnyObject,
+    font: &NSFont,
+) -> Retained<NSButton> {
+    let btn = NSButton::initWithFrame(NSButton::alloc(mtm), frame);
+    btn.setTitle(&NSString::from_str(title));
+    btn.setBezelStyle(NSBezelStyle::Rounded);
+    btn.setFont(Some(font));
+    unsafe {
+        NSControl::setTarget(&btn, Some(target));
+        NSControl::setAction(&btn, Some(action));
+    }
+    btn
+}
+
+/// Set target and action on an NSTextField (fires on Return).
+fn set_target_action(field: &NSTextField, target: &AnyObject, action: Sel) {
+    unsafe {
+        NSControl::setTarget(field, Some(target));
+        NSControl::setAction(field, Some(action));
+    }
+}
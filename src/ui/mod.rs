@@ -9,4 +9,6 @@ pub mod tab_bar;
 pub mod path_bar;
 pub mod find_bar;
 pub mod welcome_overlay;
+pub mod folder_search_panel;
+pub mod conflict_banner;
 pub use find_bar::FindBar;
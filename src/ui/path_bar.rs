@@ -1,5 +1,6 @@
 //! Thin status bar at the bottom of the window showing the current file path,
-//! live word/character count (Editor mode only), and file metadata.
+//! task progress, live word/character count (Editor mode only), and file
+//! metadata.
 
 use std::path::Path;
 
@@ -21,10 +22,13 @@ const INFO_W: f64 = 164.0;
 const INFO_PAD: f64 = 8.0;
 /// Width of the word/char count field (visible in Editor mode only).
 const WORD_W: f64 = 160.0;
+/// Width of the task progress field (visible when the document has tasks).
+const TASKS_W: f64 = 170.0;
 
 pub struct PathBar {
     container: Retained<NSView>,
     field: Retained<NSTextField>,
+    tasks_field: Retained<NSTextField>,
     word_field: Retained<NSTextField>,
     info_field: Retained<NSTextField>,
 }
@@ -71,6 +75,21 @@ impl PathBar {
         unsafe { let _: () = msg_send![&*word_field, setAlignment: NS_TEXT_ALIGNMENT_RIGHT]; }
         unsafe { let _: () = msg_send![&*word_field, setHidden: true]; }
 
+        // Task progress field (left of the word count, hidden without tasks).
+        let tasks_field = NSTextField::initWithFrame(
+            NSTextField::alloc(mtm),
+            NSRect::new(NSPoint::new(0.0, v_off), NSSize::new(TASKS_W, FIELD_H)),
+        );
+        tasks_field.setEditable(false);
+        tasks_field.setSelectable(false);
+        tasks_field.setBordered(false);
+        tasks_field.setDrawsBackground(false);
+        tasks_field.setFont(Some(&font));
+        tasks_field.setTextColor(Some(&NSColor::tertiaryLabelColor()));
+        tasks_field.setStringValue(&NSString::from_str(""));
+        unsafe { let _: () = msg_send![&*tasks_field, setAlignment: NS_TEXT_ALIGNMENT_RIGHT]; }
+        unsafe { let _: () = msg_send![&*tasks_field, setHidden: true]; }
+
         // File info labels (right side): "UTF-8   LF   Markdown"
         let info_x = (width - INFO_W - INFO_PAD).max(0.0);
         let info_field = NSTextField::initWithFrame(
@@ -88,10 +107,11 @@ impl PathBar {
         unsafe { let _: () = msg_send![&*info_field, setAlignment: NS_TEXT_ALIGNMENT_CENTER]; }
 
         container.addSubview(&field);
+        container.addSubview(&tasks_field);
         container.addSubview(&word_field);
         container.addSubview(&info_field);
 
-        Self { container, field, word_field, info_field }
+        Self { container, field, tasks_field, word_field, info_field }
     }
 
     /// Update the displayed path and file info.
@@ -108,6 +128,16 @@ impl PathBar {
         self.word_field.setStringValue(&NSString::from_str(&label));
     }
 
+    /// Show task progress (e.g. "7/12 done"), or hide the field for `None`.
+    pub fn update_tasks(&self, label: Option<&str>) {
+        self.tasks_field.setStringValue(&NSString::from_str(label.unwrap_or("")));
+        let was_hidden: bool = unsafe { msg_send![&*self.tasks_field, isHidden] };
+        if was_hidden != label.is_none() {
+            unsafe { let _: () = msg_send![&*self.tasks_field, setHidden: label.is_none()]; }
+            self.set_width(self.container.frame().size.width);
+        }
+    }
+
     /// Show or hide the word count field and re-layout.
     pub fn set_wordcount_visible(&self, visible: bool, width: f64) {
         unsafe { let _: () = msg_send![&*self.word_field, setHidden: !visible]; }
@@ -129,7 +159,9 @@ impl PathBar {
         let word_visible: bool = unsafe { msg_send![&*self.word_field, isHidden] };
         let word_visible = !word_visible;
         let word_w = if word_visible { WORD_W } else { 0.0 };
-        let field_w = (width - LEFT_PAD - word_w - INFO_W - INFO_PAD).max(0.0);
+        let tasks_hidden: bool = unsafe { msg_send![&*self.tasks_field, isHidden] };
+        let tasks_w = if tasks_hidden { 0.0 } else { TASKS_W };
+        let field_w = (width - LEFT_PAD - tasks_w - word_w - INFO_W - INFO_PAD).max(0.0);
         self.field.setFrame(NSRect::new(
             NSPoint::new(LEFT_PAD, v_off),
             NSSize::new(field_w, FIELD_H),
@@ -141,6 +173,13 @@ impl PathBar {
                 NSSize::new(WORD_W, FIELD_H),
             ));
         }
+        if !tasks_hidden {
+            let tasks_x = (width - TASKS_W - word_w - INFO_W - INFO_PAD).max(0.0);
+            self.tasks_field.setFrame(NSRect::new(
+                NSPoint::new(tasks_x, v_off),
+                NSSize::new(TASKS_W, FIELD_H),
+            ));
+        }
         let info_x = (width - INFO_W - INFO_PAD).max(0.0);
         self.info_field.setFrame(NSRect::new(
             NSPoint::new(info_x, v_off),
@@ -204,6 +204,13 @@ const BTN_DEFS: &[ButtonDef] = &[
         tooltip: "Horizontal rule",
         fallback: "\u{2014}",
     },
+    ButtonDef {
+        kind: ButtonKind::SfSymbol("tablecells"),
+        selector: c"showTableMenu:",
+        start_new_group: false,
+        tooltip: "Table",
+        fallback: "\u{229E}",
+    },
 ];
 
 // ---------------------------------------------------------------------------
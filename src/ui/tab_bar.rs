@@ -22,6 +22,16 @@ pub fn tab_label(url: Option<&Path>, is_dirty: bool) -> String {
     }
 }
 
+/// Tab label with its find match count appended while searching all tabs,
+/// e.g. "notes.md (3)". Tabs without matches keep their plain label.
+pub fn tab_label_with_matches(label: &str, matches: usize) -> String {
+    if matches == 0 {
+        label.to_string()
+    } else {
+        format!("{} ({})", label, matches)
+    }
+}
+
 /// Full path string for the path bar.
 pub fn path_label(url: Option<&Path>) -> String {
     url.map(|p| p.display().to_string())
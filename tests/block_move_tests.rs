@@ -0,0 +1,135 @@
+use mdit::editor::block_move::{
+    list_item_range, move_block_edit, move_unit_range, section_range, selected_lines, MoveUnit,
+};
+use mdit::markdown::parser::parse;
+
+/// Apply a move to `text` with the caret at the first `|`.
+fn moved(text: &str, up: bool) -> Option<(String, usize)> {
+    let caret = text.find('|').unwrap();
+    let text = text.replacen('|', "", 1);
+    let edit = move_block_edit(&text, &parse(&text), (caret, caret), up)?;
+    let mut out = text.clone();
+    out.replace_range(edit.range.0..edit.range.1, &edit.replacement);
+    Some((out, edit.selection.0))
+}
+
+const DOC: &str = "# T\n\nintro\n\n## A\ntext a\n\n### a1\n\n## B\n\nbody b\n";
+
+// ── ranges ──────────────────────────────────────────────────────────────
+
+#[test]
+fn section_runs_to_next_heading_of_same_level() {
+    let spans = parse(DOC);
+    let a = DOC.find("## A").unwrap();
+    let ((start, end), level) = section_range(DOC, &spans, a + 3).unwrap();
+    assert_eq!(level, 2);
+    assert_eq!(&DOC[start..end], "## A\ntext a\n\n### a1");
+}
+
+#[test]
+fn last_section_runs_to_end_without_trailing_blank() {
+    let spans = parse(DOC);
+    let b = DOC.find("## B").unwrap();
+    let ((start, end), _) = section_range(DOC, &spans, b).unwrap();
+    assert_eq!(&DOC[start..end], "## B\n\nbody b");
+}
+
+#[test]
+fn section_range_needs_caret_on_heading() {
+    let spans = parse(DOC);
+    assert_eq!(section_range(DOC, &spans, DOC.find("text a").unwrap()), None);
+}
+
+#[test]
+fn list_item_range_includes_nested_items() {
+    let text = "- one\n- two\n  - nested\n- three\n";
+    let (start, end) = list_item_range(text, &parse(text), 8).unwrap();
+    assert_eq!(&text[start..end], "- two\n  - nested");
+}
+
+#[test]
+fn caret_in_nested_indent_selects_nested_item() {
+    let text = "- two\n  - nested\n  - other\n";
+    let spans = parse(text);
+    let (unit, (start, end)) = move_unit_range(text, &spans, (7, 7));
+    assert_eq!(unit, MoveUnit::ListItem);
+    assert_eq!(&text[start..end], "  - nested");
+}
+
+#[test]
+fn selection_ending_at_line_start_excludes_that_line() {
+    let text = "a\nb\nc\n";
+    assert_eq!(selected_lines(text, (0, 4)), (0, 3));
+}
+
+// ── moving ──────────────────────────────────────────────────────────────
+
+#[test]
+fn line_moves_down() {
+    assert_eq!(moved("o|ne\ntwo\nthree", false), Some(("two\none\nthree".into(), 5)));
+}
+
+#[test]
+fn last_line_without_newline_moves_up() {
+    assert_eq!(moved("one\ntw|o", true), Some(("two\none".into(), 2)));
+}
+
+#[test]
+fn line_at_edge_does_not_move() {
+    assert_eq!(moved("o|ne\ntwo", true), None);
+    assert_eq!(moved("one\nt|wo", false), None);
+}
+
+#[test]
+fn selected_lines_move_together() {
+    let text = "a\nb\nc\nd";
+    let edit = move_block_edit(text, &parse(text), (2, 5), false).unwrap();
+    let mut out = text.to_string();
+    out.replace_range(edit.range.0..edit.range.1, &edit.replacement);
+    assert_eq!(out, "a\nd\nb\nc");
+    assert_eq!(edit.selection, (4, 7));
+}
+
+#[test]
+fn list_item_moves_with_children() {
+    assert_eq!(
+        moved("- one\n- t|wo\n  - nested\n- three\n", true),
+        Some(("- two\n  - nested\n- one\n- three\n".into(), 3))
+    );
+}
+
+#[test]
+fn list_item_keeps_blank_line_gap() {
+    assert_eq!(
+        moved("- o|ne\n\n- two\n", false).map(|m| m.0),
+        Some("- two\n\n- one\n".into())
+    );
+}
+
+#[test]
+fn nested_item_stays_in_its_list() {
+    assert_eq!(moved("- one\n  - a|\n- two\n", false), None);
+}
+
+#[test]
+fn section_moves_down_past_sibling() {
+    let caret = DOC.find("## A").unwrap() + 1;
+    let text = format!("{}|{}", &DOC[..caret], &DOC[caret..]);
+    let (out, sel) = moved(&text, false).unwrap();
+    assert_eq!(out, "# T\n\nintro\n\n## B\n\nbody b\n\n## A\ntext a\n\n### a1\n");
+    assert_eq!(&out[sel - 1..sel + 3], "## A");
+}
+
+#[test]
+fn section_moves_up_past_sibling() {
+    let text = DOC.replace("## B", "## |B");
+    let (out, _) = moved(&text, true).unwrap();
+    assert_eq!(out, "# T\n\nintro\n\n## B\n\nbody b\n\n## A\ntext a\n\n### a1\n");
+}
+
+#[test]
+fn section_does_not_leave_its_parent() {
+    let text = DOC.replace("### a1", "### a|1");
+    assert_eq!(moved(&text, true), None);
+    assert_eq!(moved(&text, false), None);
+}
@@ -1,4 +1,4 @@
-use mdit::editor::cursor_tracker::find_containing_span;
+use mdit::editor::cursor_tracker::{find_containing_span, find_containing_span_where};
 use mdit::markdown::parser::{parse, NodeKind};
 
 #[test]
@@ -36,3 +36,13 @@ fn cursor_in_italic_finds_emph() {
     assert!(result.is_some());
     assert_eq!(result.unwrap().kind, NodeKind::Emph);
 }
+
+#[test]
+fn filtered_lookup_finds_the_enclosing_kind() {
+    let text = "**a _b_ c**";
+    let spans = parse(text);
+    assert_eq!(find_containing_span(&spans, 5).unwrap().kind, NodeKind::Emph);
+    let strong = find_containing_span_where(&spans, 5, &|k| *k == NodeKind::Strong).unwrap();
+    assert_eq!(strong.source_range, (0, 11));
+    assert!(find_containing_span_where(&spans, 5, &|k| *k == NodeKind::Code).is_none());
+}
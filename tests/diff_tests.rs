@@ -0,0 +1,67 @@
+use mdit::editor::diff::{line_diff, render_diff, DiffLine};
+
+fn rebuild(diff: &[DiffLine], keep_added: bool) -> String {
+    diff.iter()
+        .filter_map(|l| match *l {
+            DiffLine::Same(t) => Some(t),
+            DiffLine::Added(t) if keep_added => Some(t),
+            DiffLine::Removed(t) if !keep_added => Some(t),
+            _ => None,
+        })
+        .collect()
+}
+
+// ── line_diff ───────────────────────────────────────────────────────────
+
+#[test]
+fn identical_texts_are_all_same() {
+    let diff = line_diff("a\nb\n", "a\nb\n");
+    assert_eq!(diff, [DiffLine::Same("a\n"), DiffLine::Same("b\n")]);
+}
+
+#[test]
+fn aligns_changes_between_common_lines() {
+    let old = "# Title\none\ntwo\nthree\nfour\n";
+    let new = "# Title\none\n2\nthree\nfour\nfive\n";
+    assert_eq!(
+        line_diff(old, new),
+        [
+            DiffLine::Same("# Title\n"),
+            DiffLine::Same("one\n"),
+            DiffLine::Removed("two\n"),
+            DiffLine::Added("2\n"),
+            DiffLine::Same("three\n"),
+            DiffLine::Same("four\n"),
+            DiffLine::Added("five\n"),
+        ]
+    );
+}
+
+#[test]
+fn both_sides_can_be_rebuilt() {
+    let old = "a\nb\nc\nd\ne";
+    let new = "b\nx\nc\ne\nf";
+    let diff = line_diff(old, new);
+    assert_eq!(rebuild(&diff, false), old);
+    assert_eq!(rebuild(&diff, true), new);
+}
+
+#[test]
+fn empty_sides() {
+    assert_eq!(line_diff("", "a\n"), [DiffLine::Added("a\n")]);
+    assert_eq!(line_diff("a\n", ""), [DiffLine::Removed("a\n")]);
+    assert!(line_diff("", "").is_empty());
+}
+
+// ── render_diff ─────────────────────────────────────────────────────────
+
+#[test]
+fn render_shows_context_and_elides_the_rest() {
+    let old: String = (1..=10).map(|n| format!("line {n}\n")).collect();
+    let new = old.replace("line 5\n", "line five\n");
+    assert_eq!(
+        render_diff(&line_diff(&old, &new), 1),
+        "\u{2026}\n  line 4\n- line 5\n+ line five\n  line 6\n\u{2026}\n"
+    );
+    assert_eq!(render_diff(&line_diff(&old, &old), 3), "");
+}
@@ -0,0 +1,58 @@
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use mdit::editor::file_watcher::FileWatcher;
+
+/// Poll until something changes or two seconds pass.
+fn wait_for_changes(watcher: &FileWatcher) -> Vec<PathBuf> {
+    let deadline = Instant::now() + Duration::from_secs(2);
+    loop {
+        let changed = watcher.changed();
+        if !changed.is_empty() || Instant::now() > deadline {
+            return changed;
+        }
+        std::thread::sleep(Duration::from_millis(20));
+    }
+}
+
+#[test]
+fn reports_writes_to_watched_files_only() {
+    let dir = tempfile::tempdir().unwrap();
+    let (notes, other) = (dir.path().join("notes.md"), dir.path().join("other.md"));
+    std::fs::write(&notes, "one").unwrap();
+    std::fs::write(&other, "one").unwrap();
+
+    let mut watcher = FileWatcher::new().unwrap();
+    watcher.set_files([notes.as_path()]);
+    std::fs::write(&other, "two").unwrap();
+    std::fs::write(&notes, "two").unwrap();
+    assert_eq!(wait_for_changes(&watcher), [notes]);
+}
+
+#[test]
+fn sees_files_replaced_by_rename() {
+    let dir = tempfile::tempdir().unwrap();
+    let notes = dir.path().join("notes.md");
+    std::fs::write(&notes, "one").unwrap();
+
+    let mut watcher = FileWatcher::new().unwrap();
+    watcher.set_files([notes.as_path()]);
+    let tmp = dir.path().join(".notes.md.tmp");
+    std::fs::write(&tmp, "two").unwrap();
+    std::fs::rename(&tmp, &notes).unwrap();
+    assert_eq!(wait_for_changes(&watcher), [notes]);
+}
+
+#[test]
+fn stops_reporting_files_no_longer_listed() {
+    let dir = tempfile::tempdir().unwrap();
+    let notes = dir.path().join("notes.md");
+    std::fs::write(&notes, "one").unwrap();
+
+    let mut watcher = FileWatcher::new().unwrap();
+    watcher.set_files([notes.as_path()]);
+    watcher.set_files(std::iter::empty::<&Path>());
+    std::fs::write(&notes, "two").unwrap();
+    std::thread::sleep(Duration::from_millis(200));
+    assert!(watcher.changed().is_empty());
+}
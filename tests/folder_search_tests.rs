@@ -0,0 +1,150 @@
+use std::fs;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use mdit::editor::folder_search::{
+    hit_range, markdown_files, search_folder, search_text, FolderSearch, FolderSearchEvent,
+};
+use mdit::editor::search::{SearchOptions, SearchScope, Searcher};
+
+fn searcher(query: &str) -> Searcher {
+    Searcher::new(query, SearchOptions::default()).unwrap()
+}
+
+/// Create `files` (relative path, contents) under a fresh temp directory.
+fn tree(files: &[(&str, &str)]) -> tempfile::TempDir {
+    let dir = tempfile::tempdir().unwrap();
+    for (rel, contents) in files {
+        let path = dir.path().join(rel);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, contents).unwrap();
+    }
+    dir
+}
+
+fn relative(root: &Path, path: &Path) -> String {
+    path.strip_prefix(root).unwrap().to_string_lossy().replace('\\', "/")
+}
+
+// ── walking ─────────────────────────────────────────────────────────────
+
+#[test]
+fn walk_finds_markdown_in_path_order() {
+    let dir = tree(&[
+        ("b.md", ""),
+        ("a.markdown", ""),
+        ("notes/c.MD", ""),
+        ("notes/skip.txt", ""),
+        ("image.png", ""),
+    ]);
+    let files: Vec<String> = markdown_files(dir.path()).map(|p| relative(dir.path(), &p)).collect();
+    assert_eq!(files, ["a.markdown", "b.md", "notes/c.MD"]);
+}
+
+#[test]
+fn walk_respects_gitignore_and_hidden_entries() {
+    let dir = tree(&[
+        (".gitignore", "build/\ndraft-*.md\n"),
+        ("keep.md", ""),
+        ("draft-1.md", ""),
+        ("build/out.md", ""),
+        (".hidden/secret.md", ""),
+        ("docs/.notes.md", ""),
+        ("docs/guide.md", ""),
+    ]);
+    let files: Vec<String> = markdown_files(dir.path()).map(|p| relative(dir.path(), &p)).collect();
+    assert_eq!(files, ["docs/guide.md", "keep.md"]);
+}
+
+// ── hits ────────────────────────────────────────────────────────────────
+
+#[test]
+fn hits_carry_line_columns_and_preview() {
+    let text = "# Title\n\n   indented todo here\ntodo: one, todo: two\n";
+    let hits = search_text(&searcher("todo"), text);
+    let lines: Vec<(usize, (usize, usize))> = hits.iter().map(|h| (h.line, h.columns)).collect();
+    assert_eq!(lines, [(2, (12, 16)), (3, (0, 4)), (3, (11, 15))]);
+    assert_eq!(hits[0].preview, "indented todo here");
+    assert_eq!(&hits[0].preview[hits[0].preview_range.0..hits[0].preview_range.1], "todo");
+}
+
+#[test]
+fn long_lines_are_shortened_around_the_match() {
+    let text = format!("{}needle{}", "x".repeat(100), "y".repeat(200));
+    let hit = &search_text(&searcher("needle"), &text)[0];
+    assert!(hit.preview.starts_with('\u{2026}') && hit.preview.ends_with('\u{2026}'));
+    assert_eq!(&hit.preview[hit.preview_range.0..hit.preview_range.1], "needle");
+    assert_eq!(hit.preview.chars().count(), 122);
+}
+
+#[test]
+fn hits_respect_the_search_scope() {
+    let opts = SearchOptions { scope: SearchScope::Headings, ..SearchOptions::default() };
+    let searcher = Searcher::new("api", opts).unwrap();
+    let hits = search_text(&searcher, "# api\n\napi\n");
+    assert_eq!(hits.len(), 1);
+    assert_eq!(hits[0].line, 0);
+}
+
+#[test]
+fn crlf_line_ends_clamp_the_hit_to_the_line() {
+    let regex = SearchOptions { regex: true, ..SearchOptions::default() };
+    let hits = search_text(&Searcher::new("\\n", regex).unwrap(), "a\r\nb\r\n");
+    let lines: Vec<(usize, (usize, usize))> = hits.iter().map(|h| (h.line, h.columns)).collect();
+    assert_eq!(lines, [(0, (1, 1)), (1, (1, 1))]);
+    assert_eq!(hits[0].preview, "a");
+}
+
+#[test]
+fn hit_range_clamps_to_the_current_text() {
+    let hits = search_text(&searcher("née"), "a\nla née\n");
+    assert_eq!(hit_range("a\nla née\n", &hits[0]), (5, 9));
+    // The line got shorter since the search ran.
+    assert_eq!(hit_range("a\nla n\n", &hits[0]), (5, 6));
+    // The line is gone.
+    assert_eq!(hit_range("a", &hits[0]), (1, 1));
+}
+
+// ── background search ───────────────────────────────────────────────────
+
+#[test]
+fn background_search_streams_files_then_finishes() {
+    let dir = tree(&[("a.md", "alpha\nbeta\n"), ("b.md", "gamma\n"), ("c/d.md", "beta beta\n")]);
+    let search = FolderSearch::start(dir.path().to_path_buf(), searcher("beta"));
+    let mut events = Vec::new();
+    while let Some(event) = search.recv() {
+        events.push(event);
+    }
+    let FolderSearchEvent::File(first) = &events[0] else { panic!("expected a file") };
+    assert_eq!(relative(dir.path(), &first.path), "a.md");
+    assert_eq!(first.hits.len(), 1);
+    let FolderSearchEvent::File(second) = &events[1] else { panic!("expected a file") };
+    assert_eq!(relative(dir.path(), &second.path), "c/d.md");
+    assert_eq!(second.hits.len(), 2);
+    assert_eq!(events[2], FolderSearchEvent::Finished { files: 3, cancelled: false });
+}
+
+#[test]
+fn polling_ends_with_one_finished_event() {
+    let dir = tree(&[("a.md", "x")]);
+    let search = FolderSearch::start(dir.path().to_path_buf(), searcher("x"));
+    let mut events = Vec::new();
+    while !matches!(events.last(), Some(FolderSearchEvent::Finished { .. })) {
+        events.extend(search.poll());
+    }
+    std::thread::sleep(std::time::Duration::from_millis(20));
+    assert!(search.poll().is_empty());
+    assert_eq!(events.len(), 2);
+}
+
+#[test]
+fn cancelling_stops_before_the_next_file() {
+    let dir = tree(&[("a.md", "x"), ("b.md", "x"), ("c.md", "x")]);
+    let cancel = AtomicBool::new(false);
+    let mut found = Vec::new();
+    let files = search_folder(dir.path(), &searcher("x"), &cancel, |result| {
+        found.push(result.path);
+        cancel.store(true, Ordering::Relaxed);
+    });
+    assert_eq!((files, found.len()), (1, 1));
+}
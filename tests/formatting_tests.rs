@@ -1,7 +1,14 @@
 use mdit::editor::formatting::{
-    compute_code_block_wrap, compute_inline_toggle, compute_link_wrap, detect_block_prefix,
-    find_surrounding_markers, peel_inline_markers, set_block_format, toggle_marker_in_layers,
-    wrap_with_layers, InlineToggleResult,
+    auto_pair_edit, clipboard_url, compute_code_block_wrap, compute_inline_toggle, compute_link_wrap, compute_list_continuation,
+    delimited_to_table, detect_block_prefix, detect_delimited, format_document, inline_toggle_edit,
+    insert_block_edit, insert_footnote_edit, pair_backspace_edit, renumber_footnotes_edit, link_destination, link_paste_edit, parse_delimited,
+    table_as_delimited, find_surrounding_markers, line_bounds, list_newline_edit,
+    parse_list_marker, peel_inline_markers, renumber_list_lines, set_block_format,
+    set_block_format_lines, shift_section_edit,
+    reflow_paragraphs_edit, shift_list_item, shift_list_item_edit, unwrap_paragraphs_edit, table_cell_at, table_command_edit, table_tab_edit,
+    toggle_marker_in_layers,
+    spans_equivalent, wrap_with_layers, ColumnAlign, FormatOptions, InlineToggleResult, ListContinuation, ListKind, MarkdownTable,
+    TableCommand,
 };
 
 // ── detect_block_prefix ──────────────────────────────────────────────────
@@ -83,6 +90,56 @@ fn preserves_trailing_newline() {
     assert_eq!(set_block_format("# Hello\n", "## "), "## Hello\n");
 }
 
+#[test]
+fn detect_ordered_number_beyond_one() {
+    assert_eq!(detect_block_prefix("12. Hello"), Some("1. "));
+}
+
+#[test]
+fn numbered_item_toggles_off_regardless_of_number() {
+    assert_eq!(set_block_format("3. Hello", "1. "), "Hello");
+}
+
+// ── set_block_format_lines ───────────────────────────────────────────────
+
+#[test]
+fn multi_line_plain_to_bullets() {
+    assert_eq!(set_block_format_lines("a\nb\nc", "- "), "- a\n- b\n- c");
+}
+
+#[test]
+fn multi_line_all_on_toggles_off() {
+    assert_eq!(set_block_format_lines("> a\n> b\n", "> "), "a\nb\n");
+}
+
+#[test]
+fn multi_line_mixed_turns_all_on() {
+    assert_eq!(set_block_format_lines("- a\nb\n# c", "- "), "- a\n- b\n- c");
+}
+
+#[test]
+fn multi_line_numbers_sequentially() {
+    assert_eq!(set_block_format_lines("a\n- b\nc", "1. "), "1. a\n2. b\n3. c");
+}
+
+#[test]
+fn multi_line_keeps_nested_indentation() {
+    assert_eq!(
+        set_block_format_lines("a\n   b\n   c\nd", "1. "),
+        "1. a\n   1. b\n   2. c\n2. d"
+    );
+}
+
+#[test]
+fn multi_line_skips_blank_lines() {
+    assert_eq!(set_block_format_lines("a\n\nb", "- "), "- a\n\n- b");
+}
+
+#[test]
+fn multi_line_normal_strips_all() {
+    assert_eq!(set_block_format_lines("# a\n- b\n2. c", ""), "a\nb\nc");
+}
+
 // ── find_surrounding_markers ─────────────────────────────────────────────
 
 #[test]
@@ -532,6 +589,158 @@ fn find_highlight_and_bold_nested() {
     assert_eq!(post, 4);
 }
 
+// ── shift_section_edit ─────────────────────────────────────────────────
+
+/// Shift the section whose heading contains `|`; returns text and caret.
+fn shift_section(text: &str, promote: bool) -> Option<(String, usize)> {
+    let pos = text.find('|').unwrap();
+    let text = text.replacen('|', "", 1);
+    let edit = shift_section_edit(&text, &mdit::markdown::parser::parse(&text), pos, promote)?;
+    let mut out = text.clone();
+    out.replace_range(edit.range.0..edit.range.1, &edit.replacement);
+    Some((out, edit.caret))
+}
+
+#[test]
+fn demote_section_shifts_nested_headings() {
+    let (out, caret) = shift_section("# T\n\n## A|b\n\n### c\n\ntext\n\n## D\n", false).unwrap();
+    assert_eq!(out, "# T\n\n### Ab\n\n#### c\n\ntext\n\n## D\n");
+    assert_eq!(&out[caret..caret + 1], "b");
+}
+
+#[test]
+fn promote_section_shifts_nested_headings() {
+    let (out, _) = shift_section("### |A\n#### b\n### C", true).unwrap();
+    assert_eq!(out, "## A\n### b\n### C");
+}
+
+#[test]
+fn section_at_level_limit_does_not_shift() {
+    assert_eq!(shift_section("# |A\n## b", true), None);
+    assert_eq!(shift_section("###### |A", false), None);
+}
+
+#[test]
+fn nested_headings_clamp_at_six() {
+    let (out, _) = shift_section("##### |A\n###### b", false).unwrap();
+    assert_eq!(out, "###### A\n###### b");
+}
+
+#[test]
+fn setext_heading_becomes_atx() {
+    let (out, _) = shift_section("Ti|tle\n=====\n\nbody", false).unwrap();
+    assert_eq!(out, "## Title\n\nbody");
+}
+
+#[test]
+fn shift_section_needs_caret_on_heading() {
+    assert_eq!(shift_section("# A\n\nbo|dy", false), None);
+}
+
+#[test]
+fn empty_heading_is_shifted() {
+    let (out, _) = shift_section("#|#\ntext", false).unwrap();
+    assert_eq!(out, "###\ntext");
+}
+
+#[test]
+fn empty_heading_with_trailing_space_is_shifted() {
+    let (out, _) = shift_section("#| ", false).unwrap();
+    assert_eq!(out, "## ");
+    let (out, _) = shift_section("#| \n\ntext", false).unwrap();
+    assert_eq!(out, "##\n\ntext");
+}
+
+#[test]
+fn sections_ending_in_non_ascii_are_shifted() {
+    let (out, caret) = shift_section("# Intro\n\n## Ré|sumé\n\ntext", true).unwrap();
+    assert_eq!(out, "# Intro\n\n# Résumé\n\ntext");
+    assert_eq!(&out[caret..], "sumé\n\ntext");
+    assert_eq!(shift_section("## |Café", true).unwrap().0, "# Café");
+    assert_eq!(shift_section("## |Café", false).unwrap().0, "### Café");
+}
+
+#[test]
+fn h1_button_replaces_h4_prefix() {
+    assert_eq!(set_block_format("#### Deep", "# "), "# Deep");
+}
+
+// ── footnotes ──────────────────────────────────────────────────────────
+
+/// Apply an edit returned for `text`; returns the new text and caret.
+fn apply_edit(text: &str, edit: &mdit::editor::formatting::TextEdit) -> (String, usize) {
+    let mut out = text.to_string();
+    out.replace_range(edit.range.0..edit.range.1, &edit.replacement);
+    (out, edit.caret)
+}
+
+fn insert_footnote(text: &str) -> (String, usize) {
+    let caret = text.find('|').unwrap();
+    let text = text.replacen('|', "", 1);
+    let spans = mdit::markdown::parser::parse(&text);
+    let edit = insert_footnote_edit(&text, &spans, (caret, caret));
+    let mut out = text.clone();
+    out.push_str(&edit.definition);
+    out.insert_str(edit.reference_at, &edit.reference);
+    (out, edit.caret)
+}
+
+#[test]
+fn first_footnote_starts_a_section() {
+    let (out, caret) = insert_footnote("Claim| here.\n");
+    assert_eq!(out, "Claim[^1] here.\n\n[^1]: \n");
+    assert_eq!(caret, out.len() - 1);
+}
+
+#[test]
+fn footnote_joins_trailing_definitions() {
+    let (out, caret) = insert_footnote("A[^1] b|.\n\n[^1]: one\n    more\n");
+    assert_eq!(out, "A[^1] b[^2].\n\n[^1]: one\n    more\n[^2]: \n");
+    assert_eq!(&out[caret - 6..caret], "[^2]: ");
+}
+
+#[test]
+fn footnote_number_skips_labels_in_use() {
+    let (out, _) = insert_footnote("|x[^3] `[^9]`\n\n[^3]: c\n\n[^4]: unused\n");
+    assert!(out.starts_with("[^5]x[^3]"), "{out}");
+    assert!(out.ends_with("[^4]: unused\n[^5]: \n"), "{out}");
+}
+
+#[test]
+fn footnote_after_body_text_adds_blank_line() {
+    let (out, _) = insert_footnote("A[^1].\n\n[^1]: one\n\nMore text|");
+    assert_eq!(out, "A[^1].\n\n[^1]: one\n\nMore text[^2]\n\n[^2]: \n");
+}
+
+#[test]
+fn footnote_keeps_trailing_whitespace() {
+    let (out, caret) = insert_footnote("Claim|.\n\n\n");
+    assert_eq!(out, "Claim[^1].\n\n\n[^1]: \n");
+    assert_eq!(&out[caret - 6..caret], "[^1]: ");
+    let (out, _) = insert_footnote("Claim|.  ");
+    assert_eq!(out, "Claim[^1].  \n\n[^1]: \n");
+}
+
+#[test]
+fn renumber_footnotes_by_first_reference() {
+    let text = "B[^2] a[^1] again[^2] named[^n].\n\n[^1]: one\n[^2]: two\n[^n]: named\n[^7]: orphan\n";
+    let spans = mdit::markdown::parser::parse(text);
+    let edit = renumber_footnotes_edit(text, &spans, text.len()).unwrap();
+    let (out, caret) = apply_edit(text, &edit);
+    assert_eq!(
+        out,
+        "B[^1] a[^2] again[^1] named[^n].\n\n[^2]: one\n[^1]: two\n[^n]: named\n[^3]: orphan\n"
+    );
+    assert_eq!(caret, out.len());
+}
+
+#[test]
+fn renumber_in_order_is_a_no_op() {
+    let text = "a[^1] b[^2]\n\n[^1]: x\n[^2]: y\n";
+    let spans = mdit::markdown::parser::parse(text);
+    assert_eq!(renumber_footnotes_edit(text, &spans, 0), None);
+}
+
 // ── compute_link_wrap ──────────────────────────────────────────────────
 
 #[test]
@@ -544,6 +753,56 @@ fn link_wrap_empty_selection() {
     assert_eq!(compute_link_wrap("", "[", "]()"), "[]()");
 }
 
+// ── link paste ─────────────────────────────────────────────────────────
+
+#[test]
+fn clipboard_url_accepts_schemes_and_mailto() {
+    assert_eq!(clipboard_url("  https://a.io/x?y=1\n"), Some("https://a.io/x?y=1"));
+    assert_eq!(clipboard_url("mailto:me@x.org"), Some("mailto:me@x.org"));
+    assert_eq!(clipboard_url("obsidian://open?vault=a"), Some("obsidian://open?vault=a"));
+}
+
+#[test]
+fn clipboard_url_rejects_prose() {
+    assert_eq!(clipboard_url("see https://a.io"), None);
+    assert_eq!(clipboard_url("a.io"), None);
+    assert_eq!(clipboard_url("https://"), None);
+}
+
+#[test]
+fn link_destination_brackets_unbalanced_parens() {
+    assert_eq!(link_destination("https://w.org/A_(b)"), "https://w.org/A_(b)");
+    assert_eq!(link_destination("https://w.org/a)"), "<https://w.org/a)>");
+}
+
+#[test]
+fn paste_url_onto_selection_makes_link() {
+    let text = "read the docs today";
+    let edit = link_paste_edit(text, (5, 13), "https://a.io").unwrap();
+    assert_eq!(edit.range, (5, 13));
+    assert_eq!(edit.replacement, "[the docs](https://a.io)");
+    assert_eq!(edit.caret, 5 + edit.replacement.len());
+}
+
+#[test]
+fn paste_url_keeps_selection_edge_spaces_outside() {
+    let edit = link_paste_edit("a docs b", (1, 7), "https://a.io").unwrap();
+    assert_eq!(edit.replacement, " [docs](https://a.io) ");
+    assert_eq!(edit.caret, 1 + " [docs](https://a.io)".len());
+}
+
+#[test]
+fn paste_url_needs_selection_and_url() {
+    assert_eq!(link_paste_edit("docs", (2, 2), "https://a.io"), None);
+    assert_eq!(link_paste_edit("docs", (0, 4), "not a url"), None);
+    assert_eq!(link_paste_edit("a\nb", (0, 3), "https://a.io"), None);
+}
+
+#[test]
+fn paste_url_onto_url_replaces_it() {
+    assert_eq!(link_paste_edit("https://old.io", (0, 14), "https://new.io"), None);
+}
+
 // ── compute_code_block_wrap ────────────────────────────────────────────
 
 #[test]
@@ -625,3 +884,833 @@ fn switch_bullet_to_heading() {
 fn switch_heading_to_bullet() {
     assert_eq!(set_block_format("# Hello", "- "), "- Hello");
 }
+
+// ── line_bounds ──────────────────────────────────────────────────────────
+
+#[test]
+fn line_bounds_middle_line() {
+    let text = "one\ntwo\nthree";
+    assert_eq!(line_bounds(text, 5), (4, 7));
+}
+
+#[test]
+fn line_bounds_at_end_of_text() {
+    let text = "one\ntwo";
+    assert_eq!(line_bounds(text, text.len()), (4, 7));
+}
+
+#[test]
+fn line_bounds_inside_a_multibyte_char() {
+    let text = "a\ncaf\u{e9}\nz";
+    assert_eq!(line_bounds(text, 6), (2, 7));
+}
+
+// ── parse_list_marker ────────────────────────────────────────────────────
+
+#[test]
+fn parse_bullet_marker() {
+    let m = parse_list_marker("- item").unwrap();
+    assert_eq!(m.kind, ListKind::Bullet('-'));
+    assert_eq!(m.content_start, 2);
+}
+
+#[test]
+fn parse_ordered_marker_with_indent() {
+    let m = parse_list_marker("   12) item").unwrap();
+    assert_eq!(m.indent, 3);
+    assert_eq!(m.kind, ListKind::Ordered { number: 12, delimiter: ')' });
+    assert_eq!(m.content_start, 7);
+}
+
+#[test]
+fn parse_task_marker() {
+    let m = parse_list_marker("- [x] done").unwrap();
+    assert_eq!(m.kind, ListKind::Task { bullet: '-', checked: true });
+    assert_eq!(m.content_start, 6);
+}
+
+#[test]
+fn parse_quote_marker() {
+    let m = parse_list_marker("> quoted").unwrap();
+    assert_eq!(m.kind, ListKind::Quote);
+    assert_eq!(m.content_start, 2);
+}
+
+#[test]
+fn parse_rejects_emphasis_and_plain_text() {
+    assert_eq!(parse_list_marker("**bold**"), None);
+    assert_eq!(parse_list_marker("2024 was a year"), None);
+    assert_eq!(parse_list_marker("plain"), None);
+}
+
+#[test]
+fn parse_rejects_thematic_breaks() {
+    assert_eq!(parse_list_marker("* * *"), None);
+    assert_eq!(parse_list_marker("- - -"), None);
+    assert_eq!(parse_list_marker("  *  *\t*  *"), None);
+    assert_eq!(compute_list_continuation("* * *", 5), None);
+    assert_eq!(parse_list_marker("- -").unwrap().kind, ListKind::Bullet('-'));
+    assert_eq!(parse_list_marker("* - *").unwrap().kind, ListKind::Bullet('*'));
+}
+
+// ── compute_list_continuation ────────────────────────────────────────────
+
+#[test]
+fn continue_bullet_list() {
+    assert_eq!(
+        compute_list_continuation("- item", 6),
+        Some(ListContinuation::Continue("\n- ".to_string()))
+    );
+}
+
+#[test]
+fn continue_ordered_list_increments() {
+    assert_eq!(
+        compute_list_continuation("  9. item", 9),
+        Some(ListContinuation::Continue("\n  10. ".to_string()))
+    );
+}
+
+#[test]
+fn continue_task_list_unchecked() {
+    assert_eq!(
+        compute_list_continuation("- [x] done", 10),
+        Some(ListContinuation::Continue("\n- [ ] ".to_string()))
+    );
+}
+
+#[test]
+fn continue_blockquote() {
+    assert_eq!(
+        compute_list_continuation("> quote", 7),
+        Some(ListContinuation::Continue("\n> ".to_string()))
+    );
+}
+
+#[test]
+fn empty_item_ends_list() {
+    assert_eq!(compute_list_continuation("- ", 2), Some(ListContinuation::End));
+    assert_eq!(compute_list_continuation("- [ ] ", 6), Some(ListContinuation::End));
+}
+
+#[test]
+fn caret_inside_marker_is_plain_newline() {
+    assert_eq!(compute_list_continuation("- item", 0), None);
+}
+
+#[test]
+fn non_list_line_is_plain_newline() {
+    assert_eq!(compute_list_continuation("Hello", 5), None);
+}
+
+// ── list_newline_edit ────────────────────────────────────────────────────
+
+fn apply(text: &str, edit: &mdit::editor::formatting::TextEdit) -> String {
+    format!("{}{}{}", &text[..edit.range.0], edit.replacement, &text[edit.range.1..])
+}
+
+#[test]
+fn newline_renumbers_following_items() {
+    let text = "1. a\n2. b\n3. c";
+    let edit = list_newline_edit(text, 4).unwrap();
+    let result = apply(text, &edit);
+    assert_eq!(result, "1. a\n2. \n3. b\n4. c");
+    assert_eq!(edit.caret, 8);
+}
+
+#[test]
+fn newline_on_empty_item_removes_marker() {
+    let text = "- a\n- ";
+    let edit = list_newline_edit(text, text.len()).unwrap();
+    assert_eq!(apply(text, &edit), "- a\n");
+    assert_eq!(edit.caret, 4);
+}
+
+#[test]
+fn newline_splits_item_text() {
+    let text = "- hello world";
+    let edit = list_newline_edit(text, 7).unwrap();
+    assert_eq!(apply(text, &edit), "- hello\n-  world");
+}
+
+// ── renumber_list_lines / shift_list_item ────────────────────────────────
+
+fn lines(text: &str) -> Vec<String> {
+    text.split('\n').map(str::to_string).collect()
+}
+
+#[test]
+fn renumber_keeps_start_number() {
+    let result = renumber_list_lines(&lines("3. a\n3. b\n7. c"));
+    assert_eq!(result, lines("3. a\n4. b\n5. c"));
+}
+
+#[test]
+fn renumber_nested_levels_independently() {
+    let result = renumber_list_lines(&lines("1. a\n   5. x\n   5. y\n1. b"));
+    assert_eq!(result, lines("1. a\n   1. x\n   2. y\n2. b"));
+}
+
+#[test]
+fn indent_nests_under_previous_sibling() {
+    let mut l = lines("1. a\n2. b\n3. c");
+    assert!(shift_list_item(&mut l, 1, false));
+    assert_eq!(l, lines("1. a\n   1. b\n2. c"));
+}
+
+#[test]
+fn indent_first_item_is_refused() {
+    let mut l = lines("- a\n- b");
+    assert!(!shift_list_item(&mut l, 0, false));
+    assert_eq!(l, lines("- a\n- b"));
+}
+
+#[test]
+fn outdent_moves_children_along() {
+    let mut l = lines("- a\n  - b\n    - c\n- d");
+    assert!(shift_list_item(&mut l, 1, true));
+    assert_eq!(l, lines("- a\n- b\n  - c\n- d"));
+}
+
+#[test]
+fn outdent_top_level_is_refused() {
+    let mut l = lines("- a");
+    assert!(!shift_list_item(&mut l, 0, true));
+}
+
+#[test]
+fn shift_edit_keeps_caret_in_text() {
+    let text = "- a\n- b";
+    let edit = shift_list_item_edit(text, 7, false).unwrap();
+    assert_eq!(apply(text, &edit), "- a\n  - b");
+    assert_eq!(edit.caret, 9);
+}
+
+// ── table_command_edit ───────────────────────────────────────────────────
+
+const TABLE: &str = "| a | b |\n|---|--:|\n| 1 | 2 |\n";
+
+fn run_table(text: &str, caret: usize, cmd: TableCommand) -> (String, usize) {
+    let edit = table_command_edit(text, caret, cmd).expect("table edit");
+    (apply(text, &edit), edit.caret)
+}
+
+#[test]
+fn table_format_pads_and_keeps_alignment() {
+    let text = "|a|long header|\n|:-:|-:|\n|x|y|";
+    let (out, _) = run_table(text, 1, TableCommand::Format);
+    assert_eq!(
+        out,
+        "|  a  | long header |\n| :-: | ----------: |\n|  x  |           y |"
+    );
+}
+
+#[test]
+fn table_insert_row_below_moves_caret_to_new_row() {
+    let (out, caret) = run_table(TABLE, 20, TableCommand::InsertRowBelow);
+    assert_eq!(out, "| a   |   b |\n| --- | --: |\n| 1   |   2 |\n|     |     |\n");
+    assert_eq!(caret, 44);
+}
+
+#[test]
+fn table_insert_row_above_header_is_refused() {
+    assert!(table_command_edit(TABLE, 2, TableCommand::InsertRowAbove).is_none());
+}
+
+#[test]
+fn table_delete_row() {
+    let (out, _) = run_table(TABLE, 20, TableCommand::DeleteRow);
+    assert_eq!(out, "| a   |   b |\n| --- | --: |\n");
+}
+
+#[test]
+fn table_insert_and_delete_column() {
+    let (out, caret) = run_table(TABLE, 2, TableCommand::InsertColumnRight);
+    assert_eq!(out, "| a   |     |   b |\n| --- | --- | --: |\n| 1   |     |   2 |\n");
+    assert_eq!(caret, 8);
+    let (back, _) = run_table(&out, caret, TableCommand::DeleteColumn);
+    assert_eq!(back, "| a   |   b |\n| --- | --: |\n| 1   |   2 |\n");
+}
+
+#[test]
+fn table_move_column_carries_alignment() {
+    let (out, caret) = run_table(TABLE, 2, TableCommand::MoveColumnRight);
+    assert_eq!(out, "|   b | a   |\n| --: | --- |\n|   2 | 1   |\n");
+    assert_eq!(caret, 8);
+    assert!(table_command_edit(TABLE, 2, TableCommand::MoveColumnLeft).is_none());
+}
+
+#[test]
+fn table_short_rows_are_padded() {
+    let text = "| a | b |\n|---|---|\n| 1 |";
+    let grid = {
+        let spans = mdit::markdown::parser::parse(text);
+        MarkdownTable::from_span(text, &spans[0]).unwrap()
+    };
+    assert_eq!(grid.rows, vec![vec!["1".to_string(), String::new()]]);
+    assert_eq!(grid.alignments, vec![ColumnAlign::None, ColumnAlign::None]);
+}
+
+#[test]
+fn table_command_last_cell_ends_in_multibyte_char() {
+    let (out, _) = run_table("| a | b |\n|---|---|\n| 1 | \u{e9}", 2, TableCommand::Format);
+    assert_eq!(out, "| a   | b   |\n| --- | --- |\n| 1   | \u{e9}   |");
+}
+
+#[test]
+fn table_command_outside_table_is_none() {
+    assert!(table_command_edit("plain text", 3, TableCommand::Format).is_none());
+}
+
+// ── table_cell_at / table_tab_edit ───────────────────────────────────────
+
+fn table_info(text: &str) -> mdit::editor::renderer::TableInfo {
+    let spans = mdit::markdown::parser::parse(text);
+    let out = mdit::editor::renderer::compute_attribute_runs(text, &spans, None, 14.0);
+    out.table_infos.into_iter().next().expect("table info")
+}
+
+#[test]
+fn cell_at_maps_caret_through_pipes() {
+    let info = table_info(TABLE);
+    assert_eq!(table_cell_at(TABLE, &info, 0), Some((0, 0)));
+    assert_eq!(table_cell_at(TABLE, &info, 2), Some((0, 0)));
+    assert_eq!(table_cell_at(TABLE, &info, 4), Some((0, 0)));
+    assert_eq!(table_cell_at(TABLE, &info, 5), Some((0, 1)));
+    assert_eq!(table_cell_at(TABLE, &info, 12), Some((0, 1)));
+    assert_eq!(table_cell_at(TABLE, &info, 26), Some((1, 1)));
+}
+
+#[test]
+fn cell_at_handles_pipeless_rows() {
+    let text = "a | b\n--|--\nc | d";
+    let info = table_info(text);
+    assert_eq!(table_cell_at(text, &info, 0), Some((0, 0)));
+    assert_eq!(table_cell_at(text, &info, 4), Some((0, 1)));
+    assert_eq!(table_cell_at(text, &info, 16), Some((1, 1)));
+}
+
+#[test]
+fn cell_at_outside_table_is_none() {
+    let text = "intro\n\n| a |\n|---|\n";
+    let info = table_info(text);
+    assert_eq!(table_cell_at(text, &info, 1), None);
+}
+
+#[test]
+fn tab_moves_to_next_cell_and_pads() {
+    let text = "|a|b|\n|-|-|\n|1|2|";
+    let info = table_info(text);
+    let edit = table_tab_edit(text, &info, 1, false).unwrap();
+    assert_eq!(apply(text, &edit), "| a   | b   |\n| --- | --- |\n| 1   | 2   |");
+    assert_eq!(edit.caret, 8);
+}
+
+#[test]
+fn tab_wraps_to_next_row() {
+    let info = table_info(TABLE);
+    let edit = table_tab_edit(TABLE, &info, 6, false).unwrap();
+    assert_eq!(&apply(TABLE, &edit)[edit.caret..edit.caret + 1], "1");
+}
+
+#[test]
+fn tab_in_last_cell_appends_row() {
+    let info = table_info(TABLE);
+    let edit = table_tab_edit(TABLE, &info, 26, false).unwrap();
+    let out = apply(TABLE, &edit);
+    assert_eq!(out, "| a   |   b |\n| --- | --: |\n| 1   |   2 |\n|     |     |\n");
+    assert_eq!(edit.caret, 44);
+}
+
+#[test]
+fn shift_tab_moves_back_and_stops_at_first_cell() {
+    let info = table_info(TABLE);
+    let edit = table_tab_edit(TABLE, &info, 20, true).unwrap();
+    assert_eq!(&apply(TABLE, &edit)[edit.caret..edit.caret + 1], "b");
+    let edit = table_tab_edit(TABLE, &info, 2, true).unwrap();
+    assert_eq!(edit.caret, 2);
+}
+
+#[test]
+fn tab_in_table_ending_in_multibyte_char() {
+    let text = "| a | b |\n|---|---|\n| 1 | \u{e9}";
+    let info = table_info(text);
+    let edit = table_tab_edit(text, &info, 2, false).unwrap();
+    assert_eq!(apply(text, &edit), "| a   | b   |\n| --- | --- |\n| 1   | \u{e9}   |");
+    assert_eq!(edit.caret, 8);
+}
+
+// ── CSV / TSV ────────────────────────────────────────────────────────────
+
+#[test]
+fn parse_delimited_handles_quotes_and_embedded_newlines() {
+    let csv = "name,note\r\n\"Smith, J\",\"said \"\"hi\"\"\nthen left\"\n";
+    assert_eq!(
+        parse_delimited(csv, ','),
+        vec![
+            vec!["name".to_string(), "note".to_string()],
+            vec!["Smith, J".to_string(), "said \"hi\"\nthen left".to_string()],
+        ]
+    );
+}
+
+#[test]
+fn detect_prefers_tabs() {
+    let (delimiter, records) = detect_delimited("a\tb,c\n1\t2\n").unwrap();
+    assert_eq!(delimiter, '\t');
+    assert_eq!(records[0], vec!["a".to_string(), "b,c".to_string()]);
+}
+
+#[test]
+fn detect_rejects_prose_and_ragged_rows() {
+    assert!(detect_delimited("Hello, world\nGoodbye, moon").is_none());
+    assert!(detect_delimited("a,b\n1,2,3").is_none());
+    assert!(detect_delimited("a,b").is_none());
+}
+
+#[test]
+fn detect_rejects_tab_indented_code() {
+    assert!(detect_delimited("\tfoo()\n\tbar()\n").is_none());
+    assert!(detect_delimited("\tlet x = 1;\n\tlet y = 2;").is_none());
+    // One empty leading cell is still a table.
+    assert!(detect_delimited("\tb\n1\t2\n").is_some());
+}
+
+#[test]
+fn delimited_to_table_escapes_pipes_and_newlines() {
+    let table = delimited_to_table("x\ty\n\"a|b\"\t\"l1\nl2\"\n").unwrap();
+    assert_eq!(table, "| x    | y        |\n| ---- | -------- |\n| a\\|b | l1<br>l2 |");
+}
+
+#[test]
+fn table_round_trips_to_csv() {
+    let text = "| x | y |\n|---|---|\n| a\\|b | l1<br>l2 |\n| c, d | e |\n";
+    assert_eq!(
+        table_as_delimited(text, 2, ',').unwrap(),
+        "x,y\na|b,\"l1\nl2\"\n\"c, d\",e\n"
+    );
+    assert_eq!(table_as_delimited(text, 2, '\t').unwrap(), "x\ty\na|b\t\"l1\nl2\"\nc, d\te\n");
+    assert!(table_as_delimited("no table", 2, ',').is_none());
+}
+
+#[test]
+fn insert_block_edit_separates_with_blank_lines() {
+    let text = "intro\nafter";
+    let edit = insert_block_edit(text, (6, 6), "| a |");
+    assert_eq!(apply(text, &edit), "intro\n\n| a |\n\nafter");
+    assert_eq!(edit.caret, 12);
+}
+
+// ── inline_toggle_edit ───────────────────────────────────────────────────
+
+/// Toggle `marker` on `text` with the selection given as a `[start, end)`
+/// pair; returns the new text and the selected slice of it.
+fn toggle(text: &str, selection: (usize, usize), marker: &str) -> (String, String) {
+    let spans = mdit::markdown::parser::parse(text);
+    let edit = inline_toggle_edit(text, &spans, selection, marker);
+    let out = apply_inline(text, &edit);
+    let selected = out[edit.selection.0..edit.selection.1].to_string();
+    (out, selected)
+}
+
+fn apply_inline(text: &str, edit: &mdit::editor::formatting::InlineToggleEdit) -> String {
+    format!("{}{}{}", &text[..edit.range.0], edit.replacement, &text[edit.range.1..])
+}
+
+#[test]
+fn toggle_wraps_plain_selection() {
+    assert_eq!(toggle("a word b", (2, 6), "**"), ("a **word** b".into(), "word".into()));
+}
+
+#[test]
+fn toggle_wrap_keeps_whitespace_outside() {
+    assert_eq!(toggle("a word b", (1, 7), "**").0, "a **word** b");
+}
+
+#[test]
+fn toggle_unbolds_middle_of_span() {
+    let text = "**long bold sentence**";
+    assert_eq!(toggle(text, (7, 11), "**"), ("**long** bold **sentence**".into(), "bold".into()));
+}
+
+#[test]
+fn toggle_caret_unbolds_around_nested_emphasis() {
+    assert_eq!(toggle("**a _b_ c**", (5, 5), "**").0, "**a** _b_ **c**");
+}
+
+#[test]
+fn toggle_caret_unbolds_only_its_word() {
+    let text = "**very bold**";
+    let edit = inline_toggle_edit(text, &mdit::markdown::parser::parse(text), (9, 9), "**");
+    let out = apply_inline(text, &edit);
+    assert_eq!(out, "**very** bold");
+    assert_eq!(&out[edit.selection.0..], "ld");
+    // At the end of a word the caret stays inside the new markers.
+    assert_eq!(toggle("a word", (6, 6), "**"), ("a **word**".into(), String::new()));
+    let edit = inline_toggle_edit("a word", &mdit::markdown::parser::parse("a word"), (6, 6), "**");
+    assert_eq!(edit.selection, (8, 8));
+}
+
+#[test]
+fn toggle_unbolds_start_of_span() {
+    let text = "**long bold sentence**";
+    assert_eq!(toggle(text, (2, 6), "**"), ("long **bold sentence**".into(), "long".into()));
+}
+
+#[test]
+fn toggle_unwraps_span_selected_with_surrounding_spaces() {
+    assert_eq!(toggle("say **bold** now", (3, 13), "**"), ("say bold now".into(), " bold ".into()));
+    assert_eq!(toggle("a `\u{65e5}\u{672c}` b", (1, 9), "`").0, "a \u{65e5}\u{672c} b");
+}
+
+#[test]
+fn toggle_unwraps_whole_span_selected_with_markers() {
+    assert_eq!(toggle("x **bold** y", (2, 10), "**"), ("x bold y".into(), "bold".into()));
+}
+
+#[test]
+fn toggle_unwraps_outer_span_from_nested_emphasis() {
+    assert_eq!(toggle("**a _b_ c**", (5, 6), "**").0, "**a** _b_ **c**");
+}
+
+#[test]
+fn toggle_extends_span_to_selection() {
+    let text = "one **two** three";
+    assert_eq!(toggle(text, (0, 17), "**"), ("**one two three**".into(), "one two three".into()));
+    assert_eq!(toggle(text, (6, 17), "**").0, "one **two three**");
+}
+
+#[test]
+fn toggle_caret_wraps_word_under_caret() {
+    let text = "hello world";
+    let spans = mdit::markdown::parser::parse(text);
+    let edit = inline_toggle_edit(text, &spans, (8, 8), "_");
+    assert_eq!(edit.replacement, "_world_");
+    assert_eq!(edit.range, (6, 11));
+    assert_eq!(edit.selection, (9, 9));
+}
+
+#[test]
+fn toggle_caret_inside_span_unwraps_it() {
+    let text = "a ~~gone~~ b";
+    let spans = mdit::markdown::parser::parse(text);
+    let edit = inline_toggle_edit(text, &spans, (6, 6), "~~");
+    assert_eq!(apply_inline(text, &edit), "a gone b");
+    assert_eq!(edit.selection, (4, 4));
+}
+
+#[test]
+fn toggle_caret_in_whitespace_inserts_empty_pair() {
+    let text = "a  b";
+    let spans = mdit::markdown::parser::parse(text);
+    let edit = inline_toggle_edit(text, &spans, (2, 2), "`");
+    assert_eq!(apply_inline(text, &edit), "a `` b");
+    assert_eq!(edit.selection, (3, 3));
+}
+
+// ── format_document ──────────────────────────────────────────────────────
+
+const MESSY: &str = "Title\n=====\nSome *emph* and __under__ and **bold**   \ntext   \n\
+* a\n* b\n   + nested\n\n3) one\n7) two\n- [ ] task\n\n|a|b|\n|:-|-:|\n|long cell|2|\n\
+> quote\n\n    code\n\nfoot[^1] and snake_case_word\n\n[^1]: note\n";
+
+fn fmt(text: &str) -> String {
+    format_document(text, &FormatOptions::default())
+}
+
+#[test]
+fn format_keeps_bare_urls_bare() {
+    let text = "See https://a.io/x_y and www.b.org or me@x.org.\n";
+    assert_eq!(format_document(text, &FormatOptions::default()), text);
+}
+
+#[test]
+fn format_produces_canonical_style() {
+    let out = fmt(MESSY);
+    assert!(out.starts_with("# Title\n\nSome _emph_ and __under__ and **bold**\\\ntext\n\n- a\n- b\n  - nested\n"));
+    assert!(out.contains("\n3) one\n4) two\n"));
+    assert!(out.contains("| a         |   b |\n| :-------- | --: |\n| long cell |   2 |\n"));
+    assert!(out.contains("[^1]: note"));
+    assert!(out.contains("snake_case_word"));
+    assert!(out.lines().all(|l| l == l.trim_end()), "no trailing whitespace");
+}
+
+#[test]
+fn format_is_idempotent() {
+    let once = fmt(MESSY);
+    assert_eq!(fmt(&once), once);
+    let stars = FormatOptions { emphasis: '*', bullet: '*' };
+    let once = format_document(MESSY, &stars);
+    assert_eq!(format_document(&once, &stars), once);
+}
+
+#[test]
+fn format_preserves_span_tree() {
+    let parse = mdit::markdown::parser::parse;
+    for text in [MESSY, "a*b*c and _x_", "- a\n\n1. b\n", "***both*** ~~s~~ ==h== $x$"] {
+        let out = fmt(text);
+        assert!(spans_equivalent(text, &parse(text), &out, &parse(&out)), "changed tree: {text:?}");
+    }
+}
+
+#[test]
+fn format_keeps_intraword_emphasis_as_stars() {
+    assert_eq!(fmt("a*b*c"), "a*b*c\n");
+}
+
+#[test]
+fn format_uses_configured_bullet() {
+    let out = format_document("- a\n- b\n", &FormatOptions { emphasis: '_', bullet: '*' });
+    assert_eq!(out, "* a\n* b\n");
+}
+
+#[test]
+fn format_keeps_front_matter() {
+    assert_eq!(fmt("---\ntitle: x\n---\n# H\n"), "---\ntitle: x\n---\n# H\n");
+    assert_eq!(fmt("---\ntags: [a]\n---\n\nSome *emph*\n"), "---\ntags: [a]\n---\n\nSome _emph_\n");
+}
+
+#[test]
+fn format_fences_code_blocks() {
+    assert_eq!(fmt("```\ncode\n```\n"), "```\ncode\n```\n");
+    assert!(fmt(MESSY).contains("```\ncode\n```\n"));
+}
+
+#[test]
+fn format_trims_blank_lines_in_lists_and_quotes() {
+    for text in ["- x\n  \n  - y\n", "- x\n\n  more\n", "> a\n> \n> b\n"] {
+        let out = fmt(text);
+        assert!(out.lines().all(|l| l == l.trim_end()), "trailing whitespace: {out:?}");
+    }
+    // Code is left alone.
+    assert_eq!(fmt("```\na  \n```\n"), "```\na  \n```\n");
+}
+
+/// Whether `a` and `b` parse to equivalent span trees.
+fn equivalent(a: &str, b: &str) -> bool {
+    let parse = mdit::markdown::parser::parse;
+    spans_equivalent(a, &parse(a), b, &parse(b))
+}
+
+#[test]
+fn changed_words_are_not_equivalent() {
+    assert!(equivalent("one two\nthree", "one  two three"));
+    assert!(!equivalent("one two three", "one three"));
+    assert!(!equivalent("*one* two", "*one* too"));
+}
+
+#[test]
+fn escapes_and_entities_compare_by_meaning() {
+    assert!(equivalent("snake\\_case &amp; 1\\*2", "snake_case & 1\\*2"));
+    assert!(!equivalent("a &amp; b", "a &amp;amp; b"));
+}
+
+#[test]
+fn hard_breaks_must_survive() {
+    assert!(equivalent("a  \nb", "a\\\nb"));
+    assert!(!equivalent("a\\\nb", "a\nb"));
+    assert!(!equivalent("a  \nb", "a b"));
+}
+
+// ── reflow / unwrap paragraphs ───────────────────────────────────────────
+
+fn reflow(text: &str, caret: usize, column: usize) -> String {
+    apply(text, &reflow_paragraphs_edit(text, (caret, caret), column).expect("reflow edit"))
+}
+
+#[test]
+fn reflow_wraps_at_column() {
+    let text = "one two three four five six seven";
+    assert_eq!(reflow(text, 0, 14), "one two three\nfour five six\nseven");
+}
+
+#[test]
+fn reflow_never_splits_code_spans_or_links() {
+    let text = "see `a b c` and [the link text](http://x.y) ok";
+    assert_eq!(reflow(text, 0, 10), "see\n`a b c`\nand\n[the link text](http://x.y)\nok");
+}
+
+#[test]
+fn reflow_keeps_list_and_quote_prefixes() {
+    let text = "> - alpha beta gamma delta\n";
+    assert_eq!(reflow(text, 5, 16), "> - alpha beta\n>   gamma delta\n");
+    let text = "1. alpha beta gamma";
+    assert_eq!(reflow(text, 4, 12), "1. alpha\n   beta\n   gamma");
+}
+
+#[test]
+fn reflow_keeps_hard_breaks() {
+    let text = "a b\\\nc d e";
+    assert_eq!(reflow(text, 0, 4), "a b\\\nc d\ne");
+    assert!(reflow_paragraphs_edit(text, (0, 0), 80).is_none());
+    let text = "a b  \nc d e";
+    assert_eq!(reflow(text, 0, 3), "a b  \nc d\ne");
+}
+
+#[test]
+fn reflow_does_not_start_line_with_block_marker() {
+    let text = "aaaa bbbb - cccc";
+    assert_eq!(reflow(text, 0, 9), "aaaa bbbb -\ncccc");
+}
+
+#[test]
+fn reflow_ignores_code_blocks_and_headings() {
+    assert!(reflow_paragraphs_edit("```\na b c d e f\n```", (5, 5), 3).is_none());
+    assert!(reflow_paragraphs_edit("# a b c d e f", (3, 3), 3).is_none());
+}
+
+#[test]
+fn reflow_keeps_caret_on_its_word() {
+    let text = "one two three four";
+    let edit = reflow_paragraphs_edit(text, (14, 14), 9).unwrap();
+    let out = apply(text, &edit);
+    assert_eq!(out, "one two\nthree\nfour");
+    assert_eq!(&out[edit.caret..], "four");
+}
+
+#[test]
+fn unwrap_joins_lines_and_prefixes() {
+    let text = "> one\n> two\n> three\n\nnext\npara";
+    let edit = unwrap_paragraphs_edit(text, (0, text.len())).unwrap();
+    assert_eq!(apply(text, &edit), "> one two three\n\nnext para");
+    assert!(unwrap_paragraphs_edit("single line", (0, 0)).is_none());
+}
+
+#[test]
+fn unwrap_joins_link_split_across_quoted_lines() {
+    let text = "> see [a\n> b](u) end";
+    let edit = unwrap_paragraphs_edit(text, (3, 3)).unwrap();
+    assert_eq!(apply(text, &edit), "> see [a b](u) end");
+}
+
+#[test]
+fn rewrap_from_column_zero_of_a_marker_line() {
+    let text = "> alpha beta gamma\n";
+    assert_eq!(reflow(text, 0, 12), "> alpha beta\n> gamma\n");
+    let text = "- one\n  two\n";
+    let edit = unwrap_paragraphs_edit(text, (0, 0)).unwrap();
+    assert_eq!(apply(text, &edit), "- one two\n");
+}
+
+#[test]
+fn rewrap_paragraphs_ending_in_non_ascii() {
+    assert!(reflow_paragraphs_edit("Café", (0, 0), 80).is_none());
+    assert_eq!(reflow("Some text 🎉\n", 0, 9), "Some text\n🎉\n");
+    assert!(unwrap_paragraphs_edit("é", (0, 0)).is_none());
+    let text = "un\ndeux é";
+    let edit = unwrap_paragraphs_edit(text, (0, 0)).unwrap();
+    assert_eq!(apply(text, &edit), "un deux é");
+}
+
+// ── auto-pairing ─────────────────────────────────────────────────────────
+
+/// Type `c` into `text`, where `|` marks the caret or `|…|` the selection.
+/// Returns the result with the new selection marked the same way, or
+/// `None` for a plain insert.
+fn typed(text: &str, c: char) -> Option<String> {
+    let start = text.find('|').unwrap();
+    let text = text.replacen('|', "", 1);
+    let end = text.find('|').unwrap_or(start);
+    let text = text.replacen('|', "", 1);
+    let edit = auto_pair_edit(&text, &mdit::markdown::parser::parse(&text), (start, end), c)?;
+    let mut out = text.clone();
+    out.replace_range(edit.range.0..edit.range.1, &edit.replacement);
+    let (s, e) = edit.selection;
+    out.insert(e, '|');
+    if s != e {
+        out.insert(s, '|');
+    }
+    Some(out)
+}
+
+fn backspaced(text: &str) -> Option<String> {
+    let caret = text.find('|').unwrap();
+    let text = text.replacen('|', "", 1);
+    let edit = pair_backspace_edit(&text, &mdit::markdown::parser::parse(&text), caret)?;
+    let mut out = apply(&text, &edit);
+    out.insert(edit.caret, '|');
+    Some(out)
+}
+
+#[test]
+fn openers_insert_their_closer() {
+    assert_eq!(typed("see |", '(').as_deref(), Some("see (|)"));
+    assert_eq!(typed("|", '[').as_deref(), Some("[|]"));
+    assert_eq!(typed("say |.", '"').as_deref(), Some("say \"|\"."));
+    assert_eq!(typed("a |", '`').as_deref(), Some("a `|`"));
+    assert_eq!(typed("a |", '$').as_deref(), Some("a $|$"));
+    assert_eq!(typed("a |", '*').as_deref(), Some("a *|*"));
+}
+
+#[test]
+fn no_pair_before_a_word_or_after_one() {
+    assert_eq!(typed("|word", '('), None);
+    assert_eq!(typed("don|", '"'), None);
+    assert_eq!(typed("costs 5|", '$'), None);
+    assert_eq!(typed("a|", '*'), None);
+}
+
+#[test]
+fn star_at_line_start_is_a_bullet() {
+    assert_eq!(typed("|", '*'), None);
+    assert_eq!(typed("  |", '*'), None);
+}
+
+#[test]
+fn backticks_can_build_a_fence() {
+    let one = typed("|", '`').unwrap();
+    assert_eq!(one, "`|`");
+    let two = typed(&one, '`').unwrap();
+    assert_eq!(two, "``|");
+    assert_eq!(typed(&two, '`'), None);
+}
+
+#[test]
+fn closers_are_stepped_over() {
+    assert_eq!(typed("(a|)", ')').as_deref(), Some("(a)|"));
+    assert_eq!(typed("[x|]", ']').as_deref(), Some("[x]|"));
+    assert_eq!(typed("\"hi|\"", '"').as_deref(), Some("\"hi\"|"));
+    assert_eq!(typed("**bold|**", '*').as_deref(), Some("**bold*|*"));
+    assert_eq!(typed("**bold*|*", '*').as_deref(), Some("**bold**|"));
+}
+
+#[test]
+fn empty_marker_pair_grows() {
+    assert_eq!(typed("a *|*", '*').as_deref(), Some("a **|**"));
+    assert_eq!(typed("$|$", '$').as_deref(), Some("$$|$$"));
+}
+
+#[test]
+fn selection_is_wrapped_and_stays_selected() {
+    assert_eq!(typed("a |word| b", '*').as_deref(), Some("a *|word|* b"));
+    assert_eq!(typed("a *|word|* b", '*').as_deref(), Some("a **|word|** b"));
+    assert_eq!(typed("|x|", '(').as_deref(), Some("(|x|)"));
+    assert_eq!(typed("|x|", '=').as_deref(), Some("=|x|="));
+    assert_eq!(typed("|a\nb|", '*'), None);
+    assert_eq!(typed("|x|", 'q'), None);
+}
+
+#[test]
+fn code_suppresses_pairing() {
+    assert_eq!(typed("`a|b`", '('), None);
+    assert_eq!(typed("```\nf|\n```\n", '('), None);
+    assert_eq!(typed("```\nf(|)\n```\n", ')'), None);
+    assert_eq!(typed("`a|b`", '*'), None);
+    assert_eq!(typed("`|ab`", '*'), None);
+}
+
+#[test]
+fn code_and_math_delimiters_are_stepped_over() {
+    assert_eq!(typed("`ab|`", '`').as_deref(), Some("`ab`|"));
+    assert_eq!(typed("$x|$", '$').as_deref(), Some("$x$|"));
+}
+
+#[test]
+fn backspace_deletes_empty_pair() {
+    assert_eq!(backspaced("f(|)").as_deref(), Some("f|"));
+    assert_eq!(backspaced("a \"|\"").as_deref(), Some("a |"));
+    assert_eq!(backspaced("**|**").as_deref(), Some("*|*"));
+    assert_eq!(backspaced("(a|)"), None);
+    assert_eq!(backspaced("\"a\"|\"b\""), None);
+    assert_eq!(backspaced("```\nf(|)\n```\n"), None);
+}
@@ -0,0 +1,187 @@
+use mdit::editor::html_to_markdown::{html_paste_markdown, html_to_markdown};
+
+fn fixture(name: &str) -> (String, String) {
+    let dir = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/html/");
+    let html = std::fs::read_to_string(format!("{dir}{name}.html")).unwrap();
+    let md = std::fs::read_to_string(format!("{dir}{name}.md")).unwrap_or_default();
+    (html, md.trim_end().to_string())
+}
+
+// ── fixtures ────────────────────────────────────────────────────────────
+
+#[test]
+fn article_fixture() {
+    let (html, md) = fixture("article");
+    assert_eq!(html_to_markdown(&html), md);
+}
+
+#[test]
+fn google_docs_fixture() {
+    let (html, md) = fixture("google_docs");
+    assert_eq!(html_to_markdown(&html), md);
+}
+
+#[test]
+fn confluence_fixture() {
+    let (html, md) = fixture("confluence");
+    assert_eq!(html_to_markdown(&html), md);
+}
+
+// ── blocks ──────────────────────────────────────────────────────────────
+
+#[test]
+fn headings_and_paragraphs() {
+    assert_eq!(
+        html_to_markdown("<h3>Title</h3><p>One</p><p>Two</p>"),
+        "### Title\n\nOne\n\nTwo"
+    );
+}
+
+#[test]
+fn unclosed_paragraphs_and_items() {
+    assert_eq!(html_to_markdown("<p>One<p>Two<ul><li>a<li>b</ul>"), "One\n\nTwo\n\n- a\n- b");
+}
+
+#[test]
+fn list_item_with_two_paragraphs_is_indented() {
+    assert_eq!(
+        html_to_markdown("<ol><li><p>First</p><p>More</p></li></ol>"),
+        "1. First\n\n   More"
+    );
+}
+
+#[test]
+fn nested_list_as_sibling_of_item() {
+    assert_eq!(
+        html_to_markdown("<ul><li>a</li><ul><li>b</li></ul></ul>"),
+        "- a\n  - b"
+    );
+}
+
+#[test]
+fn blockquote_prefixes_every_line() {
+    assert_eq!(
+        html_to_markdown("<blockquote><p>a</p><p>b</p></blockquote>"),
+        "> a\n>\n> b"
+    );
+}
+
+#[test]
+fn pre_uses_longer_fence_than_content() {
+    assert_eq!(
+        html_to_markdown("<pre>```\nx\n```</pre>"),
+        "````\n```\nx\n```\n````"
+    );
+}
+
+#[test]
+fn table_alignment_from_style() {
+    let md = html_to_markdown(
+        "<table><tr><th style='text-align:right'>n</th></tr><tr><td>10</td></tr></table>",
+    );
+    assert_eq!(md, "|   n |\n| --: |\n|  10 |");
+}
+
+#[test]
+fn br_is_a_hard_break() {
+    assert_eq!(html_to_markdown("<p>a<br>b</p>"), "a\\\nb");
+}
+
+#[test]
+fn double_br_splits_paragraphs() {
+    assert_eq!(html_to_markdown("a<br><br>b"), "a\n\nb");
+}
+
+// ── inline ──────────────────────────────────────────────────────────────
+
+#[test]
+fn emphasis_keeps_spaces_outside_markers() {
+    assert_eq!(html_to_markdown("a<b> bold </b>b"), "a **bold** b");
+}
+
+#[test]
+fn nested_bold_is_not_doubled() {
+    assert_eq!(html_to_markdown("<b>a <strong>b</strong></b>"), "**a b**");
+}
+
+#[test]
+fn styled_spans_become_emphasis() {
+    assert_eq!(
+        html_to_markdown("<span style='font-weight: bold'>x</span> <span style='font-style:italic'>y</span>"),
+        "**x** _y_"
+    );
+}
+
+#[test]
+fn code_span_around_backticks() {
+    assert_eq!(html_to_markdown("<code>a`b</code>"), "``a`b``");
+}
+
+#[test]
+fn markdown_characters_are_escaped() {
+    assert_eq!(
+        html_to_markdown("<p># not *a* heading [x] a_b _c_</p>"),
+        "\\# not \\*a\\* heading \\[x\\] a_b \\_c\\_"
+    );
+}
+
+#[test]
+fn entities_are_decoded() {
+    assert_eq!(html_to_markdown("a &lt;b&gt; &#233;&#x41; &bogus;"), "a \\<b> éA &bogus;");
+}
+
+#[test]
+fn link_whose_text_is_the_url_becomes_autolink() {
+    assert_eq!(
+        html_to_markdown("<a href='https://a.io'>https://a.io</a>"),
+        "<https://a.io>"
+    );
+}
+
+#[test]
+fn link_with_spaces_in_url_is_bracketed() {
+    assert_eq!(html_to_markdown("<a href='a b.md'>x</a>"), "[x](<a b.md>)");
+}
+
+#[test]
+fn anchor_and_script_links_keep_only_text() {
+    assert_eq!(
+        html_to_markdown("<a href='#s'>one</a> <a href='javascript:go()'>two</a>"),
+        "one two"
+    );
+}
+
+#[test]
+fn data_uri_image_keeps_alt_text() {
+    assert_eq!(html_to_markdown("<img src='data:image/png;base64,AAA' alt='logo'>"), "logo");
+}
+
+#[test]
+fn scripts_and_styles_are_dropped() {
+    assert_eq!(
+        html_to_markdown("<style>p { x: 1 }</style><script>if (a < b) {}</script><p>ok</p>"),
+        "ok"
+    );
+}
+
+// ── html_paste_markdown ─────────────────────────────────────────────────
+
+#[test]
+fn paste_prefers_plain_text_when_html_adds_nothing() {
+    let (html, _) = fixture("code_editor");
+    let plain = "fn main() {\n    x = a_b * 2;\n}";
+    assert_eq!(html_paste_markdown(&html, Some(plain)), None);
+}
+
+#[test]
+fn paste_uses_markdown_when_html_has_structure() {
+    assert_eq!(
+        html_paste_markdown("<p>see <a href='https://a.io'>docs</a></p>", Some("see docs")),
+        Some("see [docs](https://a.io)".to_string())
+    );
+}
+
+#[test]
+fn paste_of_empty_html_falls_back() {
+    assert_eq!(html_paste_markdown("<meta charset='utf-8'>", Some("")), None);
+}
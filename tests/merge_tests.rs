@@ -0,0 +1,64 @@
+use mdit::editor::merge::{merge3, Merge};
+
+fn clean(text: &str) -> Merge {
+    Merge { text: text.to_string(), conflicts: 0 }
+}
+
+const BASE: &str = "# Plan\n\none\ntwo\nthree\nfour\nfive\n";
+
+// ── clean merges ────────────────────────────────────────────────────────
+
+#[test]
+fn one_sided_changes_are_taken() {
+    let changed = "# Plan\n\none\n2\nthree\nfour\nfive\n";
+    assert_eq!(merge3(BASE, changed, BASE), clean(changed));
+    assert_eq!(merge3(BASE, BASE, changed), clean(changed));
+    assert_eq!(merge3(BASE, BASE, BASE), clean(BASE));
+}
+
+#[test]
+fn separate_changes_combine() {
+    let mine = "# Plan\n\nONE\ntwo\nthree\nfour\nfive\n";
+    let theirs = "# Plan\n\none\ntwo\nthree\nfour\nfive\nsix\n";
+    assert_eq!(merge3(BASE, mine, theirs), clean("# Plan\n\nONE\ntwo\nthree\nfour\nfive\nsix\n"));
+}
+
+#[test]
+fn identical_changes_merge_once() {
+    let both = "# Plan\n\none\ntwo\n3\nfour\nfive\n";
+    assert_eq!(merge3(BASE, both, both), clean(both));
+}
+
+// ── conflicts ───────────────────────────────────────────────────────────
+
+#[test]
+fn overlapping_changes_are_marked() {
+    let mine = "# Plan\n\none\ntwo\nTHREE\nfour\nfive\n";
+    let theirs = "# Plan\n\none\ntwo\n3\nfour\nfive\n";
+    assert_eq!(
+        merge3(BASE, mine, theirs),
+        Merge {
+            text: "# Plan\n\none\ntwo\n<<<<<<< mine\nTHREE\n=======\n3\n>>>>>>> on disk\nfour\nfive\n"
+                .to_string(),
+            conflicts: 1,
+        }
+    );
+}
+
+#[test]
+fn deletion_against_edit_conflicts() {
+    let mine = "# Plan\n\none\nfive\n";
+    let theirs = "# Plan\n\none\ntwo\nthree!\nfour\nfive\n";
+    let merged = merge3(BASE, mine, theirs);
+    assert_eq!(merged.conflicts, 1);
+    assert_eq!(
+        merged.text,
+        "# Plan\n\none\n<<<<<<< mine\n=======\ntwo\nthree!\nfour\n>>>>>>> on disk\nfive\n"
+    );
+}
+
+#[test]
+fn markers_stay_on_their_own_lines_at_end_of_file() {
+    let merged = merge3("a\nb", "a\nmine", "a\ntheirs");
+    assert_eq!(merged.text, "a\n<<<<<<< mine\nmine\n=======\ntheirs\n>>>>>>> on disk\n");
+}
@@ -70,6 +70,26 @@ fn parses_link() {
     );
 }
 
+#[test]
+fn bare_url_is_autolinked() {
+    let source = "see https://example.com and me@x.org";
+    let nodes = parse(source);
+    let links: Vec<_> = flatten(&nodes)
+        .into_iter()
+        .filter_map(|n| match &n.kind {
+            NodeKind::Link { url } => Some((url.clone(), &source[n.source_range.0..n.source_range.1])),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(
+        links,
+        vec![
+            ("https://example.com".to_string(), "https://example.com"),
+            ("mailto:me@x.org".to_string(), "me@x.org"),
+        ]
+    );
+}
+
 #[test]
 fn bold_source_range_is_correct() {
     let source = "hello **world** end";
@@ -0,0 +1,63 @@
+use std::path::PathBuf;
+
+use mdit::editor::recovery::{describe_changes, load_all, snapshot_path, Snapshot};
+use mdit::editor::session::write_atomic;
+
+fn snapshot(original: Option<&str>, saved_at: u64, text: &str) -> Snapshot {
+    Snapshot { original: original.map(PathBuf::from), saved_at, text: text.to_string() }
+}
+
+// ── format ──────────────────────────────────────────────────────────────
+
+#[test]
+fn round_trips_titled_and_untitled() {
+    for s in [
+        snapshot(Some("/notes/meeting notes.md"), 1_760_000_000, "# Notes\n\n- one\n"),
+        snapshot(None, 42, "\n\nstarts with blank lines"),
+        snapshot(None, 7, ""),
+    ] {
+        assert_eq!(Snapshot::parse(&s.serialize()), Some(s));
+    }
+}
+
+#[test]
+fn rejects_foreign_and_truncated_files() {
+    assert_eq!(Snapshot::parse("# just markdown\n"), None);
+    assert_eq!(Snapshot::parse("mdit-recovery 1\nsaved 5\n"), None);
+}
+
+#[test]
+fn title_is_the_file_name() {
+    assert_eq!(snapshot(Some("/notes/todo.md"), 0, "").title(), "todo.md");
+    assert_eq!(snapshot(None, 0, "").title(), "Untitled");
+}
+
+// ── directory ───────────────────────────────────────────────────────────
+
+#[test]
+fn load_all_lists_snapshots_newest_first() {
+    let dir = tempfile::tempdir().unwrap();
+    let old = snapshot(None, 100, "old");
+    let new = snapshot(Some("/a.md"), 200, "new");
+    write_atomic(&snapshot_path(dir.path(), "one"), &old.serialize()).unwrap();
+    write_atomic(&snapshot_path(dir.path(), "two"), &new.serialize()).unwrap();
+    std::fs::write(dir.path().join("notes.txt"), "ignored").unwrap();
+    std::fs::write(snapshot_path(dir.path(), "junk"), "not a snapshot").unwrap();
+
+    let found = load_all(dir.path());
+    assert_eq!(found.len(), 2);
+    assert_eq!(found[0], (snapshot_path(dir.path(), "two"), new));
+    assert_eq!(found[1].1, old);
+    assert!(load_all(&dir.path().join("missing")).is_empty());
+}
+
+// ── prompt ──────────────────────────────────────────────────────────────
+
+#[test]
+fn describes_changes_against_the_file_on_disk() {
+    let s = snapshot(Some("/a.md"), 0, "one\ntwo\nthree\n");
+    assert_eq!(describe_changes(&s, Some("one\n2\nthree\n")), "  one\n- 2\n+ two\n  three\n");
+    assert_eq!(describe_changes(&s, Some("one\ntwo\nthree\n")), "Same as the file on disk.\n");
+    assert_eq!(describe_changes(&s, None), "The file is no longer on disk; 3 lines recovered.\n");
+    assert_eq!(describe_changes(&snapshot(None, 0, "x"), None), "Untitled document, 1 line.\n");
+}
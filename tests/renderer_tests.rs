@@ -37,6 +37,19 @@ fn syntax_markers_visible_when_cursor_inside() {
     assert_eq!(hidden, 0, "** markers should be visible when cursor is inside");
 }
 
+#[test]
+fn bare_url_is_clickable() {
+    let text = "go to https://example.com now";
+    let spans = parse(text);
+    let runs = compute_attribute_runs(text, &spans, None, 16.0).runs;
+    let link = runs
+        .iter()
+        .find(|r| r.attrs.contains(&TextAttribute::Link("https://example.com".into())))
+        .expect("expected a Link attribute run");
+    assert_eq!(link.range, (6, 25));
+    assert!(!link.attrs.contains(&TextAttribute::Hidden));
+}
+
 #[test]
 fn italic_span_gets_italic_attribute() {
     let text = "*italic*";
@@ -0,0 +1,258 @@
+use mdit::editor::renderer::compute_attribute_runs;
+use mdit::editor::search::{
+    match_case, remember, step_across_tabs, SearchOptions, SearchScope, Searcher, VisibleText,
+};
+use mdit::markdown::parser::parse;
+
+fn options(case_sensitive: bool, whole_word: bool, regex: bool) -> SearchOptions {
+    SearchOptions { case_sensitive, whole_word, regex, ..SearchOptions::default() }
+}
+
+/// The matched substrings.
+fn found<'a>(text: &'a str, query: &str, opts: SearchOptions) -> Vec<&'a str> {
+    let searcher = Searcher::new(query, opts).unwrap();
+    searcher.find_all(text, &parse(text)).iter().map(|m| &text[m.range.0..m.range.1]).collect()
+}
+
+fn replaced(text: &str, query: &str, opts: SearchOptions, template: &str) -> String {
+    let searcher = Searcher::new(query, opts).unwrap();
+    match searcher.replace_all_edit(text, &[], template) {
+        Some(edit) => format!("{}{}{}", &text[..edit.range.0], edit.replacement, &text[edit.range.1..]),
+        None => text.to_string(),
+    }
+}
+
+// ── matching ────────────────────────────────────────────────────────────
+
+#[test]
+fn literal_ignores_case_by_default() {
+    let text = "Foo foo FOO";
+    assert_eq!(found(text, "foo", SearchOptions::default()), ["Foo", "foo", "FOO"]);
+    assert_eq!(found(text, "foo", options(true, false, false)), ["foo"]);
+}
+
+#[test]
+fn literal_treats_metacharacters_literally() {
+    assert_eq!(found("a.b axb (a.b)", "a.b", SearchOptions::default()), ["a.b", "a.b"]);
+    assert_eq!(found("[x] [ ]", "[ ]", SearchOptions::default()), ["[ ]"]);
+}
+
+#[test]
+fn whole_word_needs_word_boundaries() {
+    let text = "cat concat cat_x cat. (cat)";
+    assert_eq!(found(text, "cat", options(false, true, false)).len(), 3);
+    // Boundaries apply to the match, not to the query's own characters.
+    assert_eq!(found("a-b a-bc", "a-b", options(false, true, false)), ["a-b"]);
+}
+
+#[test]
+fn regex_mode_with_line_anchors() {
+    let text = "# One\ntext\n## Two\n";
+    assert_eq!(found(text, r"^#+ \w+", options(false, false, true)), ["# One", "## Two"]);
+    assert_eq!(found(text, r"\d+|^$", options(false, false, true)), Vec::<&str>::new());
+}
+
+#[test]
+fn whole_word_combines_with_regex() {
+    assert_eq!(found("item items item2 item", r"item\d?", options(false, true, true)), ["item", "item2", "item"]);
+}
+
+#[test]
+fn utf16_ranges_account_for_wide_characters() {
+    let text = "é 😀 x é";
+    let matches = Searcher::new("é", SearchOptions::default()).unwrap().find_all(text, &[]);
+    assert_eq!(matches[0].utf16, (0, 1));
+    assert_eq!(matches[1].utf16, (7, 1));
+    let emoji = Searcher::new("😀", SearchOptions::default()).unwrap().find_all(text, &[]);
+    assert_eq!(emoji[0].range, (3, 7));
+    assert_eq!(emoji[0].utf16, (2, 2));
+}
+
+#[test]
+fn invalid_regex_reports_a_short_message() {
+    let err = Searcher::new("(unclosed", options(false, false, true)).unwrap_err();
+    assert_eq!(err, "unclosed group");
+    assert!(Searcher::new("(unclosed", SearchOptions::default()).is_ok());
+}
+
+// ── replacement ─────────────────────────────────────────────────────────
+
+#[test]
+fn regex_replacement_expands_groups() {
+    let opts = options(false, false, true);
+    assert_eq!(replaced("[a](x) [b](y)", r"\[(\w)\]\((\w)\)", opts, "<$2|$1>"), "<x|a> <y|b>");
+    assert_eq!(replaced("2026-10-18", r"(?P<y>\d+)-(\d+)", opts, "${2}/${y} $$"), "10/2026 $-18");
+}
+
+#[test]
+fn literal_replacement_is_verbatim() {
+    assert_eq!(replaced("a.b a.b", "a.b", SearchOptions::default(), "$1"), "$1 $1");
+}
+
+#[test]
+fn replacement_of_a_single_match_sees_its_context() {
+    let searcher = Searcher::new(r"\bx(\d)", options(false, false, true)).unwrap();
+    let text = "ax1 x2 x3";
+    let matches = searcher.find_all(text, &[]);
+    assert_eq!(matches.len(), 2);
+    assert_eq!(searcher.replacement(text, &matches[1], "y$1"), "y3");
+    let edit = searcher.replace_all_edit(text, &[], "[$0]").unwrap();
+    assert_eq!((edit.range, edit.replacement.as_str()), ((4, 9), "[x2] [x3]"));
+}
+
+#[test]
+fn preserve_case_follows_each_match() {
+    let opts = SearchOptions { preserve_case: true, ..SearchOptions::default() };
+    assert_eq!(replaced("widget Widget WIDGET", "widget", opts, "gadget"), "gadget Gadget GADGET");
+    // Capture groups expand first, then take the match's case.
+    let opts = SearchOptions { regex: true, preserve_case: true, ..SearchOptions::default() };
+    assert_eq!(replaced("Old-style OLD-STYLE", r"old-(\w+)", opts, "new-$1"), "New-style NEW-STYLE");
+}
+
+#[test]
+fn match_case_rules() {
+    assert_eq!(match_case("widget", "macOS"), "macOS");
+    assert_eq!(match_case("Widget", "élan vital"), "Élan vital");
+    assert_eq!(match_case("W", "gadget"), "Gadget");
+    assert_eq!(match_case("WidGet", "gadget"), "gadget");
+    assert_eq!(match_case("2024", "gadget"), "gadget");
+    assert_eq!(match_case("FOO_1", "bar_2"), "BAR_2");
+}
+
+// ── history ─────────────────────────────────────────────────────────────
+
+#[test]
+fn remember_moves_repeats_to_the_front_and_caps_the_list() {
+    let mut history = Vec::new();
+    for entry in ["a", "b", "", "c", "a"] {
+        remember(&mut history, entry, 3);
+    }
+    assert_eq!(history, ["a", "c", "b"]);
+    remember(&mut history, "d", 3);
+    assert_eq!(history, ["d", "a", "c"]);
+}
+
+// ── scopes ──────────────────────────────────────────────────────────────
+
+const DOC: &str = "# Setup api\n\nCall the api via [the **api** docs](https://api.io/api).\n\n```rust\napi();\n```\n\nSee `api` or <https://api.dev>, ![api](api.png).\n";
+
+fn in_scope(scope: SearchScope) -> Vec<usize> {
+    let opts = SearchOptions { scope, ..SearchOptions::default() };
+    let searcher = Searcher::new("api", opts).unwrap();
+    searcher.find_all(DOC, &parse(DOC)).iter().map(|m| m.range.0).collect()
+}
+
+/// Byte offsets of every "api" in DOC, in order.
+fn all_offsets() -> Vec<usize> {
+    DOC.match_indices("api").map(|(i, _)| i).collect()
+}
+
+#[test]
+fn scope_all_finds_everything() {
+    assert_eq!(in_scope(SearchScope::All), all_offsets());
+    assert_eq!(all_offsets().len(), 10);
+}
+
+#[test]
+fn scope_headings() {
+    assert_eq!(in_scope(SearchScope::Headings), [all_offsets()[0]]);
+}
+
+#[test]
+fn scope_code_covers_spans_and_blocks() {
+    let all = all_offsets();
+    assert_eq!(in_scope(SearchScope::Code), [all[5], all[6]]);
+}
+
+#[test]
+fn scope_link_text_and_urls() {
+    let all = all_offsets();
+    // Label (nested in bold), autolink, image alt.
+    assert_eq!(in_scope(SearchScope::LinkText), [all[2], all[7], all[8]]);
+    // Destination host and path, autolink, image source.
+    assert_eq!(in_scope(SearchScope::LinkUrls), [all[3], all[4], all[7], all[9]]);
+}
+
+#[test]
+fn scope_prose_skips_code_and_urls() {
+    let all = all_offsets();
+    assert_eq!(in_scope(SearchScope::Prose), [all[0], all[1], all[2], all[8]]);
+}
+
+// ── viewer projection ───────────────────────────────────────────────────
+
+fn visible(text: &str) -> VisibleText {
+    VisibleText::new(text, &compute_attribute_runs(text, &parse(text), None, 16.0).runs)
+}
+
+/// Source substrings of viewer-mode matches.
+fn found_visible<'a>(text: &'a str, query: &str) -> Vec<&'a str> {
+    let searcher = Searcher::new(query, SearchOptions::default()).unwrap();
+    let matches = searcher.find_visible(text, &parse(text), &visible(text));
+    matches.iter().map(|m| &text[m.range.0..m.range.1]).collect()
+}
+
+#[test]
+fn projection_drops_hidden_markers() {
+    assert_eq!(visible("**bold** and `code`\n").as_str(), "bold and code\n");
+    assert_eq!(visible("[docs](https://x.io)\n").as_str(), "docs\n");
+}
+
+#[test]
+fn viewer_match_spans_hidden_markers() {
+    assert_eq!(found_visible("**bold** text\n", "bold text"), ["bold** text"]);
+    assert_eq!(found_visible("a *b* c\n", "a b c"), ["a *b* c"]);
+}
+
+#[test]
+fn viewer_skips_hidden_urls_and_markers() {
+    let text = "see [docs](https://docs.io) and **docs**\n";
+    assert_eq!(found_visible(text, "docs"), ["docs", "docs"]);
+    assert_eq!(found_visible(text, "https"), Vec::<&str>::new());
+    assert_eq!(found_visible(text, "*"), Vec::<&str>::new());
+}
+
+#[test]
+fn viewer_ranges_map_to_source_utf16() {
+    let text = "**é** é\n";
+    let searcher = Searcher::new("é é", SearchOptions::default()).unwrap();
+    let matches = searcher.find_visible(text, &parse(text), &visible(text));
+    assert_eq!(matches[0].range, (2, 9));
+    assert_eq!(matches[0].utf16, (2, 5));
+}
+
+// ── all tabs ────────────────────────────────────────────────────────────
+
+#[test]
+fn replace_all_edit_is_one_edit_from_first_to_last_match() {
+    let searcher = Searcher::new("cat", SearchOptions::default()).unwrap();
+    let text = "a cat, a Cat.";
+    let edit = searcher.replace_all_edit(text, &[], "dog").unwrap();
+    assert_eq!(edit.range, (2, 12));
+    assert_eq!(edit.replacement, "dog, a dog");
+    assert_eq!(edit.caret, 12);
+    assert_eq!(searcher.replace_all_edit("none", &[], "dog"), None);
+}
+
+#[test]
+fn step_moves_within_then_across_tabs() {
+    let counts = [2, 0, 1];
+    assert_eq!(step_across_tabs(&counts, (0, 0), true), Some((0, 1)));
+    assert_eq!(step_across_tabs(&counts, (0, 1), true), Some((2, 0)));
+    assert_eq!(step_across_tabs(&counts, (2, 0), true), Some((0, 0)));
+    assert_eq!(step_across_tabs(&counts, (1, 0), true), Some((2, 0)));
+}
+
+#[test]
+fn step_backwards_lands_on_last_match() {
+    let counts = [2, 0, 1];
+    assert_eq!(step_across_tabs(&counts, (0, 1), false), Some((0, 0)));
+    assert_eq!(step_across_tabs(&counts, (0, 0), false), Some((2, 0)));
+    assert_eq!(step_across_tabs(&counts, (2, 0), false), Some((0, 1)));
+}
+
+#[test]
+fn step_wraps_within_a_single_tab_and_stops_without_matches() {
+    assert_eq!(step_across_tabs(&[3], (0, 2), true), Some((0, 0)));
+    assert_eq!(step_across_tabs(&[0, 0], (0, 0), true), None);
+}
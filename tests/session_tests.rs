@@ -0,0 +1,87 @@
+use std::path::{Path, PathBuf};
+
+use mdit::editor::session::{write_atomic, Session, TabSession};
+use mdit::editor::view_mode::ViewMode;
+
+fn tab(path: &str, mode: ViewMode, caret: usize, scroll: f64) -> TabSession {
+    TabSession { path: PathBuf::from(path), mode, caret, scroll }
+}
+
+fn sample() -> Session {
+    Session {
+        tabs: vec![
+            tab("/notes/todo.md", ViewMode::Editor, 120, 340.5),
+            tab("/notes/meeting notes.md", ViewMode::Viewer, 0, 0.0),
+            tab("/notes/archive.markdown", ViewMode::Viewer, 9, 12.0),
+        ],
+        active: 1,
+        font_size: 17.0,
+    }
+}
+
+// ── serialization ───────────────────────────────────────────────────────
+
+#[test]
+fn round_trips() {
+    let session = sample();
+    let text = session.serialize();
+    assert!(text.starts_with("mdit-session 1\nfont-size 17\n"));
+    assert!(text.contains("tab viewer 0 0 /notes/meeting notes.md\n"));
+    assert_eq!(Session::parse(&text), Some(session));
+}
+
+#[test]
+fn rejects_files_without_the_header() {
+    assert_eq!(Session::parse(""), None);
+    assert_eq!(Session::parse("font-size 16\nactive 0\n"), None);
+}
+
+#[test]
+fn skips_malformed_lines_and_clamps_active() {
+    let text = "mdit-session 1\nfont-size big\ntab editor 1 2\ntab sideways 0 0 /a.md\n\
+                tab viewer x 0 /b.md\nwindow 0 0 800 600\ntab viewer 3 4 /c.md\nactive 7\n";
+    let session = Session::parse(text).unwrap();
+    assert_eq!(session.tabs, [tab("/c.md", ViewMode::Viewer, 3, 4.0)]);
+    assert_eq!(session.active, 0);
+    assert_eq!(session.font_size, 0.0);
+}
+
+#[test]
+fn paths_with_newlines_are_left_out() {
+    let mut session = sample();
+    session.tabs.insert(0, tab("/odd\nname.md", ViewMode::Viewer, 0, 0.0));
+    session.active = 2;
+    let parsed = Session::parse(&session.serialize()).unwrap();
+    assert_eq!(parsed.tabs.len(), 3);
+    assert_eq!(parsed.tabs[parsed.active].path, Path::new("/notes/meeting notes.md"));
+}
+
+// ── restoring ───────────────────────────────────────────────────────────
+
+#[test]
+fn missing_files_are_dropped_and_active_follows() {
+    let mut session = sample();
+    session.retain_existing(|p| p != Path::new("/notes/todo.md"));
+    assert_eq!(session.tabs.len(), 2);
+    assert_eq!(session.tabs[session.active].path, Path::new("/notes/meeting notes.md"));
+
+    // The active tab itself is gone: the one before it takes over.
+    let mut session = sample();
+    session.retain_existing(|p| p != Path::new("/notes/meeting notes.md"));
+    assert_eq!(session.tabs[session.active].path, Path::new("/notes/todo.md"));
+
+    let mut session = sample();
+    session.retain_existing(|_| false);
+    assert!(session.tabs.is_empty());
+    assert_eq!(session.active, 0);
+}
+
+#[test]
+fn atomic_write_creates_directories_and_replaces() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("mdit/session.txt");
+    write_atomic(&path, "one").unwrap();
+    write_atomic(&path, "two").unwrap();
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "two");
+    assert_eq!(std::fs::read_dir(path.parent().unwrap()).unwrap().count(), 1);
+}
@@ -0,0 +1,167 @@
+use mdit::editor::snippets::{
+    builtin_snippets, expand, parse_user_snippets, slash_query, Expansion, Snippet, SnippetLibrary,
+    SnippetSession,
+};
+
+fn no_vars(_: &str) -> Option<String> {
+    None
+}
+
+fn expanded(body: &str) -> Expansion {
+    expand(body, "", &no_vars)
+}
+
+/// The text of each stop, in visiting order.
+fn stop_texts(e: &Expansion) -> Vec<&str> {
+    e.stops.iter().map(|&(s, end)| &e.text[s..end]).collect()
+}
+
+// ── expansion ───────────────────────────────────────────────────────────
+
+#[test]
+fn placeholders_become_stops_in_order() {
+    let e = expanded("[${2:url}](${1:text})$0!");
+    assert_eq!(e.text, "[url](text)!");
+    assert_eq!(stop_texts(&e), ["text", "url", ""]);
+    assert_eq!(e.stops[2], (11, 11));
+}
+
+#[test]
+fn final_stop_defaults_to_end() {
+    let e = expanded("a $1 b ${2}");
+    assert_eq!(e.text, "a  b ");
+    assert_eq!(e.stops, [(2, 2), (5, 5), (5, 5)]);
+}
+
+#[test]
+fn repeated_stop_reuses_placeholder() {
+    let e = expanded("${1:name} = $1;");
+    assert_eq!(e.text, "name = name;");
+    assert_eq!(e.stops, [(0, 4), (12, 12)]);
+}
+
+#[test]
+fn escapes_and_stray_dollars_are_literal() {
+    let e = expanded(r"cost \$5, ${1:a\}b} $$ $x");
+    assert_eq!(e.text, "cost $5, a}b $$ $x");
+    assert_eq!(stop_texts(&e)[0], "a}b");
+}
+
+#[test]
+fn variables_resolve_or_stay_verbatim() {
+    let vars = |name: &str| (name == "DATE").then(|| "2026-10-18".to_string());
+    let e = expand("on $DATE by ${AUTHOR}", "", &vars);
+    assert_eq!(e.text, "on 2026-10-18 by ${AUTHOR}");
+}
+
+#[test]
+fn continuation_lines_take_the_indent() {
+    let e = expand("```${1:lang}\n$0\n```", "  ", &no_vars);
+    assert_eq!(e.text, "```lang\n  \n  ```");
+    assert_eq!(stop_texts(&e), ["lang", ""]);
+    assert_eq!(e.stops[1], (10, 10));
+}
+
+#[test]
+fn non_ascii_placeholders() {
+    let e = expanded("${1:héllo} ${2:wörld}");
+    assert_eq!(e.text, "héllo wörld");
+    assert_eq!(stop_texts(&e), ["héllo", "wörld", ""]);
+}
+
+#[test]
+fn builtin_table_expands() {
+    let table = builtin_snippets().into_iter().find(|s| s.trigger == "table").unwrap();
+    let e = expanded(&table.body);
+    assert_eq!(e.text, "| Column | Column |\n| --- | --- |\n|  |  |\n");
+    assert_eq!(e.stops.len(), 5);
+}
+
+// ── library ─────────────────────────────────────────────────────────────
+
+#[test]
+fn user_snippet_file_parses() {
+    let src = "# my snippets\n\nsnippet meeting Meeting notes\n## ${1:Topic}\n\n- $0\nendsnippet\n\nsnippet /sig\n-- Me\n";
+    let snippets = parse_user_snippets(src);
+    assert_eq!(
+        snippets,
+        [
+            Snippet {
+                trigger: "meeting".into(),
+                description: "Meeting notes".into(),
+                body: "## ${1:Topic}\n\n- $0".into(),
+            },
+            Snippet { trigger: "sig".into(), description: String::new(), body: "-- Me".into() },
+        ]
+    );
+}
+
+#[test]
+fn user_snippets_override_builtins() {
+    let user = parse_user_snippets("snippet todo\n* [ ] $0\nendsnippet\n");
+    let library = SnippetLibrary::new(user);
+    assert_eq!(library.get("todo").unwrap().body, "* [ ] $0");
+    assert!(library.get("table").is_some());
+}
+
+#[test]
+fn matching_prefers_exact_trigger() {
+    let library = SnippetLibrary::new(parse_user_snippets("snippet t\nx\nendsnippet\n"));
+    let triggers: Vec<&str> = library.matching("T").iter().map(|s| s.trigger.as_str()).collect();
+    assert_eq!(triggers, ["t", "table", "time", "todo"]);
+    assert!(library.matching("zz").is_empty());
+}
+
+#[test]
+fn cached_library_is_reused_while_the_file_is_unchanged() {
+    let first = SnippetLibrary::load_cached();
+    assert!(std::rc::Rc::ptr_eq(&first, &SnippetLibrary::load_cached()));
+    assert!(first.get("table").is_some());
+}
+
+#[test]
+fn slash_query_needs_a_word_boundary() {
+    assert_eq!(slash_query("/tab", 4), Some((0, "tab")));
+    assert_eq!(slash_query("text /", 6), Some((5, "")));
+    assert_eq!(slash_query("- /to", 5), Some((2, "to")));
+    assert_eq!(slash_query("and/or", 6), None);
+    assert_eq!(slash_query("/tab le", 7), None);
+    assert_eq!(slash_query("plain", 5), None);
+}
+
+// ── session ─────────────────────────────────────────────────────────────
+
+#[test]
+fn session_walks_stops_and_tracks_typing() {
+    let e = expanded("[${1:text}](${2:url})$0");
+    let mut session = SnippetSession::start(10, &e).unwrap();
+    assert_eq!(session.current(), (11, 15));
+    // Replace "text" with "hi!".
+    assert!(session.edit((11, 15), 3));
+    assert_eq!(session.current(), (11, 14));
+    assert_eq!(session.advance(false), (16, 19));
+    assert!(!session.is_finished());
+    assert_eq!(session.advance(true), (11, 14));
+    session.advance(false);
+    assert_eq!(session.advance(false), (20, 20));
+    assert!(session.is_finished());
+}
+
+#[test]
+fn session_grows_empty_stop_and_shifts_adjacent_ones() {
+    let e = expanded("$1$2");
+    let mut session = SnippetSession::start(0, &e).unwrap();
+    assert!(session.edit((0, 0), 2));
+    assert_eq!(session.current(), (0, 2));
+    assert_eq!(session.advance(false), (2, 2));
+    assert!(session.edit((2, 2), 1));
+    assert_eq!(session.advance(true), (0, 2));
+}
+
+#[test]
+fn session_ends_on_edit_elsewhere() {
+    let e = expanded("${1:a} ${2:b}");
+    let mut session = SnippetSession::start(5, &e).unwrap();
+    assert!(!session.edit((0, 1), 0));
+    assert_eq!(SnippetSession::start(0, &expanded("- [ ] $0")), None);
+}
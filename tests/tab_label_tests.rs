@@ -1,4 +1,4 @@
-use mdit::ui::tab_bar::{tab_label, path_label};
+use mdit::ui::tab_bar::{tab_label, tab_label_with_matches, path_label};
 use std::path::Path;
 
 #[test]
@@ -28,3 +28,13 @@ fn path_label_with_url() {
         "/Users/witt/notes.md"
     );
 }
+
+#[test]
+fn tab_label_with_match_count() {
+    assert_eq!(tab_label_with_matches("• notes.md", 3), "• notes.md (3)");
+}
+
+#[test]
+fn tab_label_without_matches_is_unchanged() {
+    assert_eq!(tab_label_with_matches("notes.md", 0), "notes.md");
+}
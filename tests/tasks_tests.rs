@@ -0,0 +1,159 @@
+use mdit::editor::formatting::TextEdit;
+use mdit::editor::tasks::{
+    archive_completed_tasks_edit, document_progress, sort_completed_tasks_edit, toggle_tasks_edit,
+    TaskProgress, TaskSummary,
+};
+use mdit::markdown::parser::parse;
+
+fn apply(text: &str, edit: &TextEdit) -> String {
+    let mut out = text.to_string();
+    out.replace_range(edit.range.0..edit.range.1, &edit.replacement);
+    out
+}
+
+/// Toggle with the selection between the first two `|` (or a caret at one).
+fn toggled(text: &str) -> Option<String> {
+    let start = text.find('|').unwrap();
+    let text = text.replacen('|', "", 1);
+    let end = text.find('|').unwrap_or(start);
+    let text = text.replacen('|', "", 1);
+    let edit = toggle_tasks_edit(&text, &parse(&text), (start, end))?;
+    Some(apply(&text, &edit))
+}
+
+fn sorted(text: &str, caret: usize) -> String {
+    match sort_completed_tasks_edit(text, &parse(text), caret) {
+        Some(edit) => apply(text, &edit),
+        None => text.to_string(),
+    }
+}
+
+fn archived(text: &str) -> Option<String> {
+    let edit = archive_completed_tasks_edit(text, &parse(text), 0)?;
+    Some(apply(text, &edit))
+}
+
+const TASKS: &str = "- [ ] a\n- [x] b\n  - [x] c\n  - [ ] d\n- plain\n\ntext\n\n1. [x] one\n";
+
+// ── progress ────────────────────────────────────────────────────────────
+
+#[test]
+fn document_progress_counts_nested_tasks() {
+    assert_eq!(document_progress(&parse(TASKS)), TaskProgress { done: 3, total: 5 });
+    assert_eq!(TaskProgress { done: 3, total: 5 }.label(), "3/5 done");
+}
+
+#[test]
+fn summary_covers_the_list_under_the_caret() {
+    let summary = TaskSummary::new(&parse(TASKS));
+    let d = TASKS.find("d\n").unwrap();
+    assert_eq!(summary.label(d).as_deref(), Some("2/4 in list · 3/5 done"));
+    assert_eq!(summary.label(TASKS.find("text").unwrap()).as_deref(), Some("3/5 done"));
+}
+
+#[test]
+fn summary_shows_list_only_when_it_differs() {
+    let single = "- [x] a\n- [ ] b\n";
+    assert_eq!(TaskSummary::new(&parse(single)).label(0).as_deref(), Some("1/2 done"));
+    assert_eq!(TaskSummary::new(&parse("- a\n")).label(0), None);
+}
+
+#[test]
+fn summary_follows_mapped_offsets() {
+    let text = "é\n\n- [x] a\n- [ ] b\n\ntext\n\n- [ ] c\n";
+    let utf16 = |b: usize| text[..b].encode_utf16().count();
+    let summary = TaskSummary::new(&parse(text)).map_offsets(utf16);
+    let a = utf16(text.find('a').unwrap());
+    assert_eq!(summary.label(a).as_deref(), Some("1/2 in list · 1/3 done"));
+    assert_eq!(summary.label(0).as_deref(), Some("1/3 done"));
+    assert_eq!(TaskSummary::default().label(0), None);
+}
+
+// ── toggle ──────────────────────────────────────────────────────────────
+
+#[test]
+fn toggle_flips_the_caret_line() {
+    assert_eq!(toggled("- [ ] a|b\n").unwrap(), "- [x] ab\n");
+    assert_eq!(toggled("- [X] |ab\n").unwrap(), "- [ ] ab\n");
+    assert_eq!(toggled("1. [ ] a|\n").unwrap(), "1. [x] a\n");
+}
+
+#[test]
+fn toggle_uses_the_innermost_task_on_the_line() {
+    assert_eq!(toggled("- [ ] a\n  - [ ] |b\n").unwrap(), "- [ ] a\n  - [x] b\n");
+}
+
+#[test]
+fn toggle_selection_checks_all_unless_all_done() {
+    assert_eq!(toggled("- [x] |a\n- [ ] b|\n").unwrap(), "- [x] a\n- [x] b\n");
+    assert_eq!(toggled("- [x] |a\n- [x] b|\n").unwrap(), "- [ ] a\n- [ ] b\n");
+}
+
+#[test]
+fn toggle_ignores_non_tasks() {
+    assert_eq!(toggled("- a|\n"), None);
+    assert_eq!(toggled("```\n- [ ] |a\n```\n"), None);
+}
+
+// ── sort ────────────────────────────────────────────────────────────────
+
+#[test]
+fn sort_moves_completed_tasks_down_with_their_children() {
+    let text = "- [x] a\n  - [ ] a1\n- [ ] b\n- [x] c\n- [ ] d\n";
+    assert_eq!(sorted(text, 0), "- [ ] b\n- [ ] d\n- [x] a\n  - [ ] a1\n- [x] c\n");
+}
+
+#[test]
+fn sort_handles_nested_lists_and_keeps_spacing() {
+    let text = "- [ ] a\n  - [x] a1\n  - [ ] a2\n\n- [x] b\n\n- [ ] c\n";
+    assert_eq!(sorted(text, 0), "- [ ] a\n  - [ ] a2\n  - [x] a1\n\n- [ ] c\n\n- [x] b\n");
+}
+
+#[test]
+fn sort_keeps_ordered_numbering() {
+    assert_eq!(sorted("1. [x] a\n2. [ ] b\n3. [ ] c\n", 0), "1. [ ] b\n2. [ ] c\n3. [x] a\n");
+}
+
+#[test]
+fn sort_acts_on_the_caret_list_or_all_lists() {
+    let text = "- [x] a\n- [ ] b\n\ntext\n\n- [x] c\n- [ ] d\n";
+    assert_eq!(sorted(text, 0), "- [ ] b\n- [x] a\n\ntext\n\n- [x] c\n- [ ] d\n");
+    let outside = text.find("text").unwrap();
+    assert_eq!(sorted(text, outside), "- [ ] b\n- [x] a\n\ntext\n\n- [ ] d\n- [x] c\n");
+}
+
+#[test]
+fn sort_leaves_sorted_lists_alone() {
+    assert_eq!(sort_completed_tasks_edit("- [ ] a\n- [x] b\n", &parse("- [ ] a\n- [x] b\n"), 0), None);
+}
+
+// ── archive ─────────────────────────────────────────────────────────────
+
+#[test]
+fn archive_creates_done_section() {
+    let text = "# Todo\n\n- [x] a\n- [ ] b\n  - [x] b1\n- [x] c\n  more c\n";
+    assert_eq!(
+        archived(text).unwrap(),
+        "# Todo\n\n- [ ] b\n\n## Done\n\n- [x] a\n- [x] b1\n- [x] c\n  more c\n"
+    );
+}
+
+#[test]
+fn archive_appends_to_existing_done_list() {
+    let text = "# Todo\n\n- [x] new\n- [ ] open\n\n## done\n\n- [x] old\n\n## Later\n\nx\n";
+    assert_eq!(
+        archived(text).unwrap(),
+        "# Todo\n\n- [ ] open\n\n## done\n\n- [x] old\n- [x] new\n\n## Later\n\nx\n"
+    );
+}
+
+#[test]
+fn archive_removes_emptied_lists() {
+    let text = "intro\n\n- [x] a\n- [x] b\n\noutro\n";
+    assert_eq!(archived(text).unwrap(), "intro\n\noutro\n\n## Done\n\n- [x] a\n- [x] b\n");
+}
+
+#[test]
+fn archive_without_completed_tasks_is_none() {
+    assert_eq!(archived("- [ ] a\n\n## Done\n\n- [x] old\n"), None);
+}